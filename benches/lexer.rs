@@ -0,0 +1,67 @@
+//! Times `Lexer::identify_tokens` on generated programs of a few megabytes
+//! each, to check that lexing stays linear in the size of the source.
+//!
+//! Run it with `cargo bench --bench lexer`.
+
+use std::{
+    fmt::Write,
+    fs,
+    time::{Duration, Instant},
+};
+
+use olive::{compiler::Compiler, lexer::Lexer};
+
+/// Sizes of the generated programs, in functions. Each function is about 170 bytes.
+const SIZES: [usize; 3] = [10_000, 20_000, 40_000];
+
+/// How many times each program is lexed. The fastest run is reported.
+const RUNS: usize = 5;
+
+fn main() {
+    let dir = std::env::temp_dir().join("olive-bench");
+    fs::create_dir_all(&dir).unwrap();
+    for functions in SIZES {
+        let path = dir.join(format!("lexer-{}.glacier", functions));
+        let source = generate(functions);
+        fs::write(&path, &source).unwrap();
+        let compiler = Compiler::new(path.to_str().unwrap(), false, false, false);
+
+        let mut fastest = Duration::MAX;
+        let mut tokens = 0;
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            tokens = Lexer::new(&compiler).identify_tokens().len();
+            fastest = fastest.min(start.elapsed());
+        }
+        assert!(!compiler.reporter.borrow().has_error());
+        let megabytes = source.len() as f64 / (1024.0 * 1024.0);
+        println!(
+            "{:>6} functions {:>6.2} MB {:>8} tokens {:>8.1} ms {:>6.1} MB/s",
+            functions,
+            megabytes,
+            tokens,
+            fastest.as_secs_f64() * 1000.0,
+            megabytes / fastest.as_secs_f64()
+        );
+    }
+}
+
+/// Returns a program of `functions` functions, with non-ASCII identifiers and
+/// strings, and a `main` that calls one of them.
+fn generate(functions: usize) -> String {
+    let mut source = String::new();
+    for i in 0..functions {
+        writeln!(
+            source,
+            "func größe_{i}(wert: int) -> int {{\n    \
+                 var grüß = \"héllo wörld ✓ {i}\";\n    \
+                 println(grüß);\n    \
+                 var summe = wert * 2 + {i} - (wert / 3);\n    \
+                 return summe;\n\
+             }}\n"
+        )
+        .unwrap();
+    }
+    source.push_str("func main() {\n    println(größe_1(2));\n}\n");
+    source
+}
@@ -1,15 +1,15 @@
 #![allow(dead_code)]
 
-use std::fmt::Display;
+use std::{fmt::Display, path::PathBuf};
 
 use crate::{
     printer::{AstPrinter, Visitor},
-    utils::Token,
+    utils::{FileId, Span, Token},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Ast {
-    items: Vec<Item>,
+    pub items: Vec<Item>,
 }
 
 impl Ast {
@@ -44,13 +44,23 @@ impl Ast {
 #[derive(Debug)]
 pub enum Item {
     Func(Function),
+    Const(Constant),
+    Struct(Struct),
+    Class(Class),
+    Enum(Enum),
+    Import(Import),
+    Attribute(Attribute),
     Unknown,
 }
 #[derive(Debug)]
 pub struct Function {
     pub name: Token,
+    /// Whether the function is declared `pub` and can be used from other modules.
+    pub public: bool,
+    /// The `self` receiver of a method.
+    pub self_param: Option<Token>,
     pub params: Vec<Parameter>,
-    pub return_ty: Option<Token>,
+    pub return_ty: Option<TypeExpr>,
     pub body: Block,
 }
 
@@ -72,6 +82,69 @@ pub struct Function {
 // }
 
 #[derive(Debug)]
+pub struct Struct {
+    pub name: Token,
+    pub public: bool,
+    pub fields: Vec<Field>,
+}
+
+/// A class holds fields like a struct, but its instances are shared by
+/// reference and it can declare methods. A method named `init` is the constructor.
+#[derive(Debug)]
+pub struct Class {
+    pub name: Token,
+    pub public: bool,
+    pub fields: Vec<Field>,
+    pub methods: Vec<Function>,
+}
+
+impl Class {
+    pub fn method(&self, name: &str) -> Option<&Function> {
+        self.methods
+            .iter()
+            .find(|method| method.name.as_ident() == name)
+    }
+}
+
+/// An enum whose variants may carry a payload, such as `Circle(float)`.
+#[derive(Debug)]
+pub struct Enum {
+    pub name: Token,
+    pub public: bool,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug)]
+pub struct Variant {
+    pub name: Token,
+    pub fields: Vec<TypeExpr>,
+}
+
+/// `import "path";` or `use foo::bar;`, which load another file as a module.
+/// `use foo::bar` imports the file `foo/bar.glacier`, whose `pub` items are
+/// then available as `foo::bar::name`, or `bar::name` for short.
+#[derive(Debug)]
+pub struct Import {
+    /// The imported file, relative to the directory of the importing file.
+    pub path: PathBuf,
+    /// The path written after `use`, such as `foo::bar`. Imports of a file by
+    /// its path have none.
+    pub use_path: Option<String>,
+    /// The span of the path as written.
+    pub span: Span,
+    /// The file that was loaded for this import, once the module loader has found it.
+    pub module: Option<FileId>,
+}
+
+/// An attribute such as `#allow(unused_variable)`, which applies to the whole
+/// file it is written in.
+#[derive(Debug)]
+pub struct Attribute {
+    pub name: Token,
+    pub args: Vec<Token>,
+}
+
+#[derive(Debug, Default, PartialEq)]
 pub struct Block(pub Vec<Statement>);
 
 impl Block {
@@ -94,23 +167,45 @@ impl Block {
 //     }
 // }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Statement {
     VarDecl {
         name: Token,
-        ty: Option<Token>,
+        mutable: bool,
+        ty: Option<TypeExpr>,
+        expr: Expr,
+    },
+    Const(Constant),
+    Return {
+        keyword: Token,
         expr: Expr,
     },
-    Return(Expr),
     Expression(Expr),
+    If {
+        condition: Expr,
+        then_branch: Block,
+        else_branch: Option<Block>,
+    },
+    While {
+        condition: Expr,
+        body: Block,
+    },
+    For {
+        var: Token,
+        range: Expr,
+        body: Block,
+    },
+    Break(Token),
+    Continue(Token),
     Unknown,
 }
-/* Constant {
-    name: String,
-    ty: String,
-    expr: Expr,
-},
-*/
+#[derive(Debug, PartialEq)]
+pub struct Constant {
+    pub name: Token,
+    pub public: bool,
+    pub ty: Option<TypeExpr>,
+    pub expr: Expr,
+}
 
 #[derive(Debug, PartialEq)]
 pub enum Expr {
@@ -126,10 +221,103 @@ pub enum Expr {
     Literal(Token),
     Var(Token),
     Grouping(Box<Expr>),
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+    },
+    /// An array literal such as `[1, 2, 3]`. The span covers the brackets.
+    Array {
+        elements: Vec<Expr>,
+        span: Span,
+    },
+    /// Indexing into an array, as in `values[i]`.
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+    },
+    /// A path to an item inside another one, such as the variant `Shape::Circle`.
+    Path(Vec<Token>),
+    /// `match scrutinee { pattern => body, ... }`.
+    Match {
+        keyword: Token,
+        scrutinee: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+    /// A struct literal such as `Point { x: 1, y: 2 }`.
+    StructLiteral {
+        name: Token,
+        fields: Vec<FieldInit>,
+    },
+    /// Access to the field `field` of `object`, as in `point.x`.
+    Field {
+        object: Box<Expr>,
+        field: Token,
+    },
+    /// `target = value`, or a compound assignment such as `target += value` when `op` is set.
+    Assign {
+        target: Box<Expr>,
+        op: Option<BinOp>,
+        value: Box<Expr>,
+    },
     None,
     Unknown,
 }
 
+impl Expr {
+    /// Returns the span of source code covered by this expression.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Binary { lhs, rhs, .. } => lhs.span().to(rhs.span()),
+            Expr::Unary { rhs, .. } => rhs.span(),
+            Expr::Literal(token) | Expr::Var(token) => token.span,
+            Expr::Grouping(expr) => expr.span(),
+            Expr::Call { callee, args } => match args.last() {
+                Some(arg) => callee.span().to(arg.span()),
+                None => callee.span(),
+            },
+            Expr::Range { start, end, .. } => start.span().to(end.span()),
+            Expr::Array { span, .. } => *span,
+            Expr::Path(segments) => segments[0].span.to(segments[segments.len() - 1].span),
+            Expr::Match { keyword, .. } => keyword.span,
+            Expr::Index { object, index } => object.span().to(index.span()),
+            Expr::StructLiteral { name, fields } => match fields.last() {
+                Some(last) => name.span.to(last.value.span()),
+                None => name.span,
+            },
+            Expr::Field { object, field } => object.span().to(field.span),
+            Expr::Assign { target, value, .. } => target.span().to(value.span()),
+            Expr::None | Expr::Unknown => Span::default(),
+        }
+    }
+}
+
+impl Statement {
+    /// Returns a span that locates this statement, such as its keyword or its
+    /// condition. Not every statement records the span of its first token.
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::VarDecl { name, .. } => name.span,
+            Statement::Const(constant) => constant.name.span,
+            Statement::Return { keyword, expr } => match expr {
+                Expr::None => keyword.span,
+                expr => keyword.span.to(expr.span()),
+            },
+            Statement::Expression(expr) => expr.span(),
+            Statement::If { condition, .. } | Statement::While { condition, .. } => {
+                condition.span()
+            }
+            Statement::For { var, .. } => var.span,
+            Statement::Break(keyword) | Statement::Continue(keyword) => keyword.span,
+            Statement::Unknown => Span::default(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum BinOp {
     // Main Binary Operations
@@ -145,6 +333,10 @@ pub enum BinOp {
     LTOrEq, // <=
     GT,     // >
     LT,     // <
+
+    // Logical Operations
+    And, // &&
+    Or,  // ||
 }
 
 impl Display for BinOp {
@@ -163,6 +355,8 @@ impl Display for BinOp {
                 BinOp::GT => ">",
                 BinOp::LTOrEq => "<=",
                 BinOp::LT => "<",
+                BinOp::And => "&&",
+                BinOp::Or => "||",
             }
         )
     }
@@ -177,10 +371,53 @@ pub enum UnaryOp {
     Unknown,
 }
 
+impl Display for UnaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                UnaryOp::Negate => "!",
+                UnaryOp::Negative => "-",
+                UnaryOp::Unknown => "<UNKNOWN>",
+            }
+        )
+    }
+}
+
+/// A type as written in the source, such as `int` or `[Point]`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TypeExpr {
+    Named(Token),
+    /// `[element]`. The span covers the brackets.
+    Array {
+        element: Box<TypeExpr>,
+        span: Span,
+    },
+}
+
+impl TypeExpr {
+    pub fn span(&self) -> Span {
+        match self {
+            TypeExpr::Named(name) => name.span,
+            TypeExpr::Array { span, .. } => *span,
+        }
+    }
+}
+
+impl Display for TypeExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeExpr::Named(name) => write!(f, "{}", name),
+            TypeExpr::Array { element, .. } => write!(f, "[{}]", element),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Parameter {
     pub name: Token,
-    pub ty: Token,
+    pub ty: TypeExpr,
 }
 
 impl Display for Parameter {
@@ -188,3 +425,64 @@ impl Display for Parameter {
         write!(f, "{}: {}", self.name, self.ty)
     }
 }
+
+/// A field declared in a struct, such as `x: int`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: Token,
+    pub ty: TypeExpr,
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.ty)
+    }
+}
+
+/// The value given to a field in a struct literal, such as `x: 1`.
+#[derive(Debug, PartialEq)]
+pub struct FieldInit {
+    pub name: Token,
+    pub value: Expr,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: ArmBody,
+}
+
+/// The code run by a match arm: a single expression, or a block whose value is `()`.
+#[derive(Debug, PartialEq)]
+pub enum ArmBody {
+    Expr(Expr),
+    Block(Block),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Pattern {
+    /// `_` matches any value.
+    Wildcard(Token),
+    /// A name matches any value and binds it.
+    Binding(Token),
+    /// A literal, possibly negated, such as `1`, `-2.5`, `'c'` or `"text"`.
+    Literal(Expr),
+    /// An enum variant such as `Shape::Circle(r)`, with patterns for its payload.
+    Variant {
+        path: Vec<Token>,
+        fields: Vec<Pattern>,
+    },
+}
+
+impl Pattern {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(token) | Pattern::Binding(token) => token.span,
+            Pattern::Literal(expr) => expr.span(),
+            Pattern::Variant { path, fields } => match fields.last() {
+                Some(field) => path[0].span.to(field.span()),
+                None => path[0].span.to(path[path.len() - 1].span),
+            },
+        }
+    }
+}
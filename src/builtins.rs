@@ -0,0 +1,35 @@
+use crate::value::Value;
+
+/// Functions provided by the language itself rather than declared in source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    /// `println(args...)` prints its arguments separated by spaces, followed by a newline.
+    Println,
+    /// `len(value)` returns the number of elements in an array or characters in a string.
+    Len,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "println" => Some(Builtin::Println),
+            "len" => Some(Builtin::Len),
+            _ => None,
+        }
+    }
+
+    pub fn call(&self, args: &[Value]) -> Value {
+        match self {
+            Builtin::Println => {
+                let line: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                println!("{}", line.join(" "));
+                Value::Unit
+            }
+            Builtin::Len => match &args[0] {
+                Value::Array(elements) => Value::Int(elements.len() as i64),
+                Value::String(string) => Value::Int(string.chars().count() as i64),
+                _ => unreachable!(),
+            },
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{builtins::Builtin, utils::Span, value::Value};
+
+/// A single instruction of the stack VM. Operands that name a constant, a local
+/// slot, a global or a function are indexes into the matching table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// Pushes a constant from the constant pool.
+    Constant(u32),
+    Unit,
+    True,
+    False,
+    Pop,
+    /// Pushes a copy of the value on top of the stack.
+    Dup,
+
+    GetLocal(u32),
+    /// Pops a value into a local slot.
+    SetLocal(u32),
+    /// Pushes the value of a local slot and leaves `()` in its place, so that it
+    /// can be updated without copying and stored back with `SetLocal`.
+    TakeLocal(u32),
+    GetGlobal(u32),
+    SetGlobal(u32),
+
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    /// Arithmetic negation, `-x`.
+    Negative,
+    /// Logical negation, `!x`.
+    Not,
+
+    /// Jumps to an absolute instruction index.
+    Jump(u32),
+    /// Pops a `bool` and jumps if it is false.
+    JumpIfFalse(u32),
+
+    /// Calls a function from the function table with the arguments on top of the stack.
+    Call {
+        function: u32,
+        args: u32,
+    },
+    /// Calls the method named by a string constant on the object below the arguments.
+    Invoke {
+        method: u32,
+        args: u32,
+    },
+    CallBuiltin {
+        builtin: Builtin,
+        args: u32,
+    },
+    /// Returns the value on top of the stack to the caller.
+    Return,
+
+    /// Pops the given number of elements into a new array.
+    Array(u32),
+    /// Pops an index and an array, and pushes the element.
+    Index,
+    /// Pops an index and pushes the element of the array below it, leaving `()` in its place.
+    TakeIndex,
+    /// Pops an index, a value and an array, and pushes the array with the element replaced.
+    SetIndex,
+
+    /// Pops the fields of a struct in declaration order into a copy of the
+    /// struct constant, which holds the field names.
+    Struct(u32),
+    /// Pops an object and pushes its field named by a string constant.
+    GetField(u32),
+    /// Pushes the named field of the object on top of the stack, leaving `()` in its place.
+    TakeField(u32),
+    /// Pops a value and an object, and pushes the object with the named field replaced.
+    SetField(u32),
+    /// Pushes a new instance of a class, copied from a struct constant with its default fields.
+    New(u32),
+
+    /// Pops the payload of a variant into a copy of the enum constant.
+    Variant(u32),
+    /// Pops an enum value and pushes whether it is the variant named by a string constant.
+    IsVariant(u32),
+    /// Pops an enum value and pushes a field of its payload.
+    VariantField(u32),
+    /// Pops the scrutinee of a `match` that no arm matched and stops with an error.
+    NoMatch,
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::Constant(index) => write!(f, "Constant {}", index),
+            Instruction::GetLocal(slot) => write!(f, "GetLocal {}", slot),
+            Instruction::SetLocal(slot) => write!(f, "SetLocal {}", slot),
+            Instruction::TakeLocal(slot) => write!(f, "TakeLocal {}", slot),
+            Instruction::GetGlobal(slot) => write!(f, "GetGlobal {}", slot),
+            Instruction::SetGlobal(slot) => write!(f, "SetGlobal {}", slot),
+            Instruction::Jump(target) => write!(f, "Jump {:04}", target),
+            Instruction::JumpIfFalse(target) => write!(f, "JumpIfFalse {:04}", target),
+            Instruction::Call { function, args } => write!(f, "Call {} {}", function, args),
+            Instruction::Invoke { method, args } => write!(f, "Invoke {} {}", method, args),
+            Instruction::CallBuiltin { builtin, args } => {
+                write!(f, "CallBuiltin {:?} {}", builtin, args)
+            }
+            Instruction::Array(len) => write!(f, "Array {}", len),
+            Instruction::Struct(index) => write!(f, "Struct {}", index),
+            Instruction::GetField(index) => write!(f, "GetField {}", index),
+            Instruction::TakeField(index) => write!(f, "TakeField {}", index),
+            Instruction::SetField(index) => write!(f, "SetField {}", index),
+            Instruction::New(index) => write!(f, "New {}", index),
+            Instruction::Variant(index) => write!(f, "Variant {}", index),
+            Instruction::IsVariant(index) => write!(f, "IsVariant {}", index),
+            Instruction::VariantField(field) => write!(f, "VariantField {}", field),
+            instruction => write!(f, "{:?}", instruction),
+        }
+    }
+}
+
+/// A compiled function: its code and, for every instruction, the span of the
+/// source it was compiled from.
+#[derive(Debug, Default)]
+pub struct Function {
+    pub name: String,
+    /// The number of arguments, including the receiver of a method.
+    pub arity: u32,
+    /// The number of local slots, including the arguments.
+    pub locals: u32,
+    pub code: Vec<Instruction>,
+    pub spans: Vec<Span>,
+}
+
+impl Function {
+    pub fn new(name: impl Into<String>, arity: u32) -> Self {
+        Self {
+            name: name.into(),
+            arity,
+            ..Default::default()
+        }
+    }
+}
+
+/// A program compiled to bytecode for the stack VM.
+#[derive(Debug, Default)]
+pub struct Program {
+    pub constants: Vec<Value>,
+    pub functions: Vec<Function>,
+    /// The names of the global constants, by slot.
+    pub globals: Vec<String>,
+    /// The methods of every class, by class and method name.
+    pub methods: HashMap<String, HashMap<String, u32>>,
+    /// The function that initializes the global constants.
+    pub init: u32,
+    pub main: Option<u32>,
+}
+
+impl Program {
+    /// Adds `value` to the constant pool, reusing an equal constant if there is one.
+    pub fn add_constant(&mut self, value: Value) -> u32 {
+        // Floats are compared bit for bit, so that `0.0` and `-0.0` stay distinct.
+        let same = |constant: &Value| match (constant, &value) {
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (constant, value) => constant == value,
+        };
+        let index = match self.constants.iter().position(same) {
+            Some(index) => index,
+            None => {
+                self.constants.push(value);
+                self.constants.len() - 1
+            }
+        };
+        index as u32
+    }
+
+    /// Returns a listing of every function, with each instruction next to the
+    /// span it came from and the operands it refers to.
+    pub fn disassemble(&self) -> String {
+        let mut output = String::new();
+        for function in &self.functions {
+            output.push_str(&format!(
+                "== {} (arity {}, locals {}) ==\n",
+                function.name, function.arity, function.locals
+            ));
+            for (offset, (instruction, span)) in
+                function.code.iter().zip(&function.spans).enumerate()
+            {
+                let line = format!("{:04}  {:<12} {}", offset, span.to_string(), instruction);
+                match self.describe(instruction) {
+                    Some(note) => output.push_str(&format!("{:<48} ; {}\n", line, note)),
+                    None => output.push_str(&format!("{}\n", line)),
+                }
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+
+    /// Describes what an operand of `instruction` refers to.
+    fn describe(&self, instruction: &Instruction) -> Option<String> {
+        match instruction {
+            Instruction::Constant(index)
+            | Instruction::Struct(index)
+            | Instruction::New(index)
+            | Instruction::Variant(index)
+            | Instruction::GetField(index)
+            | Instruction::TakeField(index)
+            | Instruction::SetField(index)
+            | Instruction::IsVariant(index)
+            | Instruction::Invoke { method: index, .. } => {
+                Some(self.constants[*index as usize].to_string())
+            }
+            Instruction::GetGlobal(slot) | Instruction::SetGlobal(slot) => {
+                Some(self.globals[*slot as usize].clone())
+            }
+            Instruction::Call { function, .. } => {
+                Some(self.functions[*function as usize].name.clone())
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,1213 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{
+        ArmBody, Ast, BinOp, Block, Class, Enum, Expr, Field, FieldInit, Function, Item, MatchArm,
+        Pattern, Statement, Struct, TypeExpr, UnaryOp,
+    },
+    builtins::Builtin,
+    compiler::Compiler,
+    diag,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    exhaustiveness::missing_patterns,
+    resolver::{Resolutions, SymbolKind},
+    types::{ClassInfo, Signature, Type, TypeInfo},
+    utils::{Span, Token, TokenType as Ty},
+};
+
+/// What owns the storage that an assignment writes to.
+enum Owner<'e> {
+    /// A struct held by the named variable, which must be mutable.
+    Variable(&'e Token),
+    /// A class instance, which can be changed through any reference to it.
+    Object,
+    /// A value that is discarded right after the assignment.
+    Temporary,
+}
+
+/// Checks that every expression, declaration and call in a program is well-typed.
+pub struct TypeChecker<'a> {
+    compiler: &'a Compiler,
+    resolutions: &'a Resolutions,
+    info: TypeInfo,
+    return_ty: Type,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn new(compiler: &'a Compiler, resolutions: &'a Resolutions) -> Self {
+        Self {
+            compiler,
+            resolutions,
+            info: TypeInfo::default(),
+            return_ty: Type::Unit,
+        }
+    }
+
+    pub fn check(mut self, ast: &Ast) -> TypeInfo {
+        // Type names are registered first so that fields and signatures can refer to any of them.
+        for item in &ast.items {
+            match item {
+                Item::Struct(decl) => {
+                    self.info
+                        .set_struct_fields(decl.name.as_ident(), Vec::new());
+                }
+                Item::Class(class) => {
+                    self.info
+                        .set_class(class.name.as_ident(), ClassInfo::default());
+                }
+                Item::Enum(decl) => {
+                    self.info
+                        .set_enum_variants(decl.name.as_ident(), Vec::new());
+                }
+                _ => {}
+            }
+        }
+        for item in &ast.items {
+            match item {
+                Item::Struct(decl) => self.check_struct(decl),
+                Item::Class(class) => self.check_class(class),
+                Item::Enum(decl) => self.check_enum(decl),
+                _ => {}
+            }
+        }
+        for item in &ast.items {
+            match item {
+                Item::Struct(decl) => self.check_struct_size(decl),
+                Item::Enum(decl) => self.check_enum_size(decl),
+                _ => {}
+            }
+        }
+
+        for item in &ast.items {
+            if let Item::Func(function) = item {
+                let signature = self.signature(function);
+                self.info.set_signature(function.name.as_ident(), signature);
+                if function.name.as_ident() == "main" {
+                    self.check_main(function);
+                }
+            }
+        }
+
+        // Constants are checked in declaration order before any function body uses them.
+        for item in &ast.items {
+            if let Item::Const(constant) = item {
+                self.check_declaration(&constant.name, &constant.ty, &constant.expr);
+            }
+        }
+
+        for item in &ast.items {
+            match item {
+                Item::Func(function) => {
+                    let signature = self.info.signature(function.name.as_ident()).cloned();
+                    self.check_function(function, signature.unwrap(), None);
+                }
+                Item::Class(class) => {
+                    let receiver = Type::Class(class.name.as_ident().to_string());
+                    for method in &class.methods {
+                        let signature = self.signature(method);
+                        self.check_function(method, signature, Some(receiver.clone()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.info
+    }
+
+    fn signature(&mut self, function: &Function) -> Signature {
+        Signature {
+            params: function
+                .params
+                .iter()
+                .map(|param| self.resolve_type(&param.ty))
+                .collect(),
+            return_ty: match &function.return_ty {
+                Some(ty) => self.resolve_type(ty),
+                None => Type::Unit,
+            },
+        }
+    }
+
+    fn resolve_type(&mut self, ty: &TypeExpr) -> Type {
+        let ty = match ty {
+            TypeExpr::Named(name) => name,
+            TypeExpr::Array { element, .. } => {
+                return Type::Array(Box::new(self.resolve_type(element)))
+            }
+        };
+        match Type::from_name(ty.as_ident()) {
+            Some(ty) => ty,
+            None if self.info.struct_fields(ty.as_ident()).is_some() => {
+                Type::Struct(ty.as_ident().to_string())
+            }
+            None if self.info.class(ty.as_ident()).is_some() => {
+                Type::Class(ty.as_ident().to_string())
+            }
+            None if self.info.enum_variants(ty.as_ident()).is_some() => {
+                Type::Enum(ty.as_ident().to_string())
+            }
+            None => {
+                self.error(
+                    ErrorCode::UnknownType,
+                    format!("Unknown type `{}`.", ty.ty),
+                    "Expected a primitive type or the name of a struct or class.",
+                    ty.span,
+                );
+                Type::Error
+            }
+        }
+    }
+
+    fn check_struct(&mut self, decl: &Struct) {
+        let fields = self.check_fields(&decl.name, &decl.fields);
+        self.info.set_struct_fields(decl.name.as_ident(), fields);
+    }
+
+    fn check_class(&mut self, class: &Class) {
+        let fields = self.check_fields(&class.name, &class.fields);
+        let mut methods: HashMap<String, Signature> = HashMap::new();
+        for method in &class.methods {
+            let name = method.name.as_ident();
+            if methods.contains_key(name) || fields.iter().any(|(field, _)| field == name) {
+                self.error(
+                    ErrorCode::DuplicateDefinition,
+                    format!("Duplicate definition of `{}`.", method.name.ty),
+                    format!(
+                        "`{}` is already a member of `{}`.",
+                        method.name.ty, class.name.ty
+                    ),
+                    method.name.span,
+                );
+                continue;
+            }
+            if let (Some(return_ty), "init") = (&method.return_ty, name) {
+                self.error(
+                    ErrorCode::InitReturnsValue,
+                    "Constructor `init` cannot return a value.",
+                    "Remove the return type.",
+                    return_ty.span(),
+                );
+            }
+            let signature = self.signature(method);
+            methods.insert(name.to_string(), signature);
+        }
+
+        self.info
+            .set_class(class.name.as_ident(), ClassInfo { fields, methods });
+    }
+
+    fn check_enum(&mut self, decl: &Enum) {
+        let mut variants: Vec<(String, Vec<Type>)> = Vec::new();
+        for variant in &decl.variants {
+            let fields = variant
+                .fields
+                .iter()
+                .map(|ty| self.resolve_type(ty))
+                .collect();
+            if variants
+                .iter()
+                .any(|(name, _)| name == variant.name.as_ident())
+            {
+                self.error(
+                    ErrorCode::DuplicateDefinition,
+                    format!("Duplicate variant `{}`.", variant.name.ty),
+                    format!(
+                        "`{}` is already a variant of `{}`.",
+                        variant.name.ty, decl.name.ty
+                    ),
+                    variant.name.span,
+                );
+                continue;
+            }
+            variants.push((variant.name.as_ident().to_string(), fields));
+        }
+        self.info.set_enum_variants(decl.name.as_ident(), variants);
+    }
+
+    fn check_fields(&mut self, owner: &Token, decl_fields: &[Field]) -> Vec<(String, Type)> {
+        let mut fields: Vec<(String, Type)> = Vec::new();
+        for field in decl_fields {
+            let ty = self.resolve_type(&field.ty);
+            if fields.iter().any(|(name, _)| name == field.name.as_ident()) {
+                self.error(
+                    ErrorCode::DuplicateDefinition,
+                    format!("Duplicate field `{}`.", field.name.ty),
+                    format!("`{}` is already a field of `{}`.", field.name.ty, owner.ty),
+                    field.name.span,
+                );
+                continue;
+            }
+            fields.push((field.name.as_ident().to_string(), ty));
+        }
+
+        fields
+    }
+
+    /// Reports structs that contain themselves, since their values would be infinitely large.
+    fn check_struct_size(&mut self, decl: &Struct) {
+        let name = decl.name.as_ident();
+        let fields = self.info.struct_fields(name).unwrap_or_default().to_vec();
+        for (field, ty) in fields {
+            if self.contains_type(&ty, name, &mut HashSet::new()) {
+                let span = decl
+                    .fields
+                    .iter()
+                    .find(|decl_field| decl_field.name.as_ident() == field)
+                    .map_or(decl.name.span, |decl_field| decl_field.ty.span());
+                self.error(
+                    ErrorCode::RecursiveType,
+                    format!("Recursive struct `{}` has infinite size.", decl.name.ty),
+                    format!("Field `{}` contains `{}` again.", field, decl.name.ty),
+                    span,
+                );
+                // Break the cycle so that later passes do not recurse forever.
+                let fields = self
+                    .info
+                    .struct_fields(name)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(other, other_ty)| {
+                        let ty = if *other == field {
+                            Type::Error
+                        } else {
+                            other_ty.clone()
+                        };
+                        (other.clone(), ty)
+                    })
+                    .collect();
+                self.info.set_struct_fields(name, fields);
+            }
+        }
+    }
+
+    fn check_enum_size(&mut self, decl: &Enum) {
+        let name = decl.name.as_ident();
+        let mut variants = self.info.enum_variants(name).unwrap_or_default().to_vec();
+        for ((variant, fields), decl_variant) in variants.iter_mut().zip(&decl.variants) {
+            for (ty, decl_ty) in fields.iter_mut().zip(&decl_variant.fields) {
+                if self.contains_type(ty, name, &mut HashSet::new()) {
+                    self.error(
+                        ErrorCode::RecursiveType,
+                        format!("Recursive enum `{}` has infinite size.", decl.name.ty),
+                        format!("Variant `{}` contains `{}` again.", variant, decl.name.ty),
+                        decl_ty.span(),
+                    );
+                    // Break the cycle so that later passes do not recurse forever.
+                    *ty = Type::Error;
+                }
+            }
+        }
+        self.info.set_enum_variants(name, variants);
+    }
+
+    /// Returns true if values of type `ty` directly contain a value of the struct or enum `target`.
+    fn contains_type(&self, ty: &Type, target: &str, visited: &mut HashSet<String>) -> bool {
+        let (Type::Struct(name) | Type::Enum(name)) = ty else {
+            return false;
+        };
+        if name == target {
+            return true;
+        }
+        if !visited.insert(name.clone()) {
+            return false;
+        }
+
+        match ty {
+            Type::Struct(_) => self
+                .info
+                .struct_fields(name)
+                .unwrap_or_default()
+                .iter()
+                .any(|(_, field_ty)| self.contains_type(field_ty, target, visited)),
+            _ => self
+                .info
+                .enum_variants(name)
+                .unwrap_or_default()
+                .iter()
+                .flat_map(|(_, fields)| fields)
+                .any(|field_ty| self.contains_type(field_ty, target, visited)),
+        }
+    }
+
+    /// The program starts by calling `main` without arguments, so it cannot declare any parameters.
+    fn check_main(&self, function: &Function) {
+        if let (Some(first), Some(last)) = (function.params.first(), function.params.last()) {
+            self.error(
+                ErrorCode::MainParameters,
+                "The `main` function cannot take parameters.",
+                "`main` is called without arguments when the program starts.",
+                first.name.span.to(last.ty.span()),
+            );
+        }
+    }
+
+    fn check_function(
+        &mut self,
+        function: &Function,
+        signature: Signature,
+        receiver: Option<Type>,
+    ) {
+        if let (Some(self_param), Some(receiver)) = (&function.self_param, receiver) {
+            self.info.set_binding(self_param, receiver);
+        }
+        for (param, ty) in function.params.iter().zip(signature.params) {
+            self.info.set_binding(&param.name, ty);
+        }
+
+        self.return_ty = signature.return_ty;
+        let returns = self.check_block(&function.body);
+        if !returns && !Type::Unit.accepts(&self.return_ty) {
+            self.error(
+                ErrorCode::MissingReturn,
+                format!(
+                    "Function `{}` does not return a value on every path.",
+                    function.name.ty
+                ),
+                format!(
+                    "Expected a value of type `{}` to be returned.",
+                    self.return_ty
+                ),
+                function.name.span,
+            );
+        }
+    }
+
+    /// Checks every statement in `block` and returns true if the block always returns.
+    fn check_block(&mut self, block: &Block) -> bool {
+        let mut returns = false;
+        for stmt in &block.0 {
+            returns |= self.check_stmt(stmt);
+        }
+
+        returns
+    }
+
+    /// Checks a single statement and returns true if it always returns.
+    fn check_stmt(&mut self, stmt: &Statement) -> bool {
+        match stmt {
+            Statement::VarDecl { name, ty, expr, .. } => {
+                self.check_declaration(name, ty, expr);
+                false
+            }
+            Statement::Const(constant) => {
+                self.check_declaration(&constant.name, &constant.ty, &constant.expr);
+                false
+            }
+            Statement::Return { keyword, expr } => {
+                let ty = self.check_expr(expr);
+                let span = match expr {
+                    Expr::None => keyword.span,
+                    expr => expr.span(),
+                };
+                let return_ty = self.return_ty.clone();
+                self.expect(&return_ty, &ty, span);
+                true
+            }
+            Statement::Expression(Expr::Match {
+                keyword,
+                scrutinee,
+                arms,
+            }) => self.check_match(keyword, scrutinee, arms).1,
+            Statement::Expression(expr) => {
+                self.check_expr(expr);
+                false
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.check_condition(condition);
+                let then_returns = self.check_block(then_branch);
+                match else_branch {
+                    Some(else_branch) => self.check_block(else_branch) && then_returns,
+                    None => false,
+                }
+            }
+            Statement::While { condition, body } => {
+                self.check_condition(condition);
+                self.check_block(body);
+                false
+            }
+            Statement::For { var, range, body } => {
+                self.check_expr(range);
+                self.info.set_binding(var, Type::Int);
+                self.check_block(body);
+                false
+            }
+            Statement::Break(_) | Statement::Continue(_) => false,
+            Statement::Unknown => false,
+        }
+    }
+
+    /// Checks a `var` or `const` declaration and records the type of the declared name.
+    fn check_declaration(&mut self, name: &Token, ty: &Option<TypeExpr>, expr: &Expr) {
+        let annotated = ty.as_ref().map(|ty| self.resolve_type(ty));
+        let initializer = match expr {
+            Expr::None => None,
+            expr => Some(self.check_expr(expr)),
+        };
+
+        let ty = match (annotated, initializer) {
+            (Some(annotated), Some(initializer)) => {
+                self.expect(&annotated, &initializer, expr.span());
+                annotated
+            }
+            (Some(Type::Class(class)), None) => {
+                self.error(
+                    ErrorCode::AnnotationsNeeded,
+                    format!("`{}` must be initialized.", name.ty),
+                    format!("Instances of class `{}` have no default value.", class),
+                    name.span,
+                );
+                Type::Class(class)
+            }
+            (None, Some(Type::Array(element))) if *element == Type::Error => {
+                if matches!(expr, Expr::Array { elements, .. } if elements.is_empty()) {
+                    self.error(
+                        ErrorCode::AnnotationsNeeded,
+                        format!("Type annotations needed for `{}`.", name.ty),
+                        format!("Give `{}` an array type such as `[int]`.", name.ty),
+                        name.span,
+                    );
+                }
+                Type::Array(element)
+            }
+            (Some(ty), None) | (None, Some(ty)) => ty,
+            (None, None) => {
+                self.error(
+                    ErrorCode::AnnotationsNeeded,
+                    format!("Type annotations needed for `{}`.", name.ty),
+                    format!("Give `{}` a type or an initial value.", name.ty),
+                    name.span,
+                );
+                Type::Error
+            }
+        };
+        self.info.set_binding(name, ty);
+    }
+
+    fn check_condition(&mut self, condition: &Expr) {
+        let ty = self.check_expr(condition);
+        self.expect(&Type::Bool, &ty, condition.span());
+    }
+
+    fn check_expr(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Binary { lhs, op, rhs } => {
+                let lhs = self.check_expr(lhs);
+                let rhs = self.check_expr(rhs);
+                self.binary_type(op, lhs, rhs, expr.span())
+            }
+            Expr::Unary { op, rhs } => {
+                let ty = self.check_expr(rhs);
+                let valid = match op {
+                    UnaryOp::Negative => ty.is_numeric(),
+                    UnaryOp::Negate => Type::Bool.accepts(&ty),
+                    UnaryOp::Unknown => false,
+                };
+                if valid {
+                    ty
+                } else {
+                    self.error(
+                        ErrorCode::InvalidOperands,
+                        format!("Cannot apply unary operator `{}` to type `{}`.", op, ty),
+                        format!("`{}` is not defined for `{}`.", op, ty),
+                        rhs.span(),
+                    );
+                    Type::Error
+                }
+            }
+            Expr::Literal(literal) => match &literal.ty {
+                Ty::Integer(int) => {
+                    if int.parse::<i64>().is_err() {
+                        self.error(
+                            ErrorCode::IntegerTooLarge,
+                            "Integer literal is too large.",
+                            format!("The largest `int` is {}.", i64::MAX),
+                            literal.span,
+                        );
+                    }
+                    Type::Int
+                }
+                Ty::Float(_) => Type::Float,
+                Ty::Char(_) => Type::Char,
+                Ty::String(_) => Type::String,
+                Ty::Bool(_) => Type::Bool,
+                _ => Type::Error,
+            },
+            Expr::Var(name) => match self.resolutions.get(name) {
+                Some(symbol) => match symbol.kind {
+                    SymbolKind::Variable | SymbolKind::Parameter | SymbolKind::Constant => {
+                        self.info.binding_by_span(symbol.span).clone()
+                    }
+                    SymbolKind::Function | SymbolKind::Builtin => {
+                        self.error(
+                            ErrorCode::NotAValue,
+                            format!("Function `{}` cannot be used as a value.", name.ty),
+                            "Call the function instead.",
+                            name.span,
+                        );
+                        Type::Error
+                    }
+                    SymbolKind::Struct => {
+                        self.error(
+                            ErrorCode::NotAValue,
+                            format!("Struct `{}` cannot be used as a value.", name.ty),
+                            format!("Create a value with `{} {{ ... }}`.", name.ty),
+                            name.span,
+                        );
+                        Type::Error
+                    }
+                    SymbolKind::Class => {
+                        self.error(
+                            ErrorCode::NotAValue,
+                            format!("Class `{}` cannot be used as a value.", name.ty),
+                            format!("Create an instance with `{}(...)`.", name.ty),
+                            name.span,
+                        );
+                        Type::Error
+                    }
+                    SymbolKind::Enum => {
+                        self.error(
+                            ErrorCode::NotAValue,
+                            format!("Enum `{}` cannot be used as a value.", name.ty),
+                            format!("Use one of its variants, such as `{}::...`.", name.ty),
+                            name.span,
+                        );
+                        Type::Error
+                    }
+                },
+                None => Type::Error,
+            },
+            Expr::Grouping(expr) => self.check_expr(expr),
+            Expr::Call { callee, args } => self.check_call(callee, args),
+            Expr::Range { start, end, .. } => {
+                for bound in [start, end] {
+                    let ty = self.check_expr(bound);
+                    self.expect(&Type::Int, &ty, bound.span());
+                }
+                Type::Unit
+            }
+            Expr::Array { elements, .. } => {
+                let mut element_ty = Type::Error;
+                for element in elements {
+                    let ty = self.check_expr(element);
+                    if element_ty == Type::Error {
+                        element_ty = ty;
+                    } else {
+                        self.expect(&element_ty, &ty, element.span());
+                    }
+                }
+                Type::Array(Box::new(element_ty))
+            }
+            Expr::Index { object, index } => {
+                let ty = self.check_expr(object);
+                self.element_type(&ty, index)
+            }
+            Expr::Path(path) => {
+                let Some((name, variant, fields)) = self.resolve_variant(path) else {
+                    return Type::Error;
+                };
+                if !fields.is_empty() {
+                    self.error(
+                        ErrorCode::InvalidVariant,
+                        format!(
+                            "Variant `{}::{}` expects {} value(s).",
+                            name,
+                            variant,
+                            fields.len()
+                        ),
+                        format!("Call it like `{}::{}(...)`.", name, variant),
+                        expr.span(),
+                    );
+                }
+                Type::Enum(name)
+            }
+            Expr::Match {
+                keyword,
+                scrutinee,
+                arms,
+            } => self.check_match(keyword, scrutinee, arms).0,
+            Expr::StructLiteral { name, fields } => self.check_struct_literal(name, fields),
+            Expr::Field { object, field } => {
+                let ty = self.check_expr(object);
+                self.field_type(&ty, field)
+            }
+            Expr::Assign { target, op, value } => {
+                let (target_ty, owner) = self.check_place(target);
+                if matches!(target.as_ref(), Expr::Field { .. } | Expr::Index { .. }) {
+                    self.check_member_assign(target, owner);
+                }
+                let value_ty = self.check_expr(value);
+                let value_ty = match op {
+                    Some(op) => self.binary_type(op, target_ty.clone(), value_ty, expr.span()),
+                    None => value_ty,
+                };
+                self.expect(&target_ty, &value_ty, value.span());
+                Type::Unit
+            }
+            Expr::None => Type::Unit,
+            Expr::Unknown => Type::Error,
+        }
+    }
+
+    /// Looks up the enum variant that `path` names, returning the enum name,
+    /// the variant name and the types of its payload.
+    fn resolve_variant(&mut self, path: &[Token]) -> Option<(String, String, Vec<Type>)> {
+        let enum_name = &path[0];
+        match self.resolutions.get(enum_name).map(|symbol| symbol.kind) {
+            Some(SymbolKind::Enum) => {}
+            Some(_) => {
+                self.error(
+                    ErrorCode::InvalidVariant,
+                    format!("`{}` is not an enum.", enum_name.ty),
+                    "Only enum variants can be named with `::`.",
+                    enum_name.span,
+                );
+                return None;
+            }
+            None => return None,
+        }
+
+        let [_, variant] = path else {
+            self.error(
+                ErrorCode::InvalidVariant,
+                "Expected a path to an enum variant.",
+                format!("Write it as `{}::Variant`.", enum_name.ty),
+                path[path.len() - 1].span,
+            );
+            return None;
+        };
+        let variants = self.info.enum_variants(enum_name.as_ident()).unwrap();
+        match variants.iter().find(|(name, _)| name == variant.as_ident()) {
+            Some((_, fields)) => Some((
+                enum_name.as_ident().to_string(),
+                variant.as_ident().to_string(),
+                fields.clone(),
+            )),
+            None => {
+                self.error(
+                    ErrorCode::InvalidVariant,
+                    format!("No variant `{}` in enum `{}`.", variant.ty, enum_name.ty),
+                    "Unknown variant.",
+                    variant.span,
+                );
+                None
+            }
+        }
+    }
+
+    /// Checks a `match` and returns its type, and whether it returns on every
+    /// path: it is exhaustive and every arm is a block that returns.
+    fn check_match(
+        &mut self,
+        keyword: &Token,
+        scrutinee: &Expr,
+        arms: &[MatchArm],
+    ) -> (Type, bool) {
+        let scrutinee_ty = self.check_expr(scrutinee);
+
+        let mut result: Option<Type> = None;
+        let mut returns = !arms.is_empty();
+        for arm in arms {
+            self.check_pattern(&arm.pattern, &scrutinee_ty);
+            let (ty, span) = match &arm.body {
+                ArmBody::Expr(expr) => {
+                    returns = false;
+                    (self.check_expr(expr), expr.span())
+                }
+                ArmBody::Block(block) => {
+                    let arm_returns = self.check_block(block);
+                    returns &= arm_returns;
+                    // An arm that always returns produces no value, so it fits
+                    // the type of the other arms.
+                    if arm_returns {
+                        continue;
+                    }
+                    (Type::Unit, arm.pattern.span())
+                }
+            };
+            match &result {
+                Some(expected) if *expected != Type::Error => self.expect(expected, &ty, span),
+                _ => result = Some(ty),
+            }
+        }
+
+        if scrutinee_ty != Type::Error {
+            let patterns: Vec<&Pattern> = arms.iter().map(|arm| &arm.pattern).collect();
+            let missing = missing_patterns(&patterns, &scrutinee_ty, &self.info);
+            if !missing.is_empty() {
+                returns = false;
+                let missing: Vec<String> = missing
+                    .iter()
+                    .map(|pattern| format!("`{}`", pattern))
+                    .collect();
+                self.error(
+                    ErrorCode::NonExhaustiveMatch,
+                    "Non-exhaustive patterns in `match`.",
+                    match missing.as_slice() {
+                        [pattern] => format!("Pattern {} not covered.", pattern),
+                        _ => format!("Patterns {} not covered.", missing.join(", ")),
+                    },
+                    keyword.span,
+                );
+            }
+        }
+
+        (result.unwrap_or(Type::Unit), returns)
+    }
+
+    /// Checks that `pattern` can match a value of type `ty` and records the types of its bindings.
+    fn check_pattern(&mut self, pattern: &Pattern, ty: &Type) {
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding(name) => self.info.set_binding(name, ty.clone()),
+            Pattern::Literal(expr) => {
+                let literal_ty = self.check_expr(expr);
+                self.expect(ty, &literal_ty, expr.span());
+            }
+            Pattern::Variant { path, fields } => {
+                let Some((name, variant, field_tys)) = self.resolve_variant(path) else {
+                    for field in fields {
+                        self.check_pattern(field, &Type::Error);
+                    }
+                    return;
+                };
+                self.expect(ty, &Type::Enum(name.clone()), pattern.span());
+                if fields.len() != field_tys.len() {
+                    self.error(
+                        ErrorCode::InvalidVariant,
+                        format!(
+                            "Variant `{}::{}` has {} field(s), but this pattern has {}.",
+                            name,
+                            variant,
+                            field_tys.len(),
+                            fields.len()
+                        ),
+                        "Wrong number of fields.",
+                        pattern.span(),
+                    );
+                }
+                for (i, field) in fields.iter().enumerate() {
+                    let field_ty = field_tys.get(i).cloned().unwrap_or(Type::Error);
+                    self.check_pattern(field, &field_ty);
+                }
+            }
+        }
+    }
+
+    fn check_struct_literal(&mut self, name: &Token, fields: &[FieldInit]) -> Type {
+        let value_types: Vec<Type> = fields
+            .iter()
+            .map(|field| self.check_expr(&field.value))
+            .collect();
+
+        match self.resolutions.get(name).map(|symbol| symbol.kind) {
+            Some(SymbolKind::Struct) => {}
+            Some(_) => {
+                self.error(
+                    ErrorCode::UnknownField,
+                    format!("`{}` is not a struct.", name.ty),
+                    "Only structs can be created with `{ ... }`.",
+                    name.span,
+                );
+                return Type::Error;
+            }
+            None => return Type::Error,
+        }
+
+        let declared = self
+            .info
+            .struct_fields(name.as_ident())
+            .unwrap_or_default()
+            .to_vec();
+        let mut seen: HashSet<&str> = HashSet::new();
+        for (field, value_ty) in fields.iter().zip(&value_types) {
+            let field_name = field.name.as_ident();
+            if !seen.insert(field_name) {
+                self.error(
+                    ErrorCode::DuplicateDefinition,
+                    format!("Field `{}` is specified more than once.", field.name.ty),
+                    "Remove the repeated field.",
+                    field.name.span,
+                );
+                continue;
+            }
+            match declared.iter().find(|(declared, _)| declared == field_name) {
+                Some((_, ty)) => self.expect(ty, value_ty, field.value.span()),
+                None => self.error(
+                    ErrorCode::UnknownField,
+                    format!(
+                        "Struct `{}` has no field named `{}`.",
+                        name.ty, field.name.ty
+                    ),
+                    "Unknown field.",
+                    field.name.span,
+                ),
+            }
+        }
+
+        let missing: Vec<String> = declared
+            .iter()
+            .filter(|(field, _)| !seen.contains(field.as_str()))
+            .map(|(field, _)| format!("`{}`", field))
+            .collect();
+        if !missing.is_empty() {
+            self.error(
+                ErrorCode::MissingFields,
+                format!("Missing fields in initializer of `{}`.", name.ty),
+                format!("Missing {}.", missing.join(", ")),
+                name.span,
+            );
+        }
+
+        Type::Struct(name.as_ident().to_string())
+    }
+
+    fn element_type(&mut self, ty: &Type, index: &Expr) -> Type {
+        let index_ty = self.check_expr(index);
+        self.expect(&Type::Int, &index_ty, index.span());
+
+        match ty {
+            Type::Array(element) => *element.clone(),
+            Type::Error => Type::Error,
+            ty => {
+                self.error(
+                    ErrorCode::NotIndexable,
+                    format!("Cannot index into a value of type `{}`.", ty),
+                    "Only arrays can be indexed.",
+                    index.span(),
+                );
+                Type::Error
+            }
+        }
+    }
+
+    fn field_type(&mut self, ty: &Type, field: &Token) -> Type {
+        let fields = match ty {
+            Type::Struct(_) | Type::Class(_) => self.info.fields(ty).unwrap_or_default(),
+            Type::Error => return Type::Error,
+            ty => {
+                self.error(
+                    ErrorCode::UnknownField,
+                    format!("Type `{}` has no fields.", ty),
+                    format!("Cannot access `{}` here.", field.ty),
+                    field.span,
+                );
+                return Type::Error;
+            }
+        };
+
+        match fields.iter().find(|(name, _)| name == field.as_ident()) {
+            Some((_, ty)) => ty.clone(),
+            None => {
+                self.error(
+                    ErrorCode::UnknownField,
+                    format!("No field `{}` on type `{}`.", field.ty, ty),
+                    "Unknown field.",
+                    field.span,
+                );
+                Type::Error
+            }
+        }
+    }
+
+    fn check_call(&mut self, callee: &Expr, args: &[Expr]) -> Type {
+        let arg_types: Vec<Type> = args.iter().map(|arg| self.check_expr(arg)).collect();
+
+        let (kind, name, signature) = match callee {
+            Expr::Var(name) => match self.resolutions.get(name).map(|symbol| symbol.kind) {
+                Some(SymbolKind::Function) => {
+                    let signature = self.info.signature(name.as_ident()).cloned().unwrap();
+                    ("Function", name, signature)
+                }
+                Some(SymbolKind::Class) => {
+                    // `Name(args)` creates an instance and passes `args` to `init`.
+                    let class = self.info.class(name.as_ident()).unwrap();
+                    let params = match class.methods.get("init") {
+                        Some(init) => init.params.clone(),
+                        None => Vec::new(),
+                    };
+                    let return_ty = Type::Class(name.as_ident().to_string());
+                    ("Class", name, Signature { params, return_ty })
+                }
+                Some(SymbolKind::Builtin) => {
+                    let builtin = Builtin::from_name(name.as_ident()).unwrap();
+                    return self.check_builtin(builtin, name, args, &arg_types);
+                }
+                Some(
+                    SymbolKind::Variable
+                    | SymbolKind::Parameter
+                    | SymbolKind::Constant
+                    | SymbolKind::Struct
+                    | SymbolKind::Enum,
+                ) => {
+                    self.error(
+                        ErrorCode::NotCallable,
+                        format!("`{}` is not a function.", name.ty),
+                        "Only functions can be called.",
+                        name.span,
+                    );
+                    return Type::Error;
+                }
+                None => return Type::Error,
+            },
+            Expr::Field { object, field } => {
+                let ty = self.check_expr(object);
+                match self.method_signature(&ty, field) {
+                    Some(signature) => ("Method", field, signature),
+                    None => return Type::Error,
+                }
+            }
+            Expr::Path(path) => {
+                // `Enum::Variant(args)` creates a variant with a payload.
+                let Some((name, _, params)) = self.resolve_variant(path) else {
+                    return Type::Error;
+                };
+                let return_ty = Type::Enum(name);
+                (
+                    "Variant",
+                    &path[path.len() - 1],
+                    Signature { params, return_ty },
+                )
+            }
+            callee => {
+                self.check_expr(callee);
+                self.error(
+                    ErrorCode::NotCallable,
+                    "Expression is not callable.",
+                    "Only functions can be called.",
+                    callee.span(),
+                );
+                return Type::Error;
+            }
+        };
+
+        if signature.params.len() != args.len() {
+            self.error(
+                ErrorCode::ArgumentCount,
+                format!(
+                    "{} `{}` takes {} argument(s) but {} were supplied.",
+                    kind,
+                    name.ty,
+                    signature.params.len(),
+                    args.len()
+                ),
+                "Wrong number of arguments.",
+                name.span,
+            );
+        } else {
+            for ((param, arg_ty), arg) in signature.params.iter().zip(&arg_types).zip(args) {
+                self.expect(param, arg_ty, arg.span());
+            }
+        }
+
+        signature.return_ty
+    }
+
+    fn check_builtin(
+        &mut self,
+        builtin: Builtin,
+        name: &Token,
+        args: &[Expr],
+        arg_types: &[Type],
+    ) -> Type {
+        match builtin {
+            Builtin::Println => Type::Unit,
+            Builtin::Len => {
+                if args.len() != 1 {
+                    self.error(
+                        ErrorCode::ArgumentCount,
+                        format!(
+                            "Function `{}` takes 1 argument(s) but {} were supplied.",
+                            name.ty,
+                            args.len()
+                        ),
+                        "Wrong number of arguments.",
+                        name.span,
+                    );
+                } else if !matches!(arg_types[0], Type::Array(_) | Type::String | Type::Error) {
+                    self.error(
+                        ErrorCode::MismatchedTypes,
+                        "Mismatched types.",
+                        format!("Expected an array or `string`, found `{}`.", arg_types[0]),
+                        args[0].span(),
+                    );
+                }
+                Type::Int
+            }
+        }
+    }
+
+    fn method_signature(&mut self, ty: &Type, method: &Token) -> Option<Signature> {
+        let class = match ty {
+            Type::Class(name) => self.info.class(name).unwrap(),
+            Type::Error => return None,
+            ty => {
+                self.error(
+                    ErrorCode::UnknownMethod,
+                    format!("Type `{}` has no methods.", ty),
+                    format!("Cannot call `{}` here.", method.ty),
+                    method.span,
+                );
+                return None;
+            }
+        };
+
+        if let Some(signature) = class.methods.get(method.as_ident()) {
+            return Some(signature.clone());
+        }
+        let is_field = class
+            .fields
+            .iter()
+            .any(|(field, _)| field == method.as_ident());
+        self.error(
+            ErrorCode::UnknownMethod,
+            format!("No method `{}` on type `{}`.", method.ty, ty),
+            if is_field {
+                format!("`{}` is a field, not a method.", method.ty)
+            } else {
+                String::from("Unknown method.")
+            },
+            method.span,
+        );
+        None
+    }
+
+    /// Checks the target of an assignment, returning its type and what owns the storage it writes to.
+    fn check_place<'e>(&mut self, target: &'e Expr) -> (Type, Owner<'e>) {
+        match target {
+            Expr::Var(name) => (self.check_expr(target), Owner::Variable(name)),
+            Expr::Field { object, field } => {
+                let (object_ty, owner) = self.check_place(object);
+                let ty = self.field_type(&object_ty, field);
+                // Objects are shared, so writing to their fields does not change the variable.
+                match object_ty {
+                    Type::Class(_) => (ty, Owner::Object),
+                    _ => (ty, owner),
+                }
+            }
+            Expr::Index { object, index } => {
+                let (object_ty, owner) = self.check_place(object);
+                (self.element_type(&object_ty, index), owner)
+            }
+            target => (self.check_expr(target), Owner::Temporary),
+        }
+    }
+
+    /// Checks an assignment to a field or an array element.
+    fn check_member_assign(&mut self, target: &Expr, owner: Owner) {
+        let member = match target {
+            Expr::Index { .. } => "an element",
+            _ => "a field",
+        };
+        let name = match owner {
+            Owner::Variable(name) => name,
+            Owner::Object => return,
+            Owner::Temporary => {
+                self.error(
+                    ErrorCode::AssignToImmutable,
+                    format!("Cannot assign to {} of a temporary value.", member),
+                    "Store the value in a `var mut` first.",
+                    target.span(),
+                );
+                return;
+            }
+        };
+
+        let (message, secondary_msg) = match self.resolutions.get(name).copied() {
+            Some(symbol) if symbol.kind == SymbolKind::Variable && !symbol.mutable => (
+                format!(
+                    "Cannot assign to {} of immutable variable `{}`.",
+                    member, name.ty
+                ),
+                format!("Declare it with `var mut {}` to make it mutable.", name.ty),
+            ),
+            Some(symbol) if symbol.kind == SymbolKind::Parameter => (
+                format!("Cannot assign to {} of parameter `{}`.", member, name.ty),
+                String::from("Parameters are immutable."),
+            ),
+            Some(symbol) if symbol.kind == SymbolKind::Constant => (
+                format!("Cannot assign to {} of constant `{}`.", member, name.ty),
+                String::from("Constants cannot change after they are declared."),
+            ),
+            _ => return,
+        };
+        self.error(
+            ErrorCode::AssignToImmutable,
+            message,
+            secondary_msg,
+            name.span,
+        );
+    }
+
+    fn binary_type(&mut self, op: &BinOp, lhs: Type, rhs: Type, span: Span) -> Type {
+        if lhs == Type::Error || rhs == Type::Error {
+            return Type::Error;
+        }
+
+        let result = match op {
+            BinOp::Add | BinOp::Subtract | BinOp::Multiply | BinOp::Divide => match (&lhs, &rhs) {
+                (Type::Int, Type::Int) => Some(Type::Int),
+                (Type::Float, Type::Float) => Some(Type::Float),
+                (Type::String, Type::String) if *op == BinOp::Add => Some(Type::String),
+                _ => None,
+            },
+            BinOp::And | BinOp::Or => {
+                (lhs == Type::Bool && rhs == Type::Bool).then_some(Type::Bool)
+            }
+            BinOp::Eq | BinOp::NotEq => (lhs == rhs
+                && matches!(
+                    lhs,
+                    Type::Int | Type::Float | Type::Bool | Type::Char | Type::String
+                ))
+            .then_some(Type::Bool),
+            BinOp::GTOrEq | BinOp::LTOrEq | BinOp::GT | BinOp::LT => (lhs == rhs
+                && matches!(lhs, Type::Int | Type::Float | Type::Char | Type::String))
+            .then_some(Type::Bool),
+        };
+
+        match result {
+            Some(ty) => ty,
+            None => {
+                self.error(
+                    ErrorCode::InvalidOperands,
+                    format!(
+                        "Cannot apply binary operator `{}` to types `{}` and `{}`.",
+                        op, lhs, rhs
+                    ),
+                    format!("`{}` is not defined between `{}` and `{}`.", op, lhs, rhs),
+                    span,
+                );
+                Type::Error
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: &Type, found: &Type, span: Span) {
+        if !expected.accepts(found) {
+            self.error(
+                ErrorCode::MismatchedTypes,
+                "Mismatched types.",
+                format!("Expected `{}`, found `{}`.", expected, found),
+                span,
+            );
+        }
+    }
+
+    fn error(
+        &self,
+        code: ErrorCode,
+        message: impl Into<String>,
+        secondary_msg: impl Into<String>,
+        span: Span,
+    ) {
+        self.compiler
+            .reporter
+            .borrow_mut()
+            .add(diag!(message.into(), secondary_msg.into(), span).with_code(code));
+    }
+}
@@ -0,0 +1,1022 @@
+use std::{collections::HashMap, ffi::c_char, fs, io::Write, path::Path};
+
+use cranelift_codegen::{
+    ir::{
+        condcodes::{FloatCC, IntCC},
+        types, AbiParam, Block as ClifBlock, FuncRef, InstBuilder, MemFlags, TrapCode,
+        Value as ClifValue,
+    },
+    isa::OwnedTargetIsa,
+    settings::{self, Configurable},
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{
+    default_libcall_names, DataDescription, DataId, FuncId, Linkage, Module, ModuleError,
+};
+use cranelift_object::{ObjectBuilder, ObjectModule};
+
+use crate::{
+    builtins::Builtin,
+    compiler::Compiler,
+    diag,
+    diagnostic::{plain_location, Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    ir::{self, BinaryOp, BlockId, Inst, InstKind, Target, Terminator, UnaryOp, ValueId},
+    transpiler::{self, BuildDir, RUNTIME},
+    types::Type,
+    utils::Span,
+    value::{stack_overflow, Value, MAX_CALL_DEPTH},
+};
+
+type Lower<T> = Result<T, Diagnostic>;
+
+/// The functions that compiled code calls into. The JIT provides them from this
+/// file and executables get them from `SUPPORT`, linked next to the object file.
+/// Strings are pointers to their length in bytes, as a 64-bit integer, followed
+/// by their UTF-8 bytes.
+const PRINT_VALUE: &str = "glacier_print_value";
+const PRINT_STR: &str = "glacier_print_str";
+const FAIL: &str = "glacier_fail";
+const STR_COMPARE: &str = "glacier_str_compare";
+const STR_CONCAT: &str = "glacier_str_concat";
+const STR_LEN: &str = "glacier_str_len";
+
+/// The C definitions of the functions above, on top of the C runtime.
+const SUPPORT: &str = r#"
+void glacier_print_value(int32_t tag, int64_t bits) {
+    GlBuf buf = {NULL, 0, 0};
+    Value value;
+    switch (tag) {
+    case GL_INT: value = gl_int(bits); break;
+    case GL_FLOAT: value = gl_float_bits((uint64_t)bits); break;
+    case GL_BOOL: value = gl_bool(bits != 0); break;
+    case GL_CHAR: value = gl_char((uint32_t)bits); break;
+    default: value = gl_unit(); break;
+    }
+    gl_buf_value(&buf, &value);
+    fwrite(buf.data, 1, buf.len, stdout);
+    free(buf.data);
+}
+
+void glacier_print_str(const uint64_t *string) {
+    fwrite(string + 1, 1, (size_t)string[0], stdout);
+}
+
+void glacier_fail(const char *code, const char *message, const char *detail,
+                  const char *location) {
+    gl_fail(code, message, detail, location);
+}
+
+int32_t glacier_str_compare(const uint64_t *a, const uint64_t *b) {
+    size_t len = a[0] < b[0] ? (size_t)a[0] : (size_t)b[0];
+    int order = memcmp(a + 1, b + 1, len);
+    if (order != 0) return order < 0 ? -1 : 1;
+    return a[0] < b[0] ? -1 : a[0] > b[0];
+}
+
+const uint64_t *glacier_str_concat(const uint64_t *a, const uint64_t *b) {
+    uint64_t *string = gl_alloc(sizeof(uint64_t) + (size_t)(a[0] + b[0]));
+    string[0] = a[0] + b[0];
+    memcpy((char *)(string + 1), a + 1, (size_t)a[0]);
+    memcpy((char *)(string + 1) + a[0], b + 1, (size_t)b[0]);
+    return string;
+}
+
+int64_t glacier_str_len(const uint64_t *string) {
+    const unsigned char *bytes = (const unsigned char *)(string + 1);
+    int64_t len = 0;
+    uint64_t i;
+    for (i = 0; i < string[0]; i++) {
+        if ((bytes[i] & 0xC0) != 0x80) len++;
+    }
+    return len;
+}
+"#;
+
+/// Runs the program in-process on machine code generated by Cranelift.
+pub fn run_jit(compiler: &Compiler, program: &ir::Module) {
+    let mut builder = JITBuilder::with_isa(host_isa(false), default_libcall_names());
+    builder.symbol(PRINT_VALUE, print_value as *const u8);
+    builder.symbol(PRINT_STR, print_str as *const u8);
+    builder.symbol(FAIL, fail as *const u8);
+    builder.symbol(STR_COMPARE, str_compare as *const u8);
+    builder.symbol(STR_CONCAT, str_concat as *const u8);
+    builder.symbol(STR_LEN, str_len as *const u8);
+
+    let mut codegen = CodeGen::new(compiler, program, JITModule::new(builder));
+    let Some(entry) = codegen.compile("glacier_entry") else {
+        return;
+    };
+    codegen
+        .module
+        .finalize_definitions()
+        .expect("Failed to finalize the generated code.");
+    let code = codegen.module.get_finalized_function(entry);
+    // SAFETY: `entry` was defined with the signature `extern "C" fn() -> i32`.
+    let entry = unsafe { std::mem::transmute::<*const u8, extern "C" fn() -> i32>(code) };
+    entry();
+    std::io::stdout().flush().unwrap();
+
+    let (fault, _) = codegen.module.get_finalized_data(codegen.fault);
+    // SAFETY: the fault object is an aligned `i64` that compiled code has finished writing.
+    let fault = unsafe { *(fault as *const i64) };
+    if let Some(diagnostic) = usize::try_from(fault - 1)
+        .ok()
+        .and_then(|site| codegen.faults.get(site))
+    {
+        compiler.reporter.borrow_mut().add(diagnostic.clone());
+    }
+}
+
+/// Compiles the program into an object file whose `main` runs it, or returns
+/// `None` if it uses something the Cranelift backend does not support.
+pub fn emit_object(compiler: &Compiler, program: &ir::Module) -> Option<Vec<u8>> {
+    let builder = ObjectBuilder::new(host_isa(true), "glacier", default_libcall_names())
+        .expect("The host platform has no supported object format.");
+    let mut codegen = CodeGen::new(compiler, program, ObjectModule::new(builder));
+    codegen.compile("main")?;
+    Some(
+        codegen
+            .module
+            .finish()
+            .emit()
+            .expect("Failed to write the object file."),
+    )
+}
+
+/// Writes `object` to `OUT.o` and links it with the support code into the
+/// executable `output`. The support code is compiled from a temporary directory.
+pub fn build_executable(object: &[u8], output: &Path) -> Result<(), Diagnostic> {
+    let object_path = output.with_extension("o");
+    transpiler::write_new(&object_path, object)?;
+    let dir = BuildDir::new()?;
+    let support_path = dir.path().join("support.c");
+    fs::write(&support_path, format!("{}{}", RUNTIME, SUPPORT))
+        .map_err(|err| transpiler::write_error(&support_path, err))?;
+    transpiler::cc(&[&object_path, &support_path], output)
+}
+
+fn host_isa(is_pic: bool) -> OwnedTargetIsa {
+    let mut flags = settings::builder();
+    flags.set("opt_level", "speed").unwrap();
+    flags
+        .set("is_pic", if is_pic { "true" } else { "false" })
+        .unwrap();
+    cranelift_native::builder()
+        .expect("The host machine is not supported by Cranelift.")
+        .finish(settings::Flags::new(flags))
+        .unwrap()
+}
+
+extern "C" fn print_value(tag: i32, bits: i64) {
+    let value = match tag {
+        1 => Value::Int(bits),
+        2 => Value::Float(f64::from_bits(bits as u64)),
+        3 => Value::Bool(bits != 0),
+        4 => Value::Char(char::from_u32(bits as u32).unwrap_or_default()),
+        _ => Value::Unit,
+    };
+    print!("{}", value);
+}
+
+/// Returns the bytes of a string made by compiled code.
+///
+/// # Safety
+///
+/// `string` must point to a length followed by that many bytes.
+unsafe fn string_bytes<'a>(string: *const u64) -> &'a [u8] {
+    std::slice::from_raw_parts(string.add(1) as *const u8, *string as usize)
+}
+
+extern "C" fn print_str(string: *const u64) {
+    // SAFETY: compiled code only passes strings.
+    let bytes = unsafe { string_bytes(string) };
+    print!("{}", String::from_utf8_lossy(bytes));
+}
+
+/// Does nothing in the JIT: compiled code records the error in its fault object
+/// and returns to `run_jit`, which reports it like the other backends.
+extern "C" fn fail(
+    _code: *const c_char,
+    _message: *const c_char,
+    _detail: *const c_char,
+    _location: *const c_char,
+) {
+}
+
+extern "C" fn str_compare(a: *const u64, b: *const u64) -> i32 {
+    // SAFETY: compiled code only passes strings.
+    let (a, b) = unsafe { (string_bytes(a), string_bytes(b)) };
+    a.cmp(b) as i32
+}
+
+/// Returns a new string. Like every string the program builds, it is never freed.
+extern "C" fn str_concat(a: *const u64, b: *const u64) -> *const u64 {
+    // SAFETY: compiled code only passes strings.
+    let (a, b) = unsafe { (string_bytes(a), string_bytes(b)) };
+    let len = a.len() + b.len();
+    let mut words = vec![0u64; 1 + len.div_ceil(8)];
+    words[0] = len as u64;
+    // SAFETY: the words after the length have room for `len` bytes.
+    let bytes =
+        unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr().add(1) as *mut u8, len) };
+    bytes[..a.len()].copy_from_slice(a);
+    bytes[a.len()..].copy_from_slice(b);
+    words.leak().as_ptr()
+}
+
+extern "C" fn str_len(string: *const u64) -> i64 {
+    // SAFETY: compiled code only passes strings.
+    let bytes = unsafe { string_bytes(string) };
+    String::from_utf8_lossy(bytes).chars().count() as i64
+}
+
+/// Compiles the functions of a program in SSA form to machine code and defines
+/// them in `module`. Only `int`, `float`, `bool`, `char` and `string` values are
+/// supported, with their operators, calls, `println`, `len` and control flow.
+struct CodeGen<'a, M: Module> {
+    compiler: &'a Compiler,
+    program: &'a ir::Module,
+    module: M,
+    functions: HashMap<String, FuncId>,
+    runtime: HashMap<&'static str, FuncId>,
+    /// The data objects of the global constants, by name.
+    globals: HashMap<String, DataId>,
+    strings: HashMap<String, DataId>,
+    /// Null-terminated strings passed to the runtime, such as error locations.
+    c_strings: HashMap<String, DataId>,
+    /// An `i64` that is set to the index of a runtime error in `faults`, plus one,
+    /// when it happens. Every function then returns at once, up to the entry.
+    fault: DataId,
+    /// The errors that compiled code can stop with.
+    faults: Vec<Diagnostic>,
+    /// An `i64` holding the number of calls in progress, which starts at one for
+    /// `main`, so that deep recursion stops with an error like in the interpreter.
+    depth: DataId,
+}
+
+impl<'a, M: Module> CodeGen<'a, M> {
+    fn new(compiler: &'a Compiler, program: &'a ir::Module, mut module: M) -> Self {
+        let fault = module
+            .declare_data("glacier_fault", Linkage::Local, true, false)
+            .unwrap();
+        let mut data = DataDescription::new();
+        data.define_zeroinit(8);
+        data.set_align(8);
+        module.define_data(fault, &data).unwrap();
+        let depth = module
+            .declare_data("glacier_depth", Linkage::Local, true, false)
+            .unwrap();
+        let mut data = DataDescription::new();
+        data.define(Box::new(1i64.to_ne_bytes()));
+        data.set_align(8);
+        module.define_data(depth, &data).unwrap();
+        Self {
+            compiler,
+            program,
+            module,
+            functions: HashMap::new(),
+            runtime: HashMap::new(),
+            globals: HashMap::new(),
+            strings: HashMap::new(),
+            c_strings: HashMap::new(),
+            fault,
+            faults: Vec::new(),
+            depth,
+        }
+    }
+
+    /// Defines every function and an entry function named `entry`, which
+    /// initializes the global constants and calls `main`. Returns its id, or
+    /// `None` if an error was reported.
+    fn compile(&mut self, entry: &str) -> Option<FuncId> {
+        let pointer = self.pointer();
+        let runtime = [
+            (PRINT_VALUE, vec![types::I32, types::I64], None),
+            (PRINT_STR, vec![pointer], None),
+            (FAIL, vec![pointer; 4], None),
+            (STR_COMPARE, vec![pointer, pointer], Some(types::I32)),
+            (STR_CONCAT, vec![pointer, pointer], Some(pointer)),
+            (STR_LEN, vec![pointer], Some(types::I64)),
+        ];
+        for (name, params, result) in runtime {
+            let mut signature = self.module.make_signature();
+            signature.params = params.into_iter().map(AbiParam::new).collect();
+            signature.returns.extend(result.map(AbiParam::new));
+            let id = self
+                .module
+                .declare_function(name, Linkage::Import, &signature)
+                .unwrap();
+            self.runtime.insert(name, id);
+        }
+
+        let program = self.program;
+        for (name, _) in &program.globals {
+            let id = self
+                .module
+                .declare_data(
+                    &format!("glacier_global_{}", name),
+                    Linkage::Local,
+                    true,
+                    false,
+                )
+                .unwrap();
+            let mut data = DataDescription::new();
+            data.define_zeroinit(8);
+            data.set_align(8);
+            self.module.define_data(id, &data).unwrap();
+            self.globals.insert(name.clone(), id);
+        }
+
+        let mut errors = Vec::new();
+        for function in &program.functions {
+            errors.extend(self.declare_function(function).err());
+        }
+        if errors.is_empty() {
+            for function in &program.functions {
+                errors.extend(self.define_function(function).err());
+            }
+        }
+        if errors.is_empty() {
+            match self.define_entry(entry) {
+                Ok(id) => return Some(id),
+                Err(diagnostic) => errors.push(diagnostic),
+            }
+        }
+
+        for diagnostic in errors {
+            self.compiler.reporter.borrow_mut().add(diagnostic);
+        }
+        None
+    }
+
+    fn declare_function(&mut self, function: &ir::Function) -> Lower<()> {
+        let mut signature = self.module.make_signature();
+        for &param in function.params() {
+            if let Some(ty) = clif_type(function.ty(param), function.span, self.pointer())? {
+                signature.params.push(AbiParam::new(ty));
+            }
+        }
+        if let Some(ty) = clif_type(&function.return_ty, function.span, self.pointer())? {
+            signature.returns.push(AbiParam::new(ty));
+        }
+
+        // Functions get a prefix so that they cannot clash with symbols of the C library.
+        let symbol = match function.name.as_str() {
+            ir::Module::INIT => String::from("glacier_globals"),
+            name => format!("glacier_fn_{}", name.replace('.', "_")),
+        };
+        let id = self
+            .module
+            .declare_function(&symbol, Linkage::Local, &signature)
+            .unwrap();
+        self.functions.insert(function.name.clone(), id);
+        Ok(())
+    }
+
+    fn define_function(&mut self, function: &ir::Function) -> Lower<()> {
+        let order = function.reverse_postorder();
+        self.check_supported(function, &order)?;
+
+        let id = self.functions[&function.name];
+        let mut ctx = self.module.make_context();
+        ctx.func.signature = self
+            .module
+            .declarations()
+            .get_function_decl(id)
+            .signature
+            .clone();
+        let mut builder_ctx = FunctionBuilderContext::new();
+        let builder = FunctionBuilder::new(&mut ctx.func, &mut builder_ctx);
+        let mut lowering = Lowering::new(self, function, builder);
+        // Blocks are lowered after the blocks that dominate them, which define the values they use.
+        for block in order {
+            lowering.block(block);
+        }
+        lowering.builder.seal_all_blocks();
+        lowering.builder.finalize();
+
+        self.module
+            .define_function(id, &mut ctx)
+            .map_err(|err| rejected(err, function.span))?;
+        Ok(())
+    }
+
+    /// Reports the first instruction in `function` that this backend cannot compile.
+    fn check_supported(&self, function: &ir::Function, order: &[BlockId]) -> Lower<()> {
+        for &block in order {
+            for &param in &function.block(block).params {
+                clif_type(function.ty(param), function.span, self.pointer())?;
+            }
+            for inst in &function.block(block).insts {
+                let what = match &inst.kind {
+                    InstKind::Array(_) | InstKind::Index(..) | InstKind::InsertElement(..) => {
+                        Some("Arrays")
+                    }
+                    InstKind::Struct(..) | InstKind::InsertField(..) => Some("Structs"),
+                    InstKind::New(_) | InstKind::StoreField(..) => Some("Classes"),
+                    InstKind::Field(object, _) => match function.ty(*object) {
+                        Type::Class(_) => Some("Classes"),
+                        _ => Some("Structs"),
+                    },
+                    InstKind::Variant(..) => Some("Enums"),
+                    InstKind::IsVariant(..) | InstKind::VariantField(..) => Some("Enum patterns"),
+                    _ => None,
+                };
+                if let Some(what) = what {
+                    return Err(unsupported(what, inst.span));
+                }
+                for operand in inst.kind.operands() {
+                    clif_type(function.ty(operand), inst.span, self.pointer())?;
+                }
+                if let Some(result) = inst.result {
+                    clif_type(function.ty(result), inst.span, self.pointer())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn define_entry(&mut self, name: &str) -> Lower<FuncId> {
+        let Some(&main) = self.functions.get("main") else {
+            return Err(diag!(
+                "No `main` function found.",
+                "Add a `func main() { ... }` to run this program.",
+                Span::default()
+            )
+            .with_code(ErrorCode::NoMain));
+        };
+        let init = self.functions[ir::Module::INIT];
+
+        let mut signature = self.module.make_signature();
+        signature.returns.push(AbiParam::new(types::I32));
+        let id = self
+            .module
+            .declare_function(name, Linkage::Export, &signature)
+            .unwrap();
+        let mut ctx = self.module.make_context();
+        ctx.func.signature = signature;
+        let mut builder_ctx = FunctionBuilderContext::new();
+        let mut builder = FunctionBuilder::new(&mut ctx.func, &mut builder_ctx);
+
+        let block = builder.create_block();
+        let run_main = builder.create_block();
+        let done = builder.create_block();
+        builder.switch_to_block(block);
+        let init = self.module.declare_func_in_func(init, builder.func);
+        builder.ins().call(init, &[]);
+        // A global that fails to initialize stops the program before `main`.
+        let fault = self.module.declare_data_in_func(self.fault, builder.func);
+        let fault = builder.ins().global_value(self.pointer(), fault);
+        let fault = builder
+            .ins()
+            .load(types::I64, MemFlags::trusted(), fault, 0);
+        builder.ins().brif(fault, done, &[], run_main, &[]);
+        builder.switch_to_block(run_main);
+        let main = self.module.declare_func_in_func(main, builder.func);
+        builder.ins().call(main, &[]);
+        builder.ins().jump(done, &[]);
+        builder.switch_to_block(done);
+        let zero = builder.ins().iconst(types::I32, 0);
+        builder.ins().return_(&[zero]);
+        builder.seal_all_blocks();
+        builder.finalize();
+
+        self.module
+            .define_function(id, &mut ctx)
+            .map_err(|err| rejected(err, Span::default()))?;
+        Ok(id)
+    }
+
+    fn pointer(&self) -> types::Type {
+        self.module.target_config().pointer_type()
+    }
+
+    /// Returns the data object holding `string` as a Glacier string.
+    fn string(&mut self, string: &str) -> DataId {
+        if let Some(&id) = self.strings.get(string) {
+            return id;
+        }
+        let mut bytes = (string.len() as u64).to_ne_bytes().to_vec();
+        bytes.extend(string.as_bytes());
+        let id = self.define_bytes(format!("glacier_string_{}", self.strings.len()), bytes);
+        self.strings.insert(string.to_string(), id);
+        id
+    }
+
+    /// Returns the data object holding `string` as a null-terminated C string.
+    fn c_string(&mut self, string: &str) -> DataId {
+        if let Some(&id) = self.c_strings.get(string) {
+            return id;
+        }
+        let mut bytes = string.as_bytes().to_vec();
+        bytes.push(0);
+        let id = self.define_bytes(format!("glacier_c_string_{}", self.c_strings.len()), bytes);
+        self.c_strings.insert(string.to_string(), id);
+        id
+    }
+
+    fn define_bytes(&mut self, name: String, bytes: Vec<u8>) -> DataId {
+        let id = self
+            .module
+            .declare_data(&name, Linkage::Local, false, false)
+            .unwrap();
+        let mut data = DataDescription::new();
+        data.define(bytes.into_boxed_slice());
+        data.set_align(8);
+        self.module.define_data(id, &data).unwrap();
+        id
+    }
+}
+
+/// Lowers the blocks of one function.
+struct Lowering<'a, 'b, M: Module> {
+    codegen: &'b mut CodeGen<'a, M>,
+    function: &'b ir::Function,
+    builder: FunctionBuilder<'b>,
+    /// The Cranelift block of every block, by index.
+    blocks: Vec<ClifBlock>,
+    /// The Cranelift value of every value, by id. Values of type `()` have none.
+    values: Vec<Option<ClifValue>>,
+}
+
+impl<'a, 'b, M: Module> Lowering<'a, 'b, M> {
+    /// Creates every block and its parameters, so that jumps can be lowered
+    /// before their targets.
+    fn new(
+        codegen: &'b mut CodeGen<'a, M>,
+        function: &'b ir::Function,
+        mut builder: FunctionBuilder<'b>,
+    ) -> Self {
+        let pointer = codegen.pointer();
+        let blocks: Vec<ClifBlock> = function
+            .blocks
+            .iter()
+            .map(|_| builder.create_block())
+            .collect();
+        let mut values = vec![None; function.values.len()];
+
+        // The entry block takes the parameters of the function.
+        let entry = blocks[function.entry().0 as usize];
+        builder.append_block_params_for_function_params(entry);
+        let mut params = builder.block_params(entry).to_vec().into_iter();
+        for &param in function.params() {
+            if *function.ty(param) != Type::Unit {
+                values[param.0 as usize] = params.next();
+            }
+        }
+        for (i, block) in function.blocks.iter().enumerate() {
+            if i == function.entry().0 as usize {
+                continue;
+            }
+            for &param in &block.params {
+                // `check_supported` has rejected values of every other type.
+                if let Ok(Some(ty)) = clif_type(function.ty(param), function.span, pointer) {
+                    values[param.0 as usize] = Some(builder.append_block_param(blocks[i], ty));
+                }
+            }
+        }
+
+        Self {
+            codegen,
+            function,
+            builder,
+            blocks,
+            values,
+        }
+    }
+
+    fn block(&mut self, block: BlockId) {
+        let function = self.function;
+        self.builder.switch_to_block(self.blocks[block.0 as usize]);
+        for inst in &function.block(block).insts {
+            self.inst(inst);
+        }
+        let (terminator, span) = function.block(block).terminator.as_ref().unwrap();
+        self.terminator(terminator, *span);
+    }
+
+    fn inst(&mut self, inst: &Inst) {
+        let value = match &inst.kind {
+            InstKind::Const(value) => self.constant(value),
+            InstKind::Global(name) => inst.result.and_then(|result| {
+                let ty = self.clif_type(result)?;
+                let address = self.data_address(self.codegen.globals[name]);
+                Some(self.builder.ins().load(ty, MemFlags::trusted(), address, 0))
+            }),
+            InstKind::SetGlobal(name, value) => {
+                if let Some(value) = self.value(*value) {
+                    let address = self.data_address(self.codegen.globals[name]);
+                    self.builder
+                        .ins()
+                        .store(MemFlags::trusted(), value, address, 0);
+                }
+                None
+            }
+            InstKind::Binary(op, a, b) => Some(self.binary(*op, *a, *b, inst.span)),
+            InstKind::Unary(op, value) => {
+                let float = *self.function.ty(*value) == Type::Float;
+                let value = self.value(*value).unwrap();
+                Some(match op {
+                    UnaryOp::Neg if float => self.builder.ins().fneg(value),
+                    UnaryOp::Neg => self.builder.ins().ineg(value),
+                    UnaryOp::Not => self.builder.ins().bxor_imm(value, 1),
+                })
+            }
+            InstKind::Call(name, args) => {
+                let args = self.args(args);
+                let id = self.codegen.functions[name];
+                let callee = self
+                    .codegen
+                    .module
+                    .declare_func_in_func(id, self.builder.func);
+                let depth = self.enter_call(inst.span);
+                let call = self.builder.ins().call(callee, &args);
+                let result = self.builder.inst_results(call).first().copied();
+                let address = self.data_address(self.codegen.depth);
+                self.builder
+                    .ins()
+                    .store(MemFlags::trusted(), depth, address, 0);
+                self.check_fault();
+                result
+            }
+            InstKind::CallBuiltin(Builtin::Println, args) => {
+                self.println(args);
+                None
+            }
+            InstKind::CallBuiltin(Builtin::Len, args) => {
+                let string = self.value(args[0]).unwrap();
+                self.call_runtime(STR_LEN, &[string])
+            }
+            // `check_supported` has rejected every other instruction.
+            _ => unreachable!("unsupported instruction {:?}", inst.kind),
+        };
+        if let Some(result) = inst.result {
+            self.values[result.0 as usize] = value;
+        }
+    }
+
+    fn terminator(&mut self, terminator: &Terminator, span: Span) {
+        match terminator {
+            Terminator::Jump(target) => {
+                let (block, args) = self.target(target);
+                self.builder.ins().jump(block, &args);
+            }
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.value(*condition).unwrap();
+                let (then, then_args) = self.target(then);
+                let (otherwise, otherwise_args) = self.target(otherwise);
+                self.builder
+                    .ins()
+                    .brif(condition, then, &then_args, otherwise, &otherwise_args);
+            }
+            Terminator::Return(value) => {
+                let values: Vec<ClifValue> = value
+                    .and_then(|value| self.value(value))
+                    .into_iter()
+                    .collect();
+                self.builder.ins().return_(&values);
+            }
+            // The type checker only accepts exhaustive matches, so this is only
+            // reached through a bug, which still stops the program cleanly.
+            Terminator::NoMatch(_) => self.fail(
+                diag!(
+                    "No arm of this `match` matched the value.",
+                    "Add an arm for this value or a wildcard `_` arm.",
+                    span
+                )
+                .with_code(ErrorCode::NonExhaustiveMatch),
+            ),
+            Terminator::Unreachable => {
+                self.builder.ins().trap(TrapCode::unwrap_user(1));
+            }
+        }
+    }
+
+    fn target(&self, target: &Target) -> (ClifBlock, Vec<ClifValue>) {
+        (
+            self.blocks[target.block.0 as usize],
+            self.args(&target.args),
+        )
+    }
+
+    /// Returns the Cranelift values of `values`, leaving out those of type `()`.
+    fn args(&self, values: &[ValueId]) -> Vec<ClifValue> {
+        values
+            .iter()
+            .filter_map(|&value| self.value(value))
+            .collect()
+    }
+
+    fn value(&self, value: ValueId) -> Option<ClifValue> {
+        self.values[value.0 as usize]
+    }
+
+    fn clif_type(&self, value: ValueId) -> Option<types::Type> {
+        clif_type(
+            self.function.ty(value),
+            self.function.span,
+            self.codegen.pointer(),
+        )
+        .ok()
+        .flatten()
+    }
+
+    fn constant(&mut self, value: &Value) -> Option<ClifValue> {
+        Some(match value {
+            Value::Int(int) => self.builder.ins().iconst(types::I64, *int),
+            Value::Float(float) => self.builder.ins().f64const(*float),
+            Value::Bool(bool) => self.builder.ins().iconst(types::I8, i64::from(*bool)),
+            Value::Char(ch) => self
+                .builder
+                .ins()
+                .iconst(types::I32, i64::from(u32::from(*ch))),
+            Value::String(string) => {
+                let data = self.codegen.string(string);
+                self.data_address(data)
+            }
+            _ => return None,
+        })
+    }
+
+    fn binary(&mut self, op: BinaryOp, a: ValueId, b: ValueId, span: Span) -> ClifValue {
+        let ty = self.function.ty(a).clone();
+        let (a, b) = (self.value(a).unwrap(), self.value(b).unwrap());
+        match (ty, op) {
+            (Type::String, BinaryOp::Add) => self.call_runtime(STR_CONCAT, &[a, b]).unwrap(),
+            (Type::String, op) => {
+                let order = self.call_runtime(STR_COMPARE, &[a, b]).unwrap();
+                self.builder.ins().icmp_imm(int_cc(op, true), order, 0)
+            }
+            (Type::Float, BinaryOp::Add) => self.builder.ins().fadd(a, b),
+            (Type::Float, BinaryOp::Sub) => self.builder.ins().fsub(a, b),
+            (Type::Float, BinaryOp::Mul) => self.builder.ins().fmul(a, b),
+            (Type::Float, BinaryOp::Div) => self.builder.ins().fdiv(a, b),
+            (Type::Float, op) => self.builder.ins().fcmp(float_cc(op), a, b),
+            (_, BinaryOp::Add) => self.builder.ins().iadd(a, b),
+            (_, BinaryOp::Sub) => self.builder.ins().isub(a, b),
+            (_, BinaryOp::Mul) => self.builder.ins().imul(a, b),
+            (_, BinaryOp::Div) => self.divide(a, b, span),
+            // Chars compare by code point.
+            (Type::Char, op) => self.builder.ins().icmp(int_cc(op, false), a, b),
+            (_, op) => self.builder.ins().icmp(int_cc(op, true), a, b),
+        }
+    }
+
+    /// Divides two integers the way the interpreter does: dividing by zero is an
+    /// error and `int::MIN / -1` wraps around instead of trapping.
+    fn divide(&mut self, a: ClifValue, b: ClifValue, span: Span) -> ClifValue {
+        let fail = self.builder.create_block();
+        let ok = self.builder.create_block();
+        let is_zero = self.builder.ins().icmp_imm(IntCC::Equal, b, 0);
+        self.builder.ins().brif(is_zero, fail, &[], ok, &[]);
+
+        self.builder.switch_to_block(fail);
+        self.fail(
+            diag!(
+                "Attempt to divide by zero.",
+                "The divisor of this expression is zero.",
+                span
+            )
+            .with_code(ErrorCode::DivisionByZero),
+        );
+
+        self.builder.switch_to_block(ok);
+        let minus_one = self.builder.ins().icmp_imm(IntCC::Equal, b, -1);
+        let one = self.builder.ins().iconst(types::I64, 1);
+        let divisor = self.builder.ins().select(minus_one, one, b);
+        let quotient = self.builder.ins().sdiv(a, divisor);
+        let negated = self.builder.ins().ineg(a);
+        self.builder.ins().select(minus_one, negated, quotient)
+    }
+
+    /// Prints the arguments separated by spaces, followed by a newline.
+    fn println(&mut self, args: &[ValueId]) {
+        for (i, &arg) in args.iter().enumerate() {
+            if i > 0 {
+                self.print_str(" ");
+            }
+            let ty = self.function.ty(arg).clone();
+            let (tag, bits) = match (self.value(arg), ty) {
+                (Some(value), Type::String) => {
+                    self.call_runtime(PRINT_STR, &[value]);
+                    continue;
+                }
+                (Some(value), Type::Int) => (1, value),
+                (Some(value), Type::Float) => (
+                    2,
+                    self.builder
+                        .ins()
+                        .bitcast(types::I64, MemFlags::new(), value),
+                ),
+                (Some(value), Type::Bool) => (3, self.builder.ins().uextend(types::I64, value)),
+                (Some(value), _) => (4, self.builder.ins().uextend(types::I64, value)),
+                (None, _) => (0, self.builder.ins().iconst(types::I64, 0)),
+            };
+            let tag = self.builder.ins().iconst(types::I32, tag);
+            self.call_runtime(PRINT_VALUE, &[tag, bits]);
+        }
+        self.print_str("\n");
+    }
+
+    fn print_str(&mut self, string: &str) {
+        let data = self.codegen.string(string);
+        let address = self.data_address(data);
+        self.call_runtime(PRINT_STR, &[address]);
+    }
+
+    /// Stops the program with `diagnostic`: executables print it and exit, and
+    /// the JIT records it in the fault object for `run_jit` to report.
+    fn fail(&mut self, diagnostic: Diagnostic) {
+        let location = diagnostic
+            .span
+            .map(|span| plain_location(self.codegen.compiler, span));
+        let strings = [
+            diagnostic.code.map(|code| code.as_str().to_string()),
+            Some(diagnostic.primary_msg.clone()),
+            diagnostic.secondary_msg.clone(),
+            location,
+        ];
+        let args: Vec<ClifValue> = strings
+            .iter()
+            .map(|string| match string {
+                Some(string) => {
+                    let data = self.codegen.c_string(string);
+                    self.data_address(data)
+                }
+                None => self.builder.ins().iconst(self.codegen.pointer(), 0),
+            })
+            .collect();
+
+        self.codegen.faults.push(diagnostic);
+        let site = self
+            .builder
+            .ins()
+            .iconst(types::I64, self.codegen.faults.len() as i64);
+        let fault = self.data_address(self.codegen.fault);
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), site, fault, 0);
+        self.call_runtime(FAIL, &args);
+        self.unwind();
+    }
+
+    /// Stops the program if a call at `span` would nest more than
+    /// `MAX_CALL_DEPTH` calls deep, and counts the call otherwise. Returns the
+    /// depth before the call, which is restored when it returns.
+    fn enter_call(&mut self, span: Span) -> ClifValue {
+        let address = self.data_address(self.codegen.depth);
+        let depth = self
+            .builder
+            .ins()
+            .load(types::I64, MemFlags::trusted(), address, 0);
+        let overflow = self.builder.create_block();
+        let call = self.builder.create_block();
+        let too_deep = self.builder.ins().icmp_imm(
+            IntCC::SignedGreaterThanOrEqual,
+            depth,
+            MAX_CALL_DEPTH as i64,
+        );
+        self.builder.ins().brif(too_deep, overflow, &[], call, &[]);
+
+        self.builder.switch_to_block(overflow);
+        self.fail(stack_overflow(span));
+
+        self.builder.switch_to_block(call);
+        let deeper = self.builder.ins().iadd_imm(depth, 1);
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), deeper, address, 0);
+        depth
+    }
+
+    /// Returns from the current function if a runtime error happened in a call.
+    fn check_fault(&mut self) {
+        let fault = self.data_address(self.codegen.fault);
+        let fault = self
+            .builder
+            .ins()
+            .load(types::I64, MemFlags::trusted(), fault, 0);
+        let unwind = self.builder.create_block();
+        let next = self.builder.create_block();
+        self.builder.ins().brif(fault, unwind, &[], next, &[]);
+        self.builder.switch_to_block(unwind);
+        self.unwind();
+        self.builder.switch_to_block(next);
+    }
+
+    /// Returns zeros from the current function, after a runtime error was recorded.
+    fn unwind(&mut self) {
+        let returns: Vec<types::Type> = self
+            .builder
+            .func
+            .signature
+            .returns
+            .iter()
+            .map(|param| param.value_type)
+            .collect();
+        let values: Vec<ClifValue> = returns
+            .into_iter()
+            .map(|ty| match ty {
+                types::F64 => self.builder.ins().f64const(0.0),
+                ty => self.builder.ins().iconst(ty, 0),
+            })
+            .collect();
+        self.builder.ins().return_(&values);
+    }
+
+    fn data_address(&mut self, data: DataId) -> ClifValue {
+        let global = self
+            .codegen
+            .module
+            .declare_data_in_func(data, self.builder.func);
+        self.builder
+            .ins()
+            .global_value(self.codegen.pointer(), global)
+    }
+
+    /// Calls the runtime function `name` and returns its result, if it has one.
+    fn call_runtime(&mut self, name: &str, args: &[ClifValue]) -> Option<ClifValue> {
+        let function = self.runtime(name);
+        let call = self.builder.ins().call(function, args);
+        self.builder.inst_results(call).first().copied()
+    }
+
+    fn runtime(&mut self, name: &str) -> FuncRef {
+        let id = self.codegen.runtime[name];
+        self.codegen
+            .module
+            .declare_func_in_func(id, self.builder.func)
+    }
+}
+
+/// Returns the Cranelift type that holds values of type `ty`, or `None` for `()`.
+/// Strings are pointers, of type `pointer`.
+fn clif_type(ty: &Type, span: Span, pointer: types::Type) -> Lower<Option<types::Type>> {
+    match ty {
+        Type::Int => Ok(Some(types::I64)),
+        Type::Float => Ok(Some(types::F64)),
+        Type::Bool => Ok(Some(types::I8)),
+        Type::Char => Ok(Some(types::I32)),
+        Type::String => Ok(Some(pointer)),
+        Type::Unit => Ok(None),
+        Type::Array(_) => Err(unsupported("Arrays", span)),
+        Type::Struct(_) => Err(unsupported("Structs", span)),
+        Type::Class(_) => Err(unsupported("Classes", span)),
+        Type::Enum(_) => Err(unsupported("Enums", span)),
+        Type::Error => unreachable!("type errors stop the compilation before code generation"),
+    }
+}
+
+fn int_cc(op: BinaryOp, signed: bool) -> IntCC {
+    match (op, signed) {
+        (BinaryOp::Eq, _) => IntCC::Equal,
+        (BinaryOp::Ne, _) => IntCC::NotEqual,
+        (BinaryOp::Lt, true) => IntCC::SignedLessThan,
+        (BinaryOp::Le, true) => IntCC::SignedLessThanOrEqual,
+        (BinaryOp::Gt, true) => IntCC::SignedGreaterThan,
+        (BinaryOp::Ge, true) => IntCC::SignedGreaterThanOrEqual,
+        (BinaryOp::Lt, false) => IntCC::UnsignedLessThan,
+        (BinaryOp::Le, false) => IntCC::UnsignedLessThanOrEqual,
+        (BinaryOp::Gt, false) => IntCC::UnsignedGreaterThan,
+        (BinaryOp::Ge, false) => IntCC::UnsignedGreaterThanOrEqual,
+        _ => unreachable!("{:?} is not a comparison", op),
+    }
+}
+
+fn float_cc(op: BinaryOp) -> FloatCC {
+    match op {
+        BinaryOp::Eq => FloatCC::Equal,
+        BinaryOp::Ne => FloatCC::NotEqual,
+        BinaryOp::Lt => FloatCC::LessThan,
+        BinaryOp::Le => FloatCC::LessThanOrEqual,
+        BinaryOp::Gt => FloatCC::GreaterThan,
+        BinaryOp::Ge => FloatCC::GreaterThanOrEqual,
+        _ => unreachable!("{:?} is not a comparison", op),
+    }
+}
+
+/// Reports code that Cranelift refused to compile, which is a bug in this backend.
+fn rejected(err: ModuleError, span: Span) -> Diagnostic {
+    diag!(
+        "Internal compiler error: Cranelift rejected the generated code.",
+        "This function could not be compiled.",
+        span
+    )
+    .with_code(ErrorCode::InternalCompilerError)
+    .with_note(err.to_string())
+}
+
+fn unsupported(what: &str, span: Span) -> Diagnostic {
+    diag!(
+        format!("{} are not supported by the Cranelift backend yet.", what),
+        "Run this program without `--jit`, or build it without `--object`.",
+        span
+    )
+    .with_code(ErrorCode::UnsupportedByBackend)
+}
@@ -6,43 +6,59 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::{ast::Ast, diagnostic::DiagnosticReporter, utils::FileId};
+use crate::{
+    ast::Ast,
+    bytecode::Program,
+    diagnostic::{DiagnosticReporter, ErrorFormat},
+    ir::Module,
+    source_map::{SourceFile, SourceMap},
+    utils::FileId,
+};
 
 #[derive(Debug)]
 pub struct Compiler {
-    pub curr_source: String,
-    modules: Vec<PathBuf>,
+    /// The text of every file of the program, read when the file is added.
+    source_map: SourceMap,
     curr_file_id: FileId,
     pub reporter: RefCell<DiagnosticReporter>,
     dump_ast: bool,
+    dump_bytecode: bool,
+    dump_ir: bool,
 }
 
 impl Compiler {
-    pub fn new(filepath: &str, dump_ast: bool) -> Self {
+    pub fn new(filepath: &str, dump_ast: bool, dump_bytecode: bool, dump_ir: bool) -> Self {
+        let mut source_map = SourceMap::new();
+        source_map.add(
+            PathBuf::from(filepath),
+            Self::get_file_source(Path::new(filepath)),
+        );
         Self {
-            curr_source: Self::get_file_source(Path::new(filepath)),
-            modules: vec![PathBuf::from(filepath)],
+            source_map,
             curr_file_id: 0,
             reporter: RefCell::new(DiagnosticReporter::new()),
             dump_ast,
+            dump_bytecode,
+            dump_ir,
         }
     }
 
-    pub fn add_module(&mut self, filename: &str) {
-        self.modules.push(PathBuf::from(filename));
+    /// Reads another file of the program and returns its id.
+    pub fn add_module(&mut self, filepath: PathBuf) -> FileId {
+        let source = Self::get_file_source(&filepath);
+        self.source_map.add(filepath, source)
     }
 
-    pub fn next_file(&mut self) {
-        if self.curr_file_id + 1 < self.modules.len() {
+    /// Moves on to the next registered file, returning false once every file has been visited.
+    pub fn next_file(&mut self) -> bool {
+        if self.curr_file_id + 1 < self.source_map.len() {
             self.curr_file_id += 1;
+            return true;
         }
+        false
     }
 
-    pub fn set_file_source(&mut self) {
-        self.curr_source = Self::get_file_source(self.get_module_filepath(self.curr_file_id))
-    }
-
-    pub fn get_file_source(filepath: &Path) -> String {
+    fn get_file_source(filepath: &Path) -> String {
         let mut file = match File::open(filepath) {
             Ok(content) => content,
             Err(r) => {
@@ -63,28 +79,51 @@ impl Compiler {
         self.curr_file_id
     }
 
+    /// Returns the text of the current file.
+    pub fn get_curr_source(&self) -> &str {
+        self.source_map.file(self.curr_file_id).source()
+    }
+
+    pub fn get_source_file(&self, file_id: FileId) -> &SourceFile {
+        self.source_map.file(file_id)
+    }
+
     pub fn get_module_filepath(&self, file_id: FileId) -> &Path {
-        self.modules.get(file_id).unwrap().as_path()
+        self.source_map.file(file_id).path()
     }
 
     pub fn get_module_filename(&self, file_id: FileId) -> &OsStr {
-        self.modules
-            .get(file_id)
-            .unwrap()
-            .as_path()
-            .file_name()
-            .unwrap()
+        self.get_module_filepath(file_id).file_name().unwrap()
     }
 
-    pub fn dump_ast(&self, ast: Ast) {
+    pub fn dump_ast(&self, ast: &Ast) {
         if self.dump_ast {
             println!("{:#?}", ast)
         }
     }
 
-    pub fn print_error(&self) {
-        if self.reporter.borrow().has_error() {
-            self.reporter.borrow().report(self);
+    pub fn dump_bytecode(&self, program: &Program) {
+        if self.dump_bytecode {
+            print!("{}", program.disassemble())
+        }
+    }
+
+    pub fn dump_ir(&self, module: &Module) {
+        if self.dump_ir {
+            print!("{}", module)
+        }
+    }
+
+    /// Prints the diagnostics reported so far in `format`, before the program runs.
+    pub fn flush_errors(&self, format: ErrorFormat) {
+        self.reporter.borrow_mut().flush(self, format);
+    }
+
+    /// Reports the diagnostics in `format`. A SARIF log is written even when
+    /// there are none, so that code scanning sees that the program is clean.
+    pub fn print_error(&self, format: ErrorFormat) {
+        if self.reporter.borrow().has_diagnostics() || format == ErrorFormat::Sarif {
+            self.reporter.borrow().report(self, format);
         }
     }
 }
@@ -1,4 +1,10 @@
-use crate::{compiler::Compiler, utils::Span};
+use crate::{
+    compiler::Compiler,
+    error_codes::ErrorCode,
+    json::Json,
+    source_map::{display_width, expand_tabs, SourceFile},
+    utils::{FileId, Span},
+};
 use colored::Colorize;
 
 #[macro_export]
@@ -25,12 +31,60 @@ pub enum DiagnosticKind {
     Warning,
 }
 
+impl DiagnosticKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticKind::Error => "error",
+            DiagnosticKind::Warning => "warning",
+        }
+    }
+}
+
+/// How diagnostics are written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// Colored text with source snippets.
+    #[default]
+    Human,
+    /// One JSON object per diagnostic, each on its own line.
+    Json,
+    /// A SARIF 2.1.0 log, for uploading to code scanning services.
+    Sarif,
+}
+
+/// A span of source code that a diagnostic points at, with a message shown
+/// next to the underline.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Additional information attached to a diagnostic.
+#[derive(Debug, Clone)]
+pub enum Child {
+    /// A secondary label, underlined with `-` below its source line.
+    Label(Label),
+    /// A `note:` line printed after the source snippets.
+    Note(String),
+    /// A `help:` line printed after the notes.
+    Help(String),
+}
+
+/// A problem in the program. The primary message is the headline, and the
+/// secondary message is shown under `span`, the primary location. Children
+/// add secondary labels, possibly in other files, and `note:` and `help:` lines.
+/// The code, if any, names the kind of problem and is explained by `--explain`.
+/// Problems with the build rather than the program, such as a missing C
+/// compiler, have no span and are printed without a snippet.
 #[derive(Debug, Clone)]
 pub struct Diagnostic {
     pub kind: DiagnosticKind,
+    pub code: Option<ErrorCode>,
     pub primary_msg: String,
     pub secondary_msg: Option<String>,
-    pub span: Span,
+    pub span: Option<Span>,
+    pub children: Vec<Child>,
 }
 
 impl Diagnostic {
@@ -42,99 +96,425 @@ impl Diagnostic {
     ) -> Self {
         Self {
             kind,
+            code: None,
             primary_msg,
             secondary_msg,
-            span,
+            span: Some(span),
+            children: Vec::new(),
         }
     }
 
+    /// Creates an error that is not about any place in the program. Details go
+    /// in notes, as there is no snippet to label.
+    pub fn without_span(primary_msg: impl Into<String>) -> Self {
+        Self {
+            kind: DiagnosticKind::Error,
+            code: None,
+            primary_msg: primary_msg.into(),
+            secondary_msg: None,
+            span: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = Some(code);
+        self
+    }
+
     pub fn with_secondary_msg(mut self, msg: String) -> Self {
         self.secondary_msg = Some(msg);
         self
     }
 
-    /* pub fn with_note(mut self, note: String) -> Self {
-        self.note.push(note);
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.children.push(Child::Label(Label {
+            span,
+            message: message.into(),
+        }));
         self
     }
 
-    pub fn with_hint(mut self, hint: String) -> Self {
-        self.hint.push(hint);
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.children.push(Child::Note(note.into()));
         self
-    } */
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.children.push(Child::Help(help.into()));
+        self
+    }
 
     pub fn print(&self, compiler: &Compiler) {
-        match self.kind {
-            DiagnosticKind::Error => {
-                eprintln!(
-                    "{}: {}",
-                    "Error".red().bold(),
-                    self.primary_msg.bright_white().bold()
-                )
-            }
-            DiagnosticKind::Warning => eprintln!("Warning: {}", self.primary_msg),
-        }
-
-        let span = &self.span;
-        let source = &Compiler::get_file_source(compiler.get_module_filepath(span.file_id));
-        let (line, column) = self.get_line_and_column(source, span.start);
-        let line_content = {
-            let start = source[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
-            let end = source[span.start..]
-                .find('\n')
-                .map(|i| span.start + i)
-                .unwrap_or(source.len());
-            &source[start..end]
-        };
-        let secondary_msg = match &self.secondary_msg {
-            Some(msg) => &format!("{}", msg),
-            None => "",
+        let mut heading = match self.kind {
+            DiagnosticKind::Error => String::from("Error"),
+            DiagnosticKind::Warning => String::from("Warning"),
         };
-
+        if let Some(code) = self.code {
+            heading.push_str(&format!("[{}]", code));
+        }
         eprintln!(
-            "\t{}",
-            format!(
-                "--> {} {}:{}",
-                compiler.get_module_filepath(span.file_id).display(),
-                line,
-                column
-            )
-            .bright_green()
-            .bold()
+            "{}: {}",
+            self.colored(&heading),
+            self.primary_msg.bright_white().bold()
         );
-        eprintln!("{}", format!("  |").cyan().bold());
-        eprintln!("{}  {}", format!("{} |", line).cyan().bold(), line_content);
-        eprintln!(
-            "{}",
-            format!("  |  {:>width$} {}", "^", secondary_msg, width = column)
-                .cyan()
-                .bold()
+
+        // Snippets are grouped by file, starting with the file of the primary
+        // span, and lines within a file are shown in order.
+        let mut snippets: Vec<Snippet> = Vec::new();
+        let primary = self.span.map(|span| Label {
+            span,
+            message: self.secondary_msg.clone().unwrap_or_default(),
+        });
+        let labels = self.children.iter().filter_map(|child| match child {
+            Child::Label(label) => Some((label, false)),
+            _ => None,
+        });
+        for (label, is_primary) in primary.iter().map(|label| (label, true)).chain(labels) {
+            let file_id = label.span.file_id;
+            let index = match snippets.iter().position(|s| s.file_id == file_id) {
+                Some(index) => index,
+                None => {
+                    snippets.push(Snippet {
+                        file_id,
+                        file: compiler.get_source_file(file_id),
+                        marks: Vec::new(),
+                    });
+                    snippets.len() - 1
+                }
+            };
+            let snippet = &mut snippets[index];
+            let mark = Mark::new(snippet.file, label, is_primary);
+            snippet.marks.push(mark);
+        }
+        for snippet in &mut snippets {
+            snippet
+                .marks
+                .sort_by_key(|mark| (mark.line, mark.display_column));
+        }
+
+        let gutter = snippets
+            .iter()
+            .flat_map(|snippet| &snippet.marks)
+            .map(|mark| mark.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        for (i, snippet) in snippets.iter().enumerate() {
+            self.print_snippet(compiler, snippet, i == 0, gutter);
+        }
+
+        let notes = self.children.iter().filter_map(|child| match child {
+            Child::Note(note) => Some(("note", note)),
+            _ => None,
+        });
+        let helps = self.children.iter().filter_map(|child| match child {
+            Child::Help(help) => Some(("help", help)),
+            _ => None,
+        });
+        for (kind, message) in notes.chain(helps) {
+            eprintln!(
+                "{} {}",
+                format!("{:gutter$} = {}:", "", kind).cyan().bold(),
+                message
+            );
+        }
+    }
+
+    fn print_snippet(&self, compiler: &Compiler, snippet: &Snippet, first: bool, gutter: usize) {
+        let bar = format!("{:gutter$} |", "").cyan().bold();
+        // The first snippet is located at the primary span, the others at their first label.
+        let (arrow, located) = match first {
+            true => (
+                "-->",
+                snippet.marks.iter().find(|mark| mark.primary).unwrap(),
+            ),
+            false => (":::", &snippet.marks[0]),
+        };
+        let location = format!(
+            "{} {} {}:{}",
+            arrow,
+            compiler.get_module_filepath(snippet.file_id).display(),
+            located.line,
+            located.column
         );
+        eprintln!("\t{}", location.bright_green().bold());
+        eprintln!("{}", bar);
+
+        let mut previous_line = None;
+        for mark in &snippet.marks {
+            if previous_line != Some(mark.line) {
+                if previous_line.is_some_and(|line| mark.line > line + 1) {
+                    eprintln!("{}", "...".cyan().bold());
+                }
+                let line_number = format!("{:>gutter$} |", mark.line).cyan().bold();
+                eprintln!(
+                    "{}  {}",
+                    line_number,
+                    expand_tabs(snippet.file.line_text(mark.line - 1))
+                );
+            }
+            previous_line = Some(mark.line);
+
+            let (underline, message) = match mark.primary {
+                true => (
+                    self.colored(&"^".repeat(mark.width)),
+                    self.colored(&mark.message),
+                ),
+                false => (
+                    "-".repeat(mark.width).cyan().bold(),
+                    mark.message.cyan().bold(),
+                ),
+            };
+            eprintln!(
+                "{}  {:>pad$}{} {}",
+                bar,
+                "",
+                underline,
+                message,
+                pad = mark.display_column - 1
+            );
+        }
     }
 
-    // Helper function to calculate line and column from a span's start index
-    fn get_line_and_column(&self, source: &str, index: usize) -> (usize, usize) {
-        let mut line = 1;
-        let mut column = 1;
-        for (i, ch) in source.chars().enumerate() {
-            if i == index {
-                break;
+    /// Returns the diagnostic as a JSON object with the location of every label.
+    fn to_json(&self, compiler: &Compiler) -> Json {
+        let mut members = vec![
+            ("kind", Json::string(self.kind.as_str())),
+            ("code", Json::from(self.code.map(|code| code.to_string()))),
+            ("message", Json::string(&self.primary_msg)),
+            ("label", Json::from(self.secondary_msg.clone())),
+        ];
+        match self.span {
+            Some(span) => members.extend(Location::new(compiler, span).to_json()),
+            None => members.extend(Location::NONE.map(|name| (name, Json::Null))),
+        }
+
+        let mut labels = Vec::new();
+        let mut notes = Vec::new();
+        let mut helps = Vec::new();
+        for child in &self.children {
+            match child {
+                Child::Label(label) => {
+                    let mut members = vec![("message", Json::string(&label.message))];
+                    members.extend(Location::new(compiler, label.span).to_json());
+                    labels.push(Json::Object(members));
+                }
+                Child::Note(note) => notes.push(Json::string(note)),
+                Child::Help(help) => helps.push(Json::string(help)),
             }
-            if ch == '\n' {
-                line += 1;
-                column = 1;
-            } else {
-                column += 1;
+        }
+        members.push(("labels", Json::Array(labels)));
+        members.push(("notes", Json::Array(notes)));
+        members.push(("help", Json::Array(helps)));
+        Json::Object(members)
+    }
+
+    /// Returns the diagnostic as a SARIF result. Secondary labels become related locations.
+    fn to_sarif(&self, compiler: &Compiler) -> Json {
+        let mut text = self.primary_msg.clone();
+        if let Some(secondary_msg) = &self.secondary_msg {
+            text.push(' ');
+            text.push_str(secondary_msg);
+        }
+        let mut related = Vec::new();
+        for child in &self.children {
+            match child {
+                Child::Label(label) => related.push(Json::Object(vec![
+                    ("id", Json::Number(related.len())),
+                    (
+                        "physicalLocation",
+                        Location::new(compiler, label.span).to_sarif(),
+                    ),
+                    ("message", sarif_message(&label.message)),
+                ])),
+                Child::Note(note) => text.push_str(&format!("\nnote: {}", note)),
+                Child::Help(help) => text.push_str(&format!("\nhelp: {}", help)),
             }
         }
-        (line, column)
+
+        let mut members = Vec::new();
+        if let Some(code) = self.code {
+            members.push(("ruleId", Json::string(code.as_str())));
+        }
+        members.extend([
+            ("level", Json::string(self.kind.as_str())),
+            ("message", sarif_message(&text)),
+            (
+                "locations",
+                Json::Array(
+                    self.span
+                        .map(|span| {
+                            Json::Object(vec![(
+                                "physicalLocation",
+                                Location::new(compiler, span).to_sarif(),
+                            )])
+                        })
+                        .into_iter()
+                        .collect(),
+                ),
+            ),
+            ("relatedLocations", Json::Array(related)),
+        ]);
+        Json::Object(members)
+    }
+
+    /// Colors `text` like the primary underline of this kind of diagnostic.
+    fn colored(&self, text: &str) -> colored::ColoredString {
+        match self.kind {
+            DiagnosticKind::Error => text.red().bold(),
+            DiagnosticKind::Warning => text.yellow().bold(),
+        }
+    }
+}
+
+/// Renders where `span` is the way a diagnostic shows its primary label, without
+/// colors and up to the underline, for the runtime errors of compiled programs.
+pub fn plain_location(compiler: &Compiler, span: Span) -> String {
+    let file = compiler.get_source_file(span.file_id);
+    let label = Label {
+        span,
+        message: String::new(),
+    };
+    let mark = Mark::new(file, &label, true);
+    let gutter = mark.line.to_string().len();
+    format!(
+        "\t--> {} {}:{}\n{:gutter$} |\n{} |  {}\n{:gutter$} |  {:pad$}{}",
+        file.path().display(),
+        mark.line,
+        mark.column,
+        "",
+        mark.line,
+        expand_tabs(file.line_text(mark.line - 1)),
+        "",
+        "",
+        "^".repeat(mark.width),
+        pad = mark.display_column - 1
+    )
+}
+
+/// The labels of a diagnostic that point into one file.
+struct Snippet<'a> {
+    file_id: FileId,
+    file: &'a SourceFile,
+    marks: Vec<Mark>,
+}
+
+/// A label placed on its line: where its underline starts and how wide it is,
+/// in display columns.
+struct Mark {
+    line: usize,
+    column: usize,
+    display_column: usize,
+    width: usize,
+    message: String,
+    primary: bool,
+}
+
+impl Mark {
+    fn new(file: &SourceFile, label: &Label, primary: bool) -> Self {
+        let start = file.clamp(label.span.start);
+        let position = file.position(start);
+        // Spans over several lines are underlined up to the end of their first line.
+        let (line_start, line_end) = file.line_range(position.line - 1);
+        let end = file.clamp(label.span.end.clamp(start, line_end.max(start)));
+        let source = file.source();
+        let width = display_width(&source[line_start..end]) + 1 - position.display_column;
+        Self {
+            line: position.line,
+            column: position.column,
+            display_column: position.display_column,
+            width: width.max(1),
+            message: label.message.clone(),
+            primary,
+        }
+    }
+}
+
+/// Where a span is, as written in the machine-readable formats. Lines and
+/// columns start at 1, columns count characters, and `end` is the position
+/// just past the span.
+struct Location {
+    file: String,
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl Location {
+    /// The members of a location in JSON, which are `null` for a diagnostic without a span.
+    const NONE: [&'static str; 7] = [
+        "file",
+        "line",
+        "column",
+        "end_line",
+        "end_column",
+        "byte_start",
+        "byte_end",
+    ];
+
+    fn new(compiler: &Compiler, span: Span) -> Self {
+        let file = compiler.get_source_file(span.file_id);
+        let start = file.clamp(span.start);
+        let end = file.clamp(span.end.max(start));
+        let start_position = file.position(start);
+        let end_position = file.position(end);
+        Self {
+            file: file.path().display().to_string(),
+            start,
+            end,
+            line: start_position.line,
+            column: start_position.column,
+            end_line: end_position.line,
+            end_column: end_position.column,
+        }
+    }
+
+    fn to_json(&self) -> Vec<(&'static str, Json)> {
+        vec![
+            ("file", Json::string(&self.file)),
+            ("line", Json::Number(self.line)),
+            ("column", Json::Number(self.column)),
+            ("end_line", Json::Number(self.end_line)),
+            ("end_column", Json::Number(self.end_column)),
+            ("byte_start", Json::Number(self.start)),
+            ("byte_end", Json::Number(self.end)),
+        ]
+    }
+
+    fn to_sarif(&self) -> Json {
+        Json::Object(vec![
+            (
+                "artifactLocation",
+                Json::Object(vec![("uri", Json::string(self.file.replace('\\', "/")))]),
+            ),
+            (
+                "region",
+                Json::Object(vec![
+                    ("startLine", Json::Number(self.line)),
+                    ("startColumn", Json::Number(self.column)),
+                    ("endLine", Json::Number(self.end_line)),
+                    ("endColumn", Json::Number(self.end_column)),
+                    ("byteOffset", Json::Number(self.start)),
+                    ("byteLength", Json::Number(self.end - self.start)),
+                ]),
+            ),
+        ])
     }
 }
 
-#[derive(Debug)]
+fn sarif_message(text: &str) -> Json {
+    Json::Object(vec![("text", Json::string(text))])
+}
+
+#[derive(Debug, Default)]
 pub struct DiagnosticReporter {
     diagnostics: Vec<Diagnostic>,
     error: u32,
+    /// How many of the diagnostics have already been printed by `flush`.
+    printed: usize,
 }
 
 impl DiagnosticReporter {
@@ -142,6 +522,7 @@ impl DiagnosticReporter {
         Self {
             diagnostics: Vec::new(),
             error: 0,
+            printed: 0,
         }
     }
 
@@ -156,24 +537,138 @@ impl DiagnosticReporter {
         self.error != 0
     }
 
-    pub fn report(&self, compiler: &Compiler) {
-        for diagnostic in &self.diagnostics {
+    pub fn has_diagnostics(&self) -> bool {
+        self.diagnostics.len() > self.printed
+    }
+
+    /// Prints the diagnostics reported so far, such as warnings about a program
+    /// that is about to run, so that they come before its output. A SARIF log
+    /// is a single document and is only written by `report`.
+    pub fn flush(&mut self, compiler: &Compiler, format: ErrorFormat) {
+        let pending = &self.diagnostics[self.printed..];
+        match format {
+            ErrorFormat::Human => {
+                for diagnostic in pending {
+                    diagnostic.print(compiler);
+                }
+                self.print_explain_hint(pending);
+            }
+            ErrorFormat::Json => {
+                for diagnostic in pending {
+                    eprintln!("{}", diagnostic.to_json(compiler));
+                }
+            }
+            ErrorFormat::Sarif => return,
+        }
+        self.printed = self.diagnostics.len();
+    }
+
+    /// Prints the diagnostics that `flush` has not printed, followed by the
+    /// number of errors.
+    pub fn report(&self, compiler: &Compiler, format: ErrorFormat) {
+        let pending = &self.diagnostics[self.printed..];
+        match format {
+            ErrorFormat::Human => {}
+            ErrorFormat::Json => {
+                for diagnostic in pending {
+                    eprintln!("{}", diagnostic.to_json(compiler));
+                }
+                return;
+            }
+            ErrorFormat::Sarif => {
+                eprintln!("{}", self.to_sarif(compiler));
+                return;
+            }
+        }
+
+        for diagnostic in pending {
             diagnostic.print(compiler);
         }
-        if self.error < 1 {
+        if self.error == 1 {
+            eprintln!("{}", "1 error has been emitted.".bright_white().bold())
+        } else if self.error > 1 {
             eprintln!(
                 "{}",
                 format!("{} errors have been emitted.", self.error)
                     .bright_white()
                     .bold()
             )
-        } else {
+        }
+        self.print_explain_hint(pending);
+    }
+
+    /// Points to `--explain` for the first error among `diagnostics`, or for
+    /// the first warning when there are only warnings.
+    fn print_explain_hint(&self, diagnostics: &[Diagnostic]) {
+        let kind = match diagnostics.iter().any(|d| d.kind == DiagnosticKind::Error) {
+            true => DiagnosticKind::Error,
+            false => DiagnosticKind::Warning,
+        };
+        let code = diagnostics
+            .iter()
+            .filter(|d| d.kind == kind)
+            .find_map(|d| d.code);
+        if let Some(code) = code {
+            let what = match kind {
+                DiagnosticKind::Error => "an error",
+                DiagnosticKind::Warning => "a warning",
+            };
             eprintln!(
                 "{}",
-                format!("{} error has been emitted.", self.error)
-                    .bright_white()
-                    .bold()
-            )
+                format!(
+                    "For more information about {}, try `{} --explain {}`.",
+                    what,
+                    env!("CARGO_PKG_NAME"),
+                    code
+                )
+                .bright_white()
+                .bold()
+            );
+        }
+    }
+
+    fn to_sarif(&self, compiler: &Compiler) -> Json {
+        let results = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.to_sarif(compiler))
+            .collect();
+        // Every code that was reported is described once, as a rule of the tool.
+        let mut codes: Vec<ErrorCode> = Vec::new();
+        for code in self.diagnostics.iter().filter_map(|d| d.code) {
+            if !codes.contains(&code) {
+                codes.push(code);
+            }
         }
+        let rules = codes
+            .into_iter()
+            .map(|code| {
+                Json::Object(vec![
+                    ("id", Json::string(code.as_str())),
+                    ("shortDescription", sarif_message(code.explanation().title)),
+                    (
+                        "fullDescription",
+                        sarif_message(&code.explanation().description.replace('\n', " ")),
+                    ),
+                ])
+            })
+            .collect();
+        let driver = Json::Object(vec![
+            ("name", Json::string(env!("CARGO_PKG_NAME"))),
+            ("version", Json::string(env!("CARGO_PKG_VERSION"))),
+            ("rules", Json::Array(rules)),
+        ]);
+        let run = Json::Object(vec![
+            ("tool", Json::Object(vec![("driver", driver)])),
+            ("results", Json::Array(results)),
+        ]);
+        Json::Object(vec![
+            (
+                "$schema",
+                Json::string("https://json.schemastore.org/sarif-2.1.0.json"),
+            ),
+            ("version", Json::string("2.1.0")),
+            ("runs", Json::Array(vec![run])),
+        ])
     }
 }
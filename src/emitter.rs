@@ -0,0 +1,754 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        ArmBody, Ast, BinOp, Block, Expr, Function, Item, MatchArm, Pattern, Statement, UnaryOp,
+    },
+    builtins::Builtin,
+    bytecode::{self, Instruction, Program},
+    resolver::{Resolutions, SymbolKind},
+    types::TypeInfo,
+    utils::{Span, Token, TokenType as Ty},
+    value::Value,
+};
+
+/// A step from a value to one of its parts on the way to an assignment target.
+enum Step {
+    /// A field, by the constant index of its name.
+    Field(u32, Span),
+    /// An element, by the local slot holding the already evaluated index.
+    Index(u32, Span),
+}
+
+/// The jumps out of the loop being compiled, patched once its end is known.
+#[derive(Default)]
+struct Loop {
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// Compiles a type checked program into bytecode for the stack VM.
+pub struct Emitter<'a> {
+    resolutions: &'a Resolutions,
+    types: &'a TypeInfo,
+    program: Program,
+    /// Function table indexes by function name, or `Class.method` for methods.
+    functions: HashMap<String, u32>,
+    /// Global slots of the top-level constants, by the span of their name.
+    globals: HashMap<Span, u32>,
+    /// Local slots of the function being compiled, by the span of the declaring name.
+    locals: HashMap<Span, u32>,
+    function: bytecode::Function,
+    loops: Vec<Loop>,
+}
+
+impl<'a> Emitter<'a> {
+    pub fn new(resolutions: &'a Resolutions, types: &'a TypeInfo) -> Self {
+        Self {
+            resolutions,
+            types,
+            program: Program::default(),
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            locals: HashMap::new(),
+            function: bytecode::Function::default(),
+            loops: Vec::new(),
+        }
+    }
+
+    pub fn emit(mut self, ast: &Ast) -> Program {
+        // Slot 0 of the function table initializes the global constants.
+        let mut next_function = 1;
+        for item in &ast.items {
+            match item {
+                Item::Func(function) => {
+                    self.functions
+                        .insert(function.name.as_ident().to_string(), next_function);
+                    next_function += 1;
+                }
+                Item::Class(class) => {
+                    let mut methods = HashMap::new();
+                    for method in &class.methods {
+                        let name = format!("{}.{}", class.name.ty, method.name.ty);
+                        self.functions.insert(name, next_function);
+                        methods.insert(method.name.as_ident().to_string(), next_function);
+                        next_function += 1;
+                    }
+                    self.program
+                        .methods
+                        .insert(class.name.as_ident().to_string(), methods);
+                }
+                Item::Const(constant) => {
+                    let slot = self.program.globals.len() as u32;
+                    self.globals.insert(constant.name.span, slot);
+                    self.program
+                        .globals
+                        .push(constant.name.as_ident().to_string());
+                }
+                _ => {}
+            }
+        }
+
+        self.function = bytecode::Function::new("<globals>", 0);
+        let mut span = Span::default();
+        for item in &ast.items {
+            if let Item::Const(constant) = item {
+                self.expr(&constant.expr);
+                let slot = self.globals[&constant.name.span];
+                self.emit_op(Instruction::SetGlobal(slot), constant.name.span);
+                span = constant.name.span;
+            }
+        }
+        self.emit_op(Instruction::Unit, span);
+        self.emit_op(Instruction::Return, span);
+        self.finish_function();
+
+        for item in &ast.items {
+            match item {
+                Item::Func(function) => {
+                    self.function(function, function.name.as_ident().to_string())
+                }
+                Item::Class(class) => {
+                    for method in &class.methods {
+                        self.function(method, format!("{}.{}", class.name.ty, method.name.ty));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.program.main = self.functions.get("main").copied();
+        self.program
+    }
+
+    fn function(&mut self, function: &Function, name: String) {
+        let arity = function.params.len() + usize::from(function.self_param.is_some());
+        self.function = bytecode::Function::new(name, arity as u32);
+        if let Some(self_param) = &function.self_param {
+            self.declare(self_param);
+        }
+        for param in &function.params {
+            self.declare(&param.name);
+        }
+
+        self.block(&function.body);
+        self.emit_op(Instruction::Unit, function.name.span);
+        self.emit_op(Instruction::Return, function.name.span);
+        self.finish_function();
+    }
+
+    fn finish_function(&mut self) {
+        let function = std::mem::take(&mut self.function);
+        self.program.functions.push(function);
+        self.locals.clear();
+    }
+
+    fn block(&mut self, block: &Block) {
+        for stmt in &block.0 {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::VarDecl { name, expr, .. } => {
+                match expr {
+                    Expr::None => {
+                        let value = Value::default_for(self.types.binding(name), self.types);
+                        self.constant(value, name.span);
+                    }
+                    expr => self.expr(expr),
+                }
+                let slot = self.declare(name);
+                self.emit_op(Instruction::SetLocal(slot), name.span);
+            }
+            Statement::Const(constant) => {
+                self.expr(&constant.expr);
+                let slot = self.declare(&constant.name);
+                self.emit_op(Instruction::SetLocal(slot), constant.name.span);
+            }
+            Statement::Return { keyword, expr } => {
+                match expr {
+                    Expr::None => {
+                        self.emit_op(Instruction::Unit, keyword.span);
+                    }
+                    expr => self.expr(expr),
+                }
+                self.emit_op(Instruction::Return, keyword.span);
+            }
+            Statement::Expression(expr) => {
+                self.expr(expr);
+                self.emit_op(Instruction::Pop, expr.span());
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                let to_else = self.emit_op(Instruction::JumpIfFalse(0), condition.span());
+                self.block(then_branch);
+                match else_branch {
+                    Some(else_branch) => {
+                        let to_end = self.emit_op(Instruction::Jump(0), condition.span());
+                        self.patch(to_else);
+                        self.block(else_branch);
+                        self.patch(to_end);
+                    }
+                    None => self.patch(to_else),
+                }
+            }
+            Statement::While { condition, body } => {
+                let start = self.here();
+                self.expr(condition);
+                let exit = self.emit_op(Instruction::JumpIfFalse(0), condition.span());
+                self.loop_body(body);
+                self.emit_op(Instruction::Jump(start as u32), condition.span());
+                self.patch(exit);
+                self.end_loop(start);
+            }
+            Statement::For { var, range, body } => {
+                let Expr::Range {
+                    start,
+                    end,
+                    inclusive,
+                } = range
+                else {
+                    unreachable!()
+                };
+                let span = range.span();
+                // The loop variable doubles as the counter; the end is evaluated once.
+                self.expr(start);
+                let counter = self.declare(var);
+                self.emit_op(Instruction::SetLocal(counter), var.span);
+                self.expr(end);
+                let last = self.temp();
+                self.emit_op(Instruction::SetLocal(last), end.span());
+
+                let top = self.here();
+                self.emit_op(Instruction::GetLocal(counter), var.span);
+                self.emit_op(Instruction::GetLocal(last), end.span());
+                let compare = if *inclusive {
+                    Instruction::LessEqual
+                } else {
+                    Instruction::Less
+                };
+                self.emit_op(compare, span);
+                let exit = self.emit_op(Instruction::JumpIfFalse(0), span);
+                self.loop_body(body);
+
+                let next = self.here();
+                // An inclusive range stops at its end without computing the
+                // next value, which could overflow.
+                let at_end = inclusive.then(|| {
+                    self.emit_op(Instruction::GetLocal(counter), var.span);
+                    self.emit_op(Instruction::GetLocal(last), end.span());
+                    self.emit_op(Instruction::NotEqual, span);
+                    self.emit_op(Instruction::JumpIfFalse(0), span)
+                });
+                self.emit_op(Instruction::GetLocal(counter), var.span);
+                self.constant(Value::Int(1), var.span);
+                self.emit_op(Instruction::Add, var.span);
+                self.emit_op(Instruction::SetLocal(counter), var.span);
+                self.emit_op(Instruction::Jump(top as u32), span);
+                self.patch(exit);
+                if let Some(at_end) = at_end {
+                    self.patch(at_end);
+                }
+                self.end_loop(next);
+            }
+            Statement::Break(keyword) => {
+                let jump = self.emit_op(Instruction::Jump(0), keyword.span);
+                self.loops.last_mut().unwrap().breaks.push(jump);
+            }
+            Statement::Continue(keyword) => {
+                let jump = self.emit_op(Instruction::Jump(0), keyword.span);
+                self.loops.last_mut().unwrap().continues.push(jump);
+            }
+            Statement::Unknown => {}
+        }
+    }
+
+    fn loop_body(&mut self, body: &Block) {
+        self.loops.push(Loop::default());
+        self.block(body);
+    }
+
+    /// Points the `break`s of the innermost loop past its end and its `continue`s at `next`.
+    fn end_loop(&mut self, next: usize) {
+        let Loop { breaks, continues } = self.loops.pop().unwrap();
+        for jump in breaks {
+            self.patch(jump);
+        }
+        for jump in continues {
+            self.patch_to(jump, next);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        let span = expr.span();
+        match expr {
+            Expr::Binary {
+                lhs,
+                op: BinOp::And,
+                rhs,
+            } => {
+                self.expr(lhs);
+                let to_false = self.emit_op(Instruction::JumpIfFalse(0), span);
+                self.expr(rhs);
+                let to_end = self.emit_op(Instruction::Jump(0), span);
+                self.patch(to_false);
+                self.emit_op(Instruction::False, span);
+                self.patch(to_end);
+            }
+            Expr::Binary {
+                lhs,
+                op: BinOp::Or,
+                rhs,
+            } => {
+                self.expr(lhs);
+                let to_rhs = self.emit_op(Instruction::JumpIfFalse(0), span);
+                self.emit_op(Instruction::True, span);
+                let to_end = self.emit_op(Instruction::Jump(0), span);
+                self.patch(to_rhs);
+                self.expr(rhs);
+                self.patch(to_end);
+            }
+            Expr::Binary { lhs, op, rhs } => {
+                self.expr(lhs);
+                self.expr(rhs);
+                self.emit_op(binary_instruction(op), span);
+            }
+            Expr::Unary { op, rhs } => {
+                self.expr(rhs);
+                match op {
+                    UnaryOp::Negative => self.emit_op(Instruction::Negative, span),
+                    UnaryOp::Negate => self.emit_op(Instruction::Not, span),
+                    UnaryOp::Unknown => self.here(),
+                };
+            }
+            Expr::Literal(literal) => {
+                let value = match &literal.ty {
+                    Ty::Integer(int) => Value::Int(int.parse().unwrap_or_default()),
+                    Ty::Float(float) => Value::Float(float.parse().unwrap_or(f64::NAN)),
+                    Ty::Char(ch) => Value::Char(ch.chars().next().unwrap_or_default()),
+                    Ty::String(string) => Value::String(string.clone()),
+                    Ty::Bool(true) => {
+                        self.emit_op(Instruction::True, span);
+                        return;
+                    }
+                    Ty::Bool(false) => {
+                        self.emit_op(Instruction::False, span);
+                        return;
+                    }
+                    _ => unreachable!(),
+                };
+                self.constant(value, span);
+            }
+            Expr::Var(name) => match self.variable(name) {
+                Some(instruction) => {
+                    self.emit_op(instruction, span);
+                }
+                None => {
+                    self.emit_op(Instruction::Unit, span);
+                }
+            },
+            Expr::Grouping(expr) => self.expr(expr),
+            Expr::Call { callee, args } => self.call(callee, args, span),
+            Expr::Path(path) => {
+                self.constant(variant(path, 0), span);
+            }
+            Expr::Match {
+                keyword,
+                scrutinee,
+                arms,
+            } => self.match_expr(keyword, scrutinee, arms),
+            Expr::StructLiteral { name, fields } => {
+                let order: Vec<&str> = self
+                    .types
+                    .struct_fields(name.as_ident())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(field, _)| field.as_str())
+                    .collect();
+                let in_order = fields
+                    .iter()
+                    .map(|field| field.name.as_ident())
+                    .eq(order.iter().copied());
+                if in_order {
+                    for field in fields {
+                        self.expr(&field.value);
+                    }
+                } else {
+                    // Fields are evaluated in the order they are written, then
+                    // pushed in declaration order.
+                    let mut slots = HashMap::new();
+                    for field in fields {
+                        self.expr(&field.value);
+                        let slot = self.temp();
+                        self.emit_op(Instruction::SetLocal(slot), field.name.span);
+                        slots.insert(field.name.as_ident(), slot);
+                    }
+                    for field in &order {
+                        match slots.get(field) {
+                            Some(slot) => self.emit_op(Instruction::GetLocal(*slot), span),
+                            None => self.emit_op(Instruction::Unit, span),
+                        };
+                    }
+                }
+                let template = Value::Struct {
+                    name: name.as_ident().to_string(),
+                    fields: order
+                        .iter()
+                        .map(|field| (field.to_string(), Value::Unit))
+                        .collect(),
+                };
+                let index = self.program.add_constant(template);
+                self.emit_op(Instruction::Struct(index), span);
+            }
+            Expr::Field { object, field } => {
+                self.expr(object);
+                let name = self.name(field);
+                self.emit_op(Instruction::GetField(name), field.span);
+            }
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+                self.emit_op(Instruction::Array(elements.len() as u32), span);
+            }
+            Expr::Index { object, index } => {
+                self.expr(object);
+                self.expr(index);
+                self.emit_op(Instruction::Index, span);
+            }
+            Expr::Assign { target, op, value } => self.assign(target, op.as_ref(), value, span),
+            Expr::Range { .. } | Expr::None | Expr::Unknown => {
+                self.emit_op(Instruction::Unit, span);
+            }
+        }
+    }
+
+    /// Returns the instruction that loads the variable or constant `name`.
+    fn variable(&self, name: &Token) -> Option<Instruction> {
+        let symbol = self.resolutions.get(name)?;
+        if let Some(slot) = self.locals.get(&symbol.span) {
+            return Some(Instruction::GetLocal(*slot));
+        }
+        self.globals
+            .get(&symbol.span)
+            .map(|slot| Instruction::GetGlobal(*slot))
+    }
+
+    fn call(&mut self, callee: &Expr, args: &[Expr], span: Span) {
+        match callee {
+            Expr::Var(name) => match self.resolutions.get(name).map(|symbol| symbol.kind) {
+                Some(SymbolKind::Function) => {
+                    self.args(args);
+                    let function = self.functions[name.as_ident()];
+                    let args = args.len() as u32;
+                    self.emit_op(Instruction::Call { function, args }, name.span);
+                }
+                Some(SymbolKind::Class) => {
+                    let instance = Value::new_instance(name.as_ident(), self.types);
+                    let index = self.program.add_constant(instance);
+                    self.emit_op(Instruction::New(index), name.span);
+                    if let Some(&init) = self.functions.get(&format!("{}.init", name.ty)) {
+                        // The constructor runs on a copy of the reference and its result is dropped.
+                        self.emit_op(Instruction::Dup, name.span);
+                        self.args(args);
+                        let args = args.len() as u32 + 1;
+                        self.emit_op(
+                            Instruction::Call {
+                                function: init,
+                                args,
+                            },
+                            name.span,
+                        );
+                        self.emit_op(Instruction::Pop, name.span);
+                    }
+                }
+                Some(SymbolKind::Builtin) => {
+                    self.args(args);
+                    let builtin = Builtin::from_name(name.as_ident()).unwrap();
+                    let args = args.len() as u32;
+                    self.emit_op(Instruction::CallBuiltin { builtin, args }, name.span);
+                }
+                _ => {
+                    self.emit_op(Instruction::Unit, span);
+                }
+            },
+            Expr::Field { object, field } => {
+                self.expr(object);
+                self.args(args);
+                let method = self.name(field);
+                let args = args.len() as u32;
+                self.emit_op(Instruction::Invoke { method, args }, field.span);
+            }
+            Expr::Path(path) => {
+                self.args(args);
+                let index = self.program.add_constant(variant(path, args.len()));
+                self.emit_op(Instruction::Variant(index), span);
+            }
+            _ => {
+                self.emit_op(Instruction::Unit, span);
+            }
+        }
+    }
+
+    fn args(&mut self, args: &[Expr]) {
+        for arg in args {
+            self.expr(arg);
+        }
+    }
+
+    /// Compiles an assignment. The new value and every index along the target are
+    /// evaluated first, then the value holding the target is taken out of its
+    /// storage, updated and stored back, so that arrays are not copied.
+    fn assign(&mut self, target: &Expr, op: Option<&BinOp>, value: &Expr, span: Span) {
+        if let Expr::Var(name) = target {
+            if let Some(Instruction::GetLocal(slot)) = self.variable(name) {
+                if let Some(op) = op {
+                    self.emit_op(Instruction::GetLocal(slot), name.span);
+                    self.expr(value);
+                    self.emit_op(binary_instruction(op), span);
+                } else {
+                    self.expr(value);
+                }
+                self.emit_op(Instruction::SetLocal(slot), name.span);
+                self.emit_op(Instruction::Unit, span);
+                return;
+            }
+        }
+
+        self.expr(value);
+        let value_slot = self.temp();
+        self.emit_op(Instruction::SetLocal(value_slot), value.span());
+
+        let mut steps = Vec::new();
+        let mut place = target;
+        loop {
+            match place {
+                Expr::Field { object, field } => {
+                    steps.push(Step::Field(self.name(field), field.span));
+                    place = object;
+                }
+                Expr::Index { object, index } => {
+                    self.expr(index);
+                    let slot = self.temp();
+                    self.emit_op(Instruction::SetLocal(slot), index.span());
+                    steps.push(Step::Index(slot, place.span()));
+                    place = object;
+                }
+                _ => break,
+            }
+        }
+        steps.reverse();
+
+        let root = match place {
+            Expr::Var(name) => match self.variable(name) {
+                Some(Instruction::GetLocal(slot)) => {
+                    self.emit_op(Instruction::TakeLocal(slot), name.span);
+                    Some(slot)
+                }
+                _ => {
+                    self.expr(place);
+                    None
+                }
+            },
+            place => {
+                self.expr(place);
+                None
+            }
+        };
+
+        let (last, path) = steps.split_last().unwrap();
+        for step in path {
+            self.take(step);
+        }
+        match op {
+            Some(op) => {
+                self.take(last);
+                self.emit_op(Instruction::GetLocal(value_slot), span);
+                self.emit_op(binary_instruction(op), span);
+            }
+            None => {
+                self.emit_op(Instruction::GetLocal(value_slot), span);
+            }
+        }
+        self.store(last);
+        for step in path.iter().rev() {
+            self.store(step);
+        }
+
+        match root {
+            Some(slot) => self.emit_op(Instruction::SetLocal(slot), target.span()),
+            None => self.emit_op(Instruction::Pop, target.span()),
+        };
+        self.emit_op(Instruction::Unit, span);
+    }
+
+    /// Pushes the part of the value on top of the stack that `step` leads to.
+    fn take(&mut self, step: &Step) {
+        match step {
+            Step::Field(name, span) => {
+                self.emit_op(Instruction::TakeField(*name), *span);
+            }
+            Step::Index(slot, span) => {
+                self.emit_op(Instruction::GetLocal(*slot), *span);
+                self.emit_op(Instruction::TakeIndex, *span);
+            }
+        }
+    }
+
+    /// Pops the new value of the part that `step` leads to into the value below it.
+    fn store(&mut self, step: &Step) {
+        match step {
+            Step::Field(name, span) => {
+                self.emit_op(Instruction::SetField(*name), *span);
+            }
+            Step::Index(slot, span) => {
+                self.emit_op(Instruction::GetLocal(*slot), *span);
+                self.emit_op(Instruction::SetIndex, *span);
+            }
+        }
+    }
+
+    fn match_expr(&mut self, keyword: &Token, scrutinee: &Expr, arms: &[MatchArm]) {
+        self.expr(scrutinee);
+        let scrutinee_slot = self.temp();
+        self.emit_op(Instruction::SetLocal(scrutinee_slot), scrutinee.span());
+
+        let mut to_end = Vec::new();
+        for arm in arms {
+            let mut to_next_arm = Vec::new();
+            self.pattern(&arm.pattern, scrutinee_slot, &mut to_next_arm);
+            match &arm.body {
+                ArmBody::Expr(expr) => self.expr(expr),
+                ArmBody::Block(block) => {
+                    self.block(block);
+                    self.emit_op(Instruction::Unit, arm.pattern.span());
+                }
+            }
+            to_end.push(self.emit_op(Instruction::Jump(0), arm.pattern.span()));
+            for jump in to_next_arm {
+                self.patch(jump);
+            }
+        }
+
+        self.emit_op(Instruction::GetLocal(scrutinee_slot), keyword.span);
+        self.emit_op(Instruction::NoMatch, keyword.span);
+        for jump in to_end {
+            self.patch(jump);
+        }
+    }
+
+    /// Compiles the test of whether the value in `slot` matches `pattern` and binds its names.
+    /// Every jump taken when the value does not match is added to `to_next_arm`.
+    fn pattern(&mut self, pattern: &Pattern, slot: u32, to_next_arm: &mut Vec<usize>) {
+        let span = pattern.span();
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding(name) => {
+                self.emit_op(Instruction::GetLocal(slot), span);
+                let binding = self.declare(name);
+                self.emit_op(Instruction::SetLocal(binding), span);
+            }
+            Pattern::Literal(expr) => {
+                self.emit_op(Instruction::GetLocal(slot), span);
+                self.expr(expr);
+                self.emit_op(Instruction::Equal, span);
+                to_next_arm.push(self.emit_op(Instruction::JumpIfFalse(0), span));
+            }
+            Pattern::Variant { path, fields } => {
+                self.emit_op(Instruction::GetLocal(slot), span);
+                let name = self.name(&path[path.len() - 1]);
+                self.emit_op(Instruction::IsVariant(name), span);
+                to_next_arm.push(self.emit_op(Instruction::JumpIfFalse(0), span));
+                for (i, field) in fields.iter().enumerate() {
+                    if let Pattern::Wildcard(_) = field {
+                        continue;
+                    }
+                    self.emit_op(Instruction::GetLocal(slot), field.span());
+                    self.emit_op(Instruction::VariantField(i as u32), field.span());
+                    let field_slot = self.temp();
+                    self.emit_op(Instruction::SetLocal(field_slot), field.span());
+                    self.pattern(field, field_slot, to_next_arm);
+                }
+            }
+        }
+    }
+
+    /// Allocates a local slot for the variable declared by `name`.
+    fn declare(&mut self, name: &Token) -> u32 {
+        let slot = self.temp();
+        self.locals.insert(name.span, slot);
+        slot
+    }
+
+    /// Allocates a local slot for a value that has no name in the source.
+    fn temp(&mut self) -> u32 {
+        let slot = self.function.locals;
+        self.function.locals += 1;
+        slot
+    }
+
+    /// Returns the constant index of the string `name`, used for field, method and variant names.
+    fn name(&mut self, name: &Token) -> u32 {
+        self.program
+            .add_constant(Value::String(name.as_ident().to_string()))
+    }
+
+    fn constant(&mut self, value: Value, span: Span) {
+        let index = self.program.add_constant(value);
+        self.emit_op(Instruction::Constant(index), span);
+    }
+
+    fn emit_op(&mut self, instruction: Instruction, span: Span) -> usize {
+        self.function.code.push(instruction);
+        self.function.spans.push(span);
+        self.function.code.len() - 1
+    }
+
+    fn here(&self) -> usize {
+        self.function.code.len()
+    }
+
+    /// Points the jump at `jump` to the next instruction to be emitted.
+    fn patch(&mut self, jump: usize) {
+        self.patch_to(jump, self.here());
+    }
+
+    fn patch_to(&mut self, jump: usize, target: usize) {
+        let target = target as u32;
+        match &mut self.function.code[jump] {
+            Instruction::Jump(to) | Instruction::JumpIfFalse(to) => *to = target,
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn binary_instruction(op: &BinOp) -> Instruction {
+    match op {
+        BinOp::Add => Instruction::Add,
+        BinOp::Subtract => Instruction::Subtract,
+        BinOp::Multiply => Instruction::Multiply,
+        BinOp::Divide => Instruction::Divide,
+        BinOp::Eq => Instruction::Equal,
+        BinOp::NotEq => Instruction::NotEqual,
+        BinOp::LT => Instruction::Less,
+        BinOp::LTOrEq => Instruction::LessEqual,
+        BinOp::GT => Instruction::Greater,
+        BinOp::GTOrEq => Instruction::GreaterEqual,
+        BinOp::And | BinOp::Or => unreachable!(),
+    }
+}
+
+/// The constant for the variant named by `path` with `fields` payload values, filled in by the VM.
+fn variant(path: &[Token], fields: usize) -> Value {
+    Value::Enum {
+        name: path[0].as_ident().to_string(),
+        variant: path[path.len() - 1].as_ident().to_string(),
+        fields: vec![Value::Unit; fields],
+    }
+}
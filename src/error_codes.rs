@@ -0,0 +1,1109 @@
+use std::fmt::Display;
+
+/// Declares the error codes together with their explanations, so that every
+/// code has exactly one entry in the catalogue printed by `--explain`.
+macro_rules! error_codes {
+    ( $(
+        $variant:ident = $code:literal {
+            title: $title:literal,
+            description: $description:literal,
+            wrong: $wrong:literal,
+            fixed: $fixed:literal $(,)?
+        }
+    ),* $(,)? ) => {
+        /// A stable code identifying a kind of diagnostic, such as `E0003`.
+        /// The codes of lints start with `W`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum ErrorCode {
+            $( $variant, )*
+        }
+
+        impl ErrorCode {
+            pub const ALL: &'static [ErrorCode] = &[$( ErrorCode::$variant, )*];
+
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $( ErrorCode::$variant => $code, )*
+                }
+            }
+
+            pub fn explanation(self) -> Explanation {
+                match self {
+                    $( ErrorCode::$variant => Explanation {
+                        title: $title,
+                        description: $description,
+                        wrong: $wrong,
+                        fixed: $fixed,
+                    }, )*
+                }
+            }
+        }
+    };
+}
+
+/// The entry of an error code in the catalogue.
+pub struct Explanation {
+    pub title: &'static str,
+    pub description: &'static str,
+    /// A program that is reported with the code, or nothing for problems
+    /// that do not come from the program.
+    pub wrong: &'static str,
+    /// The same program, corrected.
+    pub fixed: &'static str,
+}
+
+impl ErrorCode {
+    /// Finds the code written as `code`, such as `E0003`, `e3` or `W0001`.
+    pub fn parse(code: &str) -> Option<ErrorCode> {
+        let (prefix, digits) = code.split_at_checked(1)?;
+        let number = digits.parse::<usize>().ok()?;
+        ErrorCode::ALL.iter().copied().find(|code| {
+            let (code_prefix, code_digits) = code.as_str().split_at(1);
+            code_prefix.eq_ignore_ascii_case(prefix) && code_digits.parse() == Ok(number)
+        })
+    }
+
+    /// Prints the explanation of the code, as shown by `--explain`.
+    pub fn explain(self) {
+        let explanation = self.explanation();
+        println!("{}: {}", self.as_str(), explanation.title);
+        println!();
+        println!("{}", explanation.description);
+        // Problems with the build rather than the program have no examples.
+        if explanation.wrong.is_empty() {
+            return;
+        }
+        println!();
+        println!("Erroneous code example:");
+        println!();
+        print_example(explanation.wrong);
+        println!();
+        println!("Corrected:");
+        println!();
+        print_example(explanation.fixed);
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+fn print_example(example: &str) {
+    for line in example.trim_matches('\n').lines() {
+        if line.is_empty() {
+            println!();
+        } else {
+            println!("    {}", line);
+        }
+    }
+}
+
+error_codes! {
+    UnknownToken = "E0001" {
+        title: "Unknown token",
+        description: "\
+A character that does not start any token of the language was found, such as
+`@`, `$` or a single `&`. The logical operators are written `&&` and `||`.",
+        wrong: r#"
+func main() {
+    println(true & false);
+}
+"#,
+        fixed: r#"
+func main() {
+    println(true && false);
+}
+"#,
+    },
+    UnterminatedChar = "E0002" {
+        title: "Unterminated character literal",
+        description: "\
+A character literal holds exactly one character between single quotes. Text
+made of several characters is a string and is written between double quotes.",
+        wrong: r#"
+func main() {
+    println('ok');
+}
+"#,
+        fixed: r#"
+func main() {
+    println("ok");
+}
+"#,
+    },
+    UnknownEscape = "E0003" {
+        title: "Unknown escape",
+        description: "\
+Inside a string, a backslash starts an escape sequence. The known escapes are
+`\\n`, `\\t`, `\\r`, `\\\\`, `\\\"`, `\\xHH` with two hexadecimal digits and
+`\\u{HHHH}` with a Unicode code point. Any other character after a backslash is
+an error. Write `\\\\` for a backslash.",
+        wrong: r#"
+func main() {
+    println("C:\data");
+}
+"#,
+        fixed: r#"
+func main() {
+    println("C:\\data");
+}
+"#,
+    },
+    UnexpectedToken = "E0004" {
+        title: "Unexpected token",
+        description: "\
+The parser found a token where the grammar does not allow one, or reached the
+end of the file too early. Most often a `;`, a closing bracket or a name is
+missing just before the reported location.",
+        wrong: r#"
+func main() {
+    var x = 1
+    println(x);
+}
+"#,
+        fixed: r#"
+func main() {
+    var x = 1;
+    println(x);
+}
+"#,
+    },
+    ExpectedExpression = "E0005" {
+        title: "Expected an expression",
+        description: "\
+An expression was expected, for example after the `=` of a declaration or as an
+operand of an operator, but the code continues with something that is not an
+expression.",
+        wrong: r#"
+func main() {
+    var x = ;
+    println(x);
+}
+"#,
+        fixed: r#"
+func main() {
+    var x = 1;
+    println(x);
+}
+"#,
+    },
+    InvalidAssignmentTarget = "E0006" {
+        title: "Invalid assignment target",
+        description: "\
+Only variables, fields and array elements can be assigned to. The left side of
+`=`, `+=` and the other assignment operators cannot be a call, a literal or any
+other expression.",
+        wrong: r#"
+func one() -> int {
+    return 1;
+}
+
+func main() {
+    one() = 2;
+}
+"#,
+        fixed: r#"
+func one() -> int {
+    return 1;
+}
+
+func main() {
+    var mut x = one();
+    x = 2;
+    println(x);
+}
+"#,
+    },
+    MisplacedSelf = "E0007" {
+        title: "Misplaced `self` parameter",
+        description: "\
+`self` is the instance a method is called on. It is the first parameter of
+every method of a class, and it cannot be a parameter of a function outside of
+a class.",
+        wrong: r#"
+func describe(self) {
+    println("a shape");
+}
+
+func main() {}
+"#,
+        fixed: r#"
+class Shape {
+    func describe(self) {
+        println("a shape");
+    }
+}
+
+func main() {
+    Shape().describe();
+}
+"#,
+    },
+    ModuleNotFound = "E0008" {
+        title: "Module not found",
+        description: "\
+`import` and `use` load another file, relative to the directory of the file
+that imports it. `import \"path.glacier\"` names the file and `use a::b` loads
+`a/b.glacier`. The file must exist. In the examples, the directory of the
+program contains `math.glacier` but no `maths.glacier`.",
+        wrong: r#"
+import "maths.glacier";
+
+func main() {
+    println(maths::square(3));
+}
+"#,
+        fixed: r#"
+import "math.glacier";
+
+func main() {
+    println(math::square(3));
+}
+"#,
+    },
+    ImportCycle = "E0009" {
+        title: "Import cycle",
+        description: "\
+Files import each other, directly or through other files. The items of an
+imported file are declared before those of the file importing it, so imports
+cannot form a cycle. Move what both files need into a third file that both
+import. In the examples, the program is `a.glacier`, and `b.glacier` starts
+with `import \"a.glacier\";`. In the correction, `value` was moved to
+`common.glacier`, which `b.glacier` imports instead of `a.glacier`.",
+        wrong: r#"
+import "b.glacier";
+
+func main() {
+    println(b::value());
+}
+"#,
+        fixed: r#"
+import "common.glacier";
+
+func main() {
+    println(common::value());
+}
+"#,
+    },
+    UndefinedName = "E0010" {
+        title: "Undefined name",
+        description: "\
+A name was used that is not declared in any enclosing scope, or a path such as
+`math::square` names an item that the module does not declare or a module that
+is not imported. Check the spelling, or declare or import the name.",
+        wrong: r#"
+func main() {
+    var count = 3;
+    println(cuont);
+}
+"#,
+        fixed: r#"
+func main() {
+    var count = 3;
+    println(count);
+}
+"#,
+    },
+    DuplicateDefinition = "E0011" {
+        title: "Duplicate definition",
+        description: "\
+The same name is declared twice in one scope: two items of a module, two
+variables of a block, or two fields, methods or variants of one type. Rename
+one of them, or assign to the existing variable instead.",
+        wrong: r#"
+func main() {
+    var total = 1;
+    var total = 2;
+    println(total);
+}
+"#,
+        fixed: r#"
+func main() {
+    var mut total = 1;
+    total = 2;
+    println(total);
+}
+"#,
+    },
+    UseBeforeDeclaration = "E0012" {
+        title: "Use before declaration",
+        description: "\
+A variable or constant is used before the declaration that introduces it. Move
+the declaration before its first use.",
+        wrong: r#"
+func main() {
+    println(x);
+    var x = 1;
+}
+"#,
+        fixed: r#"
+func main() {
+    var x = 1;
+    println(x);
+}
+"#,
+    },
+    AssignToImmutable = "E0013" {
+        title: "Assignment to an immutable value",
+        description: "\
+Variables declared with `var` cannot change after they are initialized, and
+neither can constants, parameters, functions and types, nor the fields and
+elements of immutable values. Declare the variable with `var mut`, or copy a
+parameter into a `var mut` first.",
+        wrong: r#"
+func main() {
+    var count = 0;
+    count += 1;
+    println(count);
+}
+"#,
+        fixed: r#"
+func main() {
+    var mut count = 0;
+    count += 1;
+    println(count);
+}
+"#,
+    },
+    PrivateItem = "E0014" {
+        title: "Private item",
+        description: "\
+The items of a module are private to it unless they are declared with `pub`.
+Other modules can only use its public items. The examples show `math.glacier`,
+which is imported by a program that calls `math::square(3)`.",
+        wrong: r#"
+func square(x: int) -> int {
+    return x * x;
+}
+"#,
+        fixed: r#"
+pub func square(x: int) -> int {
+    return x * x;
+}
+"#,
+    },
+    OutsideLoop = "E0015" {
+        title: "`break` or `continue` outside of a loop",
+        description: "\
+`break` and `continue` leave or restart the innermost loop, so they can only be
+used inside the body of a `while` or `for` loop.",
+        wrong: r#"
+func main() {
+    var done = true;
+    if done {
+        break;
+    }
+}
+"#,
+        fixed: r#"
+func main() {
+    var done = true;
+    while true {
+        if done {
+            break;
+        }
+    }
+}
+"#,
+    },
+    NonConstantInitializer = "E0016" {
+        title: "Non-constant initializer",
+        description: "\
+The value of a constant is known before the program runs, so it can only be
+made of literals, other constants and operators, not of variables or calls.",
+        wrong: r#"
+func limit() -> int {
+    return 10;
+}
+
+const MAX = limit();
+
+func main() {
+    println(MAX);
+}
+"#,
+        fixed: r#"
+const MAX = 10;
+
+func main() {
+    println(MAX);
+}
+"#,
+    },
+    UnknownType = "E0017" {
+        title: "Unknown type",
+        description: "\
+A type annotation names a type that does not exist. The primitive types are
+`int`, `float`, `bool`, `char` and `string`, arrays are written `[T]`, and the
+structs, classes and enums of the program can be used by name.",
+        wrong: r#"
+func main() {
+    var x: integer = 1;
+    println(x);
+}
+"#,
+        fixed: r#"
+func main() {
+    var x: int = 1;
+    println(x);
+}
+"#,
+    },
+    MismatchedTypes = "E0018" {
+        title: "Mismatched types",
+        description: "\
+A value of one type was used where another type is expected, for example as an
+argument, a return value, an initializer or a condition.",
+        wrong: r#"
+func main() {
+    var x: int = "one";
+    println(x);
+}
+"#,
+        fixed: r#"
+func main() {
+    var x: int = 1;
+    println(x);
+}
+"#,
+    },
+    InvalidOperands = "E0019" {
+        title: "Invalid operand types",
+        description: "\
+The operator is not defined for the types of its operands. Arithmetic needs two
+numbers of the same type, and `!`, `&&` and `||` need `bool`s.",
+        wrong: r#"
+func main() {
+    println(1 + true);
+}
+"#,
+        fixed: r#"
+func main() {
+    println(1 + 1);
+}
+"#,
+    },
+    ArgumentCount = "E0020" {
+        title: "Wrong number of arguments",
+        description: "\
+A function, method or class was called with a different number of arguments
+than it has parameters. A class is called with the parameters of its `init`
+method, after `self`.",
+        wrong: r#"
+func add(a: int, b: int) -> int {
+    return a + b;
+}
+
+func main() {
+    println(add(1));
+}
+"#,
+        fixed: r#"
+func add(a: int, b: int) -> int {
+    return a + b;
+}
+
+func main() {
+    println(add(1, 2));
+}
+"#,
+    },
+    UnknownField = "E0021" {
+        title: "Unknown field",
+        description: "\
+A field was accessed that the type of the value does not have, or a struct
+literal names a field that the struct does not declare or a type that is not a
+struct.",
+        wrong: r#"
+struct Point { x: int, y: int }
+
+func main() {
+    var p = Point { x: 1, y: 2 };
+    println(p.z);
+}
+"#,
+        fixed: r#"
+struct Point { x: int, y: int }
+
+func main() {
+    var p = Point { x: 1, y: 2 };
+    println(p.y);
+}
+"#,
+    },
+    MissingFields = "E0022" {
+        title: "Missing fields",
+        description: "A struct literal must give a value to every field of the struct.",
+        wrong: r#"
+struct Point { x: int, y: int }
+
+func main() {
+    var p = Point { x: 1 };
+    println(p.x);
+}
+"#,
+        fixed: r#"
+struct Point { x: int, y: int }
+
+func main() {
+    var p = Point { x: 1, y: 0 };
+    println(p.x);
+}
+"#,
+    },
+    UnknownMethod = "E0023" {
+        title: "Unknown method",
+        description: "\
+A method was called that the type of the value does not have. Methods are
+declared inside classes, and a field cannot be called like a method.",
+        wrong: r#"
+class Counter {
+    count: int,
+
+    func get(self) -> int {
+        return self.count;
+    }
+}
+
+func main() {
+    var c = Counter();
+    println(c.value());
+}
+"#,
+        fixed: r#"
+class Counter {
+    count: int,
+
+    func get(self) -> int {
+        return self.count;
+    }
+}
+
+func main() {
+    var c = Counter();
+    println(c.get());
+}
+"#,
+    },
+    NotCallable = "E0024" {
+        title: "Call of a value that is not a function",
+        description: "\
+Only functions, methods and classes can be called. A variable or any other
+expression that is not a function cannot be followed by `(...)`.",
+        wrong: r#"
+func main() {
+    var n = 3;
+    println(n());
+}
+"#,
+        fixed: r#"
+func main() {
+    var n = 3;
+    println(n);
+}
+"#,
+    },
+    NotAValue = "E0025" {
+        title: "Item used as a value",
+        description: "\
+Functions, structs, classes and enums are not values. Call a function to use
+its result, create a struct with `Name { ... }`, an instance of a class with
+`Name(...)` and an enum value with one of its variants.",
+        wrong: r#"
+func five() -> int {
+    return 5;
+}
+
+func main() {
+    var x = five;
+    println(x);
+}
+"#,
+        fixed: r#"
+func five() -> int {
+    return 5;
+}
+
+func main() {
+    var x = five();
+    println(x);
+}
+"#,
+    },
+    InvalidVariant = "E0026" {
+        title: "Invalid enum variant",
+        description: "\
+A path with `::` must name a variant of an enum, and a variant must be given as
+many values, or be matched with as many patterns, as it has fields.",
+        wrong: r#"
+enum Shape { Circle(float), Empty }
+
+func main() {
+    var s = Shape::Square(1.0);
+    println(s);
+}
+"#,
+        fixed: r#"
+enum Shape { Circle(float), Empty }
+
+func main() {
+    var s = Shape::Circle(1.0);
+    println(s);
+}
+"#,
+    },
+    NonExhaustiveMatch = "E0027" {
+        title: "Non-exhaustive match",
+        description: "\
+A `match` must handle every possible value of the matched expression. Add arms
+for the patterns that are not covered, or a `_` arm for all the others.",
+        wrong: r#"
+enum Shape { Circle(float), Empty }
+
+func area(s: Shape) -> float {
+    return match s {
+        Shape::Circle(r) => 3.14 * r * r,
+    };
+}
+
+func main() {
+    println(area(Shape::Empty));
+}
+"#,
+        fixed: r#"
+enum Shape { Circle(float), Empty }
+
+func area(s: Shape) -> float {
+    return match s {
+        Shape::Circle(r) => 3.14 * r * r,
+        Shape::Empty => 0.0,
+    };
+}
+
+func main() {
+    println(area(Shape::Empty));
+}
+"#,
+    },
+    MissingReturn = "E0028" {
+        title: "Missing return",
+        description: "\
+A function with a return type must end with a `return` on every path through
+its body, including every branch of an `if`.",
+        wrong: r#"
+func sign(x: int) -> int {
+    if x < 0 {
+        return -1;
+    }
+}
+
+func main() {
+    println(sign(5));
+}
+"#,
+        fixed: r#"
+func sign(x: int) -> int {
+    if x < 0 {
+        return -1;
+    }
+    return 1;
+}
+
+func main() {
+    println(sign(5));
+}
+"#,
+    },
+    InitReturnsValue = "E0029" {
+        title: "Constructor with a return type",
+        description: "\
+The `init` method of a class sets up a new instance when the class is called.
+The call always produces the instance, so `init` cannot return a value.",
+        wrong: r#"
+class Counter {
+    count: int,
+
+    func init(self, start: int) -> int {
+        self.count = start;
+        return start;
+    }
+}
+
+func main() {
+    println(Counter(1).count);
+}
+"#,
+        fixed: r#"
+class Counter {
+    count: int,
+
+    func init(self, start: int) {
+        self.count = start;
+    }
+}
+
+func main() {
+    println(Counter(1).count);
+}
+"#,
+    },
+    RecursiveType = "E0030" {
+        title: "Recursive type",
+        description: "\
+A struct or enum contains itself, directly or through other structs and enums,
+so its values would be infinitely large. Break the cycle with an array, which
+can be empty.",
+        wrong: r#"
+struct Node { value: int, next: Node }
+
+func main() {}
+"#,
+        fixed: r#"
+struct Node { value: int, next: [Node] }
+
+func main() {}
+"#,
+    },
+    AnnotationsNeeded = "E0031" {
+        title: "Type annotations needed",
+        description: "\
+The type of a variable cannot be inferred, because it has no initial value or
+its initial value is an empty array. Give it a type. Variables of a class type
+must always be initialized, since instances have no default value.",
+        wrong: r#"
+func main() {
+    var items = [];
+    println(len(items));
+}
+"#,
+        fixed: r#"
+func main() {
+    var items: [int] = [];
+    println(len(items));
+}
+"#,
+    },
+    IntegerTooLarge = "E0032" {
+        title: "Integer literal too large",
+        description: "\
+Integer literals must fit in an `int`, a signed 64-bit integer whose largest
+value is 9223372036854775807. Use a `float` for larger numbers.",
+        wrong: r#"
+func main() {
+    println(10000000000000000000);
+}
+"#,
+        fixed: r#"
+func main() {
+    println(10000000000000000000.0);
+}
+"#,
+    },
+    NotIndexable = "E0033" {
+        title: "Index into a value that is not an array",
+        description: "Only arrays can be indexed with `[...]`.",
+        wrong: r#"
+func main() {
+    var n = 5;
+    println(n[0]);
+}
+"#,
+        fixed: r#"
+func main() {
+    var n = [5];
+    println(n[0]);
+}
+"#,
+    },
+    NoMain = "E0034" {
+        title: "No `main` function",
+        description: "\
+A program starts by calling its `main` function, which must be declared in the
+file that is run or built.",
+        wrong: r#"
+func start() {
+    println("Hello");
+}
+"#,
+        fixed: r#"
+func main() {
+    println("Hello");
+}
+"#,
+    },
+    DivisionByZero = "E0035" {
+        title: "Division by zero",
+        description: "\
+Dividing an `int` by zero stops the program with an error. When the divisor is
+always zero, the optimizer also warns about it before the program runs. Check
+the divisor first.",
+        wrong: r#"
+func main() {
+    var d = 0;
+    println(10 / d);
+}
+"#,
+        fixed: r#"
+func main() {
+    var d = 0;
+    if d != 0 {
+        println(10 / d);
+    }
+}
+"#,
+    },
+    IndexOutOfBounds = "E0036" {
+        title: "Index out of bounds",
+        description: "\
+An array was indexed with a negative number or a number that is not less than
+its length. The elements of an array `a` are at the indices `0` to
+`len(a) - 1`.",
+        wrong: r#"
+func main() {
+    var a = [1, 2, 3];
+    println(a[3]);
+}
+"#,
+        fixed: r#"
+func main() {
+    var a = [1, 2, 3];
+    println(a[2]);
+}
+"#,
+    },
+    UnknownLint = "E0037" {
+        title: "Unknown lint",
+        description: "\
+`#allow(...)` names lints that are not reported in the file it is written in.
+The lints are `unused_variable`, `unused_parameter`, `unreachable_code`,
+`shadowed_binding` and `non_snake_case`. Unknown names are ignored with this
+warning.",
+        wrong: r#"
+#allow(unused_variables)
+
+func main() {
+    var x = 1;
+}
+"#,
+        fixed: r#"
+#allow(unused_variable)
+
+func main() {
+    var x = 1;
+}
+"#,
+    },
+    MainParameters = "E0038" {
+        title: "`main` takes parameters",
+        description: "\
+The program starts by calling `main` without any arguments, so `main` cannot
+declare parameters. Values that `main` needs are computed inside it or passed
+on to the functions it calls.",
+        wrong: r#"
+func main(x: int) {
+    println(x);
+}
+"#,
+        fixed: r#"
+func main() {
+    var x = 1;
+    println(x);
+}
+"#,
+    },
+    StackOverflow = "E0039" {
+        title: "Stack overflow",
+        description: "\
+Calls were nested too deeply, which usually means that a recursive function
+never reaches the case that stops the recursion. The program is stopped when
+calls are nested more than 10000 deep.",
+        wrong: r#"
+func count(n: int) -> int {
+    return count(n + 1);
+}
+
+func main() {
+    println(count(0));
+}
+"#,
+        fixed: r#"
+func count(n: int) -> int {
+    if n == 100 {
+        return n;
+    }
+    return count(n + 1);
+}
+
+func main() {
+    println(count(0));
+}
+"#,
+    },
+    UnsupportedByBackend = "E0040" {
+        title: "Not supported by the Cranelift backend",
+        description: "\
+The Cranelift backend, used by `--jit` and `build --object`, compiles only
+part of the language so far. A program that uses anything else can still be
+run by the interpreter or the VM, and built through C by `build` without
+`--object`.",
+        wrong: r#"
+func main() {
+    var a = [1, 2, 3];
+    println(a[0]);
+}
+"#,
+        fixed: r#"
+func main() {
+    var a = 1;
+    println(a);
+}
+"#,
+    },
+    InternalCompilerError = "E0041" {
+        title: "Internal compiler error",
+        description: "\
+The compiler produced code that is invalid, which is a bug in the compiler
+rather than in the program. The IR verifier, Cranelift or the C compiler
+rejected what a backend generated. Please report it together with the program
+that caused it. Another backend may still run the program.",
+        wrong: "",
+        fixed: "",
+    },
+    CCompilerNotFound = "E0042" {
+        title: "The C compiler could not be run",
+        description: "\
+`build` turns the program into an executable with the system C compiler, which
+is run as `cc`. Install a C compiler, such as GCC or Clang, and make sure that
+`cc` is on the `PATH`.",
+        wrong: "",
+        fixed: "",
+    },
+    CannotWriteFile = "E0043" {
+        title: "A file could not be written",
+        description: "\
+`build` could not write the executable or a file it was asked to keep, such
+as `OUT.c` with `--emit-c` or `OUT.o` with `--object`. The directory may not
+exist or may not be writable. A kept file that already exists is never
+replaced: remove it first, or choose another output with `-o`.",
+        wrong: "",
+        fixed: "",
+    },
+    UnusedVariable = "W0001" {
+        title: "Unused variable",
+        description: "\
+A local variable or constant is declared but never used. It may be left over
+from an earlier change, or another name may have been used by mistake. Names
+starting with `_` are not reported. This is the `unused_variable` lint, which
+`-A`, `-W`, `-D` and `#allow(unused_variable)` configure.",
+        wrong: r#"
+func main() {
+    var total = 1 + 2;
+    println(3);
+}
+"#,
+        fixed: r#"
+func main() {
+    var total = 1 + 2;
+    println(total);
+}
+"#,
+    },
+    UnusedParameter = "W0002" {
+        title: "Unused parameter",
+        description: "\
+A parameter of a function is never used in its body. Remove it, or name it
+with a leading `_` if it is needed for another reason. This is the
+`unused_parameter` lint.",
+        wrong: r#"
+func square(x: int, y: int) -> int {
+    return x * x;
+}
+
+func main() {
+    println(square(2, 3));
+}
+"#,
+        fixed: r#"
+func square(x: int) -> int {
+    return x * x;
+}
+
+func main() {
+    println(square(2));
+}
+"#,
+    },
+    UnreachableCode = "W0003" {
+        title: "Unreachable code",
+        description: "\
+A statement comes after a `return`, `break` or `continue`, or after an `if`
+or `match` that leaves the block on every path, so it never runs. This is the
+`unreachable_code` lint.",
+        wrong: r#"
+func main() {
+    println(1);
+    return;
+    println(2);
+}
+"#,
+        fixed: r#"
+func main() {
+    println(1);
+    println(2);
+}
+"#,
+    },
+    ShadowedBinding = "W0004" {
+        title: "Shadowed binding",
+        description: "\
+A local variable has the same name as a variable of an enclosing scope, which
+cannot be used while the new one is in scope. This is the `shadowed_binding`
+lint.",
+        wrong: r#"
+func main() {
+    var x = 1;
+    if x > 0 {
+        var x = 2;
+        println(x);
+    }
+    println(x);
+}
+"#,
+        fixed: r#"
+func main() {
+    var x = 1;
+    if x > 0 {
+        var y = 2;
+        println(y);
+    }
+    println(x);
+}
+"#,
+    },
+    NonSnakeCase = "W0005" {
+        title: "Name not in snake case",
+        description: "\
+Functions and methods are named in snake case: lowercase words separated by
+underscores. This is the `non_snake_case` lint.",
+        wrong: r#"
+func addOne(x: int) -> int {
+    return x + 1;
+}
+
+func main() {
+    println(addOne(1));
+}
+"#,
+        fixed: r#"
+func add_one(x: int) -> int {
+    return x + 1;
+}
+
+func main() {
+    println(add_one(1));
+}
+"#,
+    },
+}
@@ -0,0 +1,181 @@
+use std::iter;
+
+use crate::{
+    ast::{Expr, Pattern, UnaryOp},
+    types::{Type, TypeInfo},
+    utils::TokenType as Ty,
+};
+
+/// A pattern reduced to what matters when looking for unmatched values.
+#[derive(Debug, Clone)]
+enum Pat {
+    /// Matches every value: `_`, a binding, or a pattern that failed to type check.
+    Wild,
+    Ctor(Ctor, Vec<Pat>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ctor {
+    Bool(bool),
+    /// An enum variant, by its full path such as `Shape::Circle`.
+    Variant(String),
+    /// A literal of a type with too many values to list, by its source text.
+    Literal(String),
+}
+
+/// Returns the values that no arm of a `match` on a value of type `ty` matches,
+/// written as patterns. For enums, every variant that is not fully covered is listed.
+pub fn missing_patterns(patterns: &[&Pattern], ty: &Type, info: &TypeInfo) -> Vec<String> {
+    let rows: Vec<Vec<Pat>> = patterns
+        .iter()
+        .map(|pattern| vec![lower(pattern)])
+        .collect();
+
+    match constructors(ty, info) {
+        Some(all) => all
+            .into_iter()
+            .filter_map(|(ctor, field_tys)| {
+                let rows = specialize(&rows, &ctor, field_tys.len());
+                let fields = witness(&rows, &field_tys, info)?;
+                Some(format_pat(&Pat::Ctor(ctor, fields)))
+            })
+            .collect(),
+        None => witness(&rows, std::slice::from_ref(ty), info)
+            .map(|missing| format_pat(&missing[0]))
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn lower(pattern: &Pattern) -> Pat {
+    match pattern {
+        Pattern::Wildcard(_) | Pattern::Binding(_) => Pat::Wild,
+        Pattern::Literal(Expr::Literal(literal)) => match &literal.ty {
+            Ty::Bool(bool) => Pat::Ctor(Ctor::Bool(*bool), Vec::new()),
+            ty => Pat::Ctor(Ctor::Literal(ty.to_string()), Vec::new()),
+        },
+        Pattern::Literal(Expr::Unary {
+            op: UnaryOp::Negative,
+            rhs,
+        }) => match rhs.as_ref() {
+            Expr::Literal(literal) => {
+                Pat::Ctor(Ctor::Literal(format!("-{}", literal.ty)), Vec::new())
+            }
+            _ => Pat::Wild,
+        },
+        Pattern::Literal(_) => Pat::Wild,
+        Pattern::Variant { path, fields } => {
+            let path: Vec<String> = path.iter().map(|segment| segment.ty.to_string()).collect();
+            Pat::Ctor(
+                Ctor::Variant(path.join("::")),
+                fields.iter().map(lower).collect(),
+            )
+        }
+    }
+}
+
+/// Lists every constructor of `ty` with the types of its fields, or `None` if
+/// there are too many to list and only a wildcard can cover them all.
+fn constructors(ty: &Type, info: &TypeInfo) -> Option<Vec<(Ctor, Vec<Type>)>> {
+    match ty {
+        Type::Bool => Some(vec![
+            (Ctor::Bool(true), Vec::new()),
+            (Ctor::Bool(false), Vec::new()),
+        ]),
+        Type::Enum(name) => Some(
+            info.enum_variants(name)
+                .unwrap_or_default()
+                .iter()
+                .map(|(variant, fields)| {
+                    (
+                        Ctor::Variant(format!("{}::{}", name, variant)),
+                        fields.clone(),
+                    )
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Keeps the rows whose first pattern can match `ctor`, replacing that pattern with its fields.
+fn specialize(rows: &[Vec<Pat>], ctor: &Ctor, arity: usize) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let mut fields = match &row[0] {
+                Pat::Wild => vec![Pat::Wild; arity],
+                Pat::Ctor(head, fields) if head == ctor => fields.clone(),
+                Pat::Ctor(..) => return None,
+            };
+            // Patterns with the wrong number of fields were already reported.
+            fields.resize(arity, Pat::Wild);
+            fields.extend_from_slice(&row[1..]);
+            Some(fields)
+        })
+        .collect()
+}
+
+/// Finds a list of values, one per type in `tys`, that none of `rows` matches.
+fn witness(rows: &[Vec<Pat>], tys: &[Type], info: &TypeInfo) -> Option<Vec<Pat>> {
+    let Some((ty, rest_tys)) = tys.split_first() else {
+        return rows.is_empty().then(Vec::new);
+    };
+
+    let heads: Vec<&Ctor> = rows
+        .iter()
+        .filter_map(|row| match &row[0] {
+            Pat::Ctor(ctor, _) => Some(ctor),
+            Pat::Wild => None,
+        })
+        .collect();
+    let all = constructors(ty, info);
+
+    if let Some(all) = &all {
+        if all.iter().all(|(ctor, _)| heads.contains(&ctor)) {
+            // Every constructor is listed, so the value is missed only if one of them is.
+            for (ctor, field_tys) in all {
+                let rows = specialize(rows, ctor, field_tys.len());
+                let tys: Vec<Type> = field_tys.iter().chain(rest_tys).cloned().collect();
+                if let Some(mut fields) = witness(&rows, &tys, info) {
+                    let rest = fields.split_off(field_tys.len());
+                    let head = Pat::Ctor(ctor.clone(), fields);
+                    return Some(iter::once(head).chain(rest).collect());
+                }
+            }
+            return None;
+        }
+    }
+
+    // Some constructor is never listed, so only rows starting with a wildcard can match it.
+    let defaults: Vec<Vec<Pat>> = rows
+        .iter()
+        .filter(|row| matches!(row[0], Pat::Wild))
+        .map(|row| row[1..].to_vec())
+        .collect();
+    let rest = witness(&defaults, rest_tys, info)?;
+    let head = match all {
+        Some(all) => {
+            let (ctor, field_tys) = all
+                .into_iter()
+                .find(|(ctor, _)| !heads.contains(&ctor))
+                .unwrap();
+            Pat::Ctor(ctor, vec![Pat::Wild; field_tys.len()])
+        }
+        None => Pat::Wild,
+    };
+
+    Some(iter::once(head).chain(rest).collect())
+}
+
+fn format_pat(pat: &Pat) -> String {
+    match pat {
+        Pat::Wild => String::from("_"),
+        Pat::Ctor(Ctor::Bool(bool), _) => bool.to_string(),
+        Pat::Ctor(Ctor::Literal(literal), _) => literal.clone(),
+        Pat::Ctor(Ctor::Variant(path), fields) if fields.is_empty() => path.clone(),
+        Pat::Ctor(Ctor::Variant(path), fields) => {
+            let fields: Vec<String> = fields.iter().map(format_pat).collect();
+            format!("{}({})", path, fields.join(", "))
+        }
+    }
+}
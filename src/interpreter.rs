@@ -0,0 +1,669 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    ast::{ArmBody, Ast, BinOp, Block, Class, Expr, Function, Item, Pattern, Statement, UnaryOp},
+    builtins::Builtin,
+    compiler::Compiler,
+    diag,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    resolver::{Resolutions, SymbolKind},
+    types::TypeInfo,
+    utils::{Span, Token, TokenType as Ty},
+    value::{binary_op, element_index, out_of_bounds, stack_overflow, Value, MAX_CALL_DEPTH},
+};
+
+/// Anything that stops the normal, statement-by-statement execution of a block.
+enum Unwind {
+    Return(Value),
+    Break,
+    Continue,
+    Error(Diagnostic),
+}
+
+impl From<Diagnostic> for Unwind {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Unwind::Error(diagnostic)
+    }
+}
+
+type EvalResult<T> = Result<T, Unwind>;
+
+/// A change applied in place to the storage of an assignment target.
+type Update<'u> = dyn FnMut(&mut Value) -> EvalResult<()> + 'u;
+
+/// A tree-walking interpreter that executes a program starting from its `main` function.
+pub struct Interpreter<'a> {
+    compiler: &'a Compiler,
+    resolutions: &'a Resolutions,
+    types: &'a TypeInfo,
+    functions: HashMap<&'a str, &'a Function>,
+    classes: HashMap<&'a str, &'a Class>,
+    globals: HashMap<String, Value>,
+    scopes: Vec<HashMap<String, Value>>,
+    /// The number of calls in progress.
+    depth: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(compiler: &'a Compiler, resolutions: &'a Resolutions, types: &'a TypeInfo) -> Self {
+        Self {
+            compiler,
+            resolutions,
+            types,
+            functions: HashMap::new(),
+            classes: HashMap::new(),
+            globals: HashMap::new(),
+            scopes: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    pub fn run(mut self, ast: &'a Ast) {
+        for item in &ast.items {
+            match item {
+                Item::Func(function) => {
+                    self.functions.insert(function.name.as_ident(), function);
+                }
+                Item::Class(class) => {
+                    self.classes.insert(class.name.as_ident(), class);
+                }
+                _ => {}
+            }
+        }
+
+        for item in &ast.items {
+            if let Item::Const(constant) = item {
+                match self.eval_expr(&constant.expr) {
+                    Ok(value) => {
+                        self.globals
+                            .insert(constant.name.as_ident().to_string(), value);
+                    }
+                    Err(Unwind::Error(diagnostic)) => {
+                        self.compiler.reporter.borrow_mut().add(diagnostic);
+                        return;
+                    }
+                    Err(_) => unreachable!(),
+                }
+            }
+        }
+
+        let main = match self.functions.get("main") {
+            Some(main) => *main,
+            None => {
+                self.compiler.reporter.borrow_mut().add(
+                    diag!(
+                        "No `main` function found.",
+                        "Add a `func main() { ... }` to run this program.",
+                        Span::default()
+                    )
+                    .with_code(ErrorCode::NoMain),
+                );
+                return;
+            }
+        };
+
+        if let Err(Unwind::Error(diagnostic)) =
+            self.call_function(main, None, Vec::new(), main.name.span)
+        {
+            self.compiler.reporter.borrow_mut().add(diagnostic);
+        }
+    }
+
+    fn call(&mut self, name: &Token, args: Vec<Value>) -> EvalResult<Value> {
+        match self.resolutions.get(name).map(|symbol| symbol.kind) {
+            Some(SymbolKind::Function) => {
+                let function = self.functions[name.as_ident()];
+                self.call_function(function, None, args, name.span)
+            }
+            Some(SymbolKind::Class) => {
+                let object = Value::new_object(name.as_ident(), self.types);
+                match self.classes[name.as_ident()].method("init") {
+                    Some(init) => {
+                        self.call_function(init, Some(object.clone()), args, name.span)?;
+                    }
+                    None if !args.is_empty() => {
+                        return Err(self.error(
+                            ErrorCode::ArgumentCount,
+                            format!("Class `{}` takes no arguments.", name.ty),
+                            name.span,
+                        ));
+                    }
+                    None => {}
+                }
+                Ok(object)
+            }
+            Some(SymbolKind::Builtin) => {
+                Ok(Builtin::from_name(name.as_ident()).unwrap().call(&args))
+            }
+            _ => Err(self.error(
+                ErrorCode::NotCallable,
+                format!("`{}` is not a function.", name.ty),
+                name.span,
+            )),
+        }
+    }
+
+    fn call_method(
+        &mut self,
+        object: Value,
+        method: &Token,
+        args: Vec<Value>,
+    ) -> EvalResult<Value> {
+        let function = match &object {
+            Value::Object(instance) => {
+                let class = instance.borrow().type_name();
+                self.classes[class.as_str()].method(method.as_ident())
+            }
+            _ => None,
+        };
+
+        match function {
+            Some(function) => self.call_function(function, Some(object), args, method.span),
+            None => Err(self.error(
+                ErrorCode::UnknownMethod,
+                format!(
+                    "No method `{}` on type `{}`.",
+                    method.ty,
+                    object.type_name()
+                ),
+                method.span,
+            )),
+        }
+    }
+
+    fn call_function(
+        &mut self,
+        function: &Function,
+        receiver: Option<Value>,
+        args: Vec<Value>,
+        span: Span,
+    ) -> EvalResult<Value> {
+        if function.params.len() != args.len() {
+            return Err(self.error(
+                ErrorCode::ArgumentCount,
+                format!(
+                    "Function `{}` takes {} argument(s) but {} were supplied.",
+                    function.name.ty,
+                    function.params.len(),
+                    args.len()
+                ),
+                span,
+            ));
+        }
+
+        let mut frame = HashMap::new();
+        if let (Some(self_param), Some(receiver)) = (&function.self_param, receiver) {
+            frame.insert(self_param.as_ident().to_string(), receiver);
+        }
+        for (param, arg) in function.params.iter().zip(args) {
+            frame.insert(param.name.as_ident().to_string(), arg);
+        }
+
+        if self.depth == MAX_CALL_DEPTH {
+            return Err(Unwind::Error(stack_overflow(span)));
+        }
+        self.depth += 1;
+        let caller_scopes = std::mem::replace(&mut self.scopes, vec![frame]);
+        let result = self.exec_block(&function.body);
+        self.scopes = caller_scopes;
+        self.depth -= 1;
+
+        match result {
+            Ok(()) => Ok(Value::Unit),
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(unwind) => Err(unwind),
+        }
+    }
+
+    fn exec_block(&mut self, block: &Block) -> EvalResult<()> {
+        self.scopes.push(HashMap::new());
+        let result = block.0.iter().try_for_each(|stmt| self.exec_stmt(stmt));
+        self.scopes.pop();
+        result
+    }
+
+    fn exec_stmt(&mut self, stmt: &Statement) -> EvalResult<()> {
+        match stmt {
+            Statement::VarDecl { name, expr, .. } => {
+                let value = match expr {
+                    Expr::None => Value::default_for(self.types.binding(name), self.types),
+                    expr => self.eval_expr(expr)?,
+                };
+                self.define(name, value);
+            }
+            Statement::Const(constant) => {
+                let value = self.eval_expr(&constant.expr)?;
+                self.define(&constant.name, value);
+            }
+            Statement::Return { expr, .. } => {
+                return Err(Unwind::Return(self.eval_expr(expr)?));
+            }
+            Statement::Expression(expr) => {
+                self.eval_expr(expr)?;
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.eval_condition(condition)? {
+                    self.exec_block(then_branch)?;
+                } else if let Some(else_branch) = else_branch {
+                    self.exec_block(else_branch)?;
+                }
+            }
+            Statement::While { condition, body } => {
+                while self.eval_condition(condition)? {
+                    match self.exec_block(body) {
+                        Ok(()) | Err(Unwind::Continue) => {}
+                        Err(Unwind::Break) => break,
+                        Err(unwind) => return Err(unwind),
+                    }
+                }
+            }
+            Statement::For { var, range, body } => {
+                let Expr::Range {
+                    start,
+                    end,
+                    inclusive,
+                } = range
+                else {
+                    unreachable!()
+                };
+                let start = self.eval_int(start)?;
+                let end = self.eval_int(end)?;
+                let last = if *inclusive {
+                    Some(end)
+                } else {
+                    end.checked_sub(1)
+                };
+
+                for i in last.map(|last| start..=last).into_iter().flatten() {
+                    self.scopes
+                        .push(HashMap::from([(var.as_ident().to_string(), Value::Int(i))]));
+                    let result = self.exec_block(body);
+                    self.scopes.pop();
+                    match result {
+                        Ok(()) | Err(Unwind::Continue) => {}
+                        Err(Unwind::Break) => break,
+                        Err(unwind) => return Err(unwind),
+                    }
+                }
+            }
+            Statement::Break(_) => return Err(Unwind::Break),
+            Statement::Continue(_) => return Err(Unwind::Continue),
+            Statement::Unknown => {}
+        }
+
+        Ok(())
+    }
+
+    fn eval_condition(&mut self, condition: &Expr) -> EvalResult<bool> {
+        match self.eval_expr(condition)? {
+            Value::Bool(bool) => Ok(bool),
+            value => Err(self.error(
+                ErrorCode::MismatchedTypes,
+                format!(
+                    "Expected a `bool` condition, found `{}`.",
+                    value.type_name()
+                ),
+                condition.span(),
+            )),
+        }
+    }
+
+    fn eval_int(&mut self, expr: &Expr) -> EvalResult<i64> {
+        match self.eval_expr(expr)? {
+            Value::Int(int) => Ok(int),
+            value => Err(self.error(
+                ErrorCode::MismatchedTypes,
+                format!("Expected an `int`, found `{}`.", value.type_name()),
+                expr.span(),
+            )),
+        }
+    }
+
+    fn eval_expr(&mut self, expr: &Expr) -> EvalResult<Value> {
+        match expr {
+            Expr::Binary {
+                lhs,
+                op: op @ (BinOp::And | BinOp::Or),
+                rhs,
+            } => {
+                // `&&` and `||` only evaluate their right-hand side when it decides the result.
+                let lhs_value = self.eval_condition(lhs)?;
+                if lhs_value == (*op == BinOp::Or) {
+                    Ok(Value::Bool(lhs_value))
+                } else {
+                    Ok(Value::Bool(self.eval_condition(rhs)?))
+                }
+            }
+            Expr::Binary { lhs, op, rhs } => {
+                let lhs_value = self.eval_expr(lhs)?;
+                let rhs_value = self.eval_expr(rhs)?;
+                Ok(binary_op(op, lhs_value, rhs_value, expr.span())?)
+            }
+            Expr::Unary { op, rhs } => {
+                let value = self.eval_expr(rhs)?;
+                match (op, value) {
+                    (UnaryOp::Negative, Value::Int(int)) => Ok(Value::Int(int.wrapping_neg())),
+                    (UnaryOp::Negative, Value::Float(float)) => Ok(Value::Float(-float)),
+                    (UnaryOp::Negate, Value::Bool(bool)) => Ok(Value::Bool(!bool)),
+                    (op, value) => Err(self.error(
+                        ErrorCode::InvalidOperands,
+                        format!(
+                            "Cannot apply unary operator `{}` to type `{}`.",
+                            op,
+                            value.type_name()
+                        ),
+                        rhs.span(),
+                    )),
+                }
+            }
+            Expr::Literal(literal) => self.literal(literal),
+            Expr::Var(name) => self.lookup(name),
+            Expr::Grouping(expr) => self.eval_expr(expr),
+            Expr::Call { callee, args } => match callee.as_ref() {
+                Expr::Var(name) => {
+                    let args = self.eval_args(args)?;
+                    self.call(name, args)
+                }
+                Expr::Field { object, field } => {
+                    let object = self.eval_expr(object)?;
+                    let args = self.eval_args(args)?;
+                    self.call_method(object, field, args)
+                }
+                Expr::Path(path) => {
+                    let fields = self.eval_args(args)?;
+                    Ok(variant(path, fields))
+                }
+                _ => Err(self.error(
+                    ErrorCode::NotCallable,
+                    "Expression is not callable.",
+                    callee.span(),
+                )),
+            },
+            Expr::Path(path) => Ok(variant(path, Vec::new())),
+            Expr::Match {
+                keyword,
+                scrutinee,
+                arms,
+            } => {
+                let value = self.eval_expr(scrutinee)?;
+                for arm in arms {
+                    let mut bindings = HashMap::new();
+                    if !self.match_pattern(&arm.pattern, &value, &mut bindings)? {
+                        continue;
+                    }
+                    self.scopes.push(bindings);
+                    let result = match &arm.body {
+                        ArmBody::Expr(expr) => self.eval_expr(expr),
+                        ArmBody::Block(block) => self.exec_block(block).map(|()| Value::Unit),
+                    };
+                    self.scopes.pop();
+                    return result;
+                }
+                Err(self.error(
+                    ErrorCode::NonExhaustiveMatch,
+                    format!("No arm of this `match` matched the value `{}`.", value),
+                    keyword.span,
+                ))
+            }
+            Expr::Range { .. } => Err(Unwind::Error(
+                diag!("Ranges can only be used in `for` loops.", expr.span())
+                    .with_code(ErrorCode::ExpectedExpression),
+            )),
+            Expr::StructLiteral { name, fields } => {
+                let mut values = HashMap::new();
+                for field in fields {
+                    let value = self.eval_expr(&field.value)?;
+                    values.insert(field.name.as_ident(), value);
+                }
+                // Fields are stored in declaration order, whatever order the literal lists them in.
+                let fields = self
+                    .types
+                    .struct_fields(name.as_ident())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(field, _)| {
+                        let value = values.remove(field.as_str()).unwrap_or(Value::Unit);
+                        (field.clone(), value)
+                    })
+                    .collect();
+                Ok(Value::Struct {
+                    name: name.as_ident().to_string(),
+                    fields,
+                })
+            }
+            Expr::Field { object, field } => {
+                let object = self.eval_expr(object)?;
+                match object.field(field.as_ident()) {
+                    Some(value) => Ok(value),
+                    None => Err(no_field(field, &object)),
+                }
+            }
+            Expr::Array { elements, .. } => {
+                let elements = self.eval_args(elements)?;
+                Ok(Value::Array(Rc::new(elements)))
+            }
+            Expr::Index { object, index } => {
+                let object = self.eval_expr(object)?;
+                let index = self.eval_int(index)?;
+                let Value::Array(elements) = &object else {
+                    return Err(self.error(
+                        ErrorCode::NotIndexable,
+                        format!(
+                            "Cannot index into a value of type `{}`.",
+                            object.type_name()
+                        ),
+                        expr.span(),
+                    ));
+                };
+                match element_index(elements, index) {
+                    Some(i) => Ok(elements[i].clone()),
+                    None => Err(out_of_bounds(elements, index, expr.span()).into()),
+                }
+            }
+            Expr::Assign { target, op, value } => {
+                let value = self.eval_expr(value)?;
+                let span = expr.span();
+                let mut value = Some(value);
+                self.update_place(target, &mut |slot| {
+                    let value = value.take().unwrap();
+                    *slot = match op {
+                        Some(op) => binary_op(op, slot.clone(), value, span)?,
+                        None => value,
+                    };
+                    Ok(())
+                })?;
+                Ok(Value::Unit)
+            }
+            Expr::None | Expr::Unknown => Ok(Value::Unit),
+        }
+    }
+
+    fn literal(&self, literal: &Token) -> EvalResult<Value> {
+        Ok(match &literal.ty {
+            Ty::Integer(int) => match int.parse() {
+                Ok(int) => Value::Int(int),
+                Err(_) => {
+                    return Err(self.error(
+                        ErrorCode::IntegerTooLarge,
+                        "Integer literal is too large.",
+                        literal.span,
+                    ));
+                }
+            },
+            Ty::Float(float) => Value::Float(float.parse().unwrap_or(f64::NAN)),
+            Ty::Char(ch) => Value::Char(ch.chars().next().unwrap_or_default()),
+            Ty::String(string) => Value::String(string.clone()),
+            Ty::Bool(bool) => Value::Bool(*bool),
+            _ => unreachable!(),
+        })
+    }
+
+    /// Returns whether `value` matches `pattern`, adding the values of its bindings to `bindings`.
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value: &Value,
+        bindings: &mut HashMap<String, Value>,
+    ) -> EvalResult<bool> {
+        match pattern {
+            Pattern::Wildcard(_) => Ok(true),
+            Pattern::Binding(name) => {
+                bindings.insert(name.as_ident().to_string(), value.clone());
+                Ok(true)
+            }
+            Pattern::Literal(expr) => Ok(self.eval_expr(expr)? == *value),
+            Pattern::Variant { path, fields } => {
+                let Value::Enum {
+                    variant,
+                    fields: values,
+                    ..
+                } = value
+                else {
+                    return Ok(false);
+                };
+                if variant != path[path.len() - 1].as_ident() {
+                    return Ok(false);
+                }
+                for (field, value) in fields.iter().zip(values) {
+                    if !self.match_pattern(field, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    fn define(&mut self, name: &Token, value: Value) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.as_ident().to_string(), value);
+    }
+
+    fn eval_args(&mut self, args: &[Expr]) -> EvalResult<Vec<Value>> {
+        args.iter().map(|arg| self.eval_expr(arg)).collect()
+    }
+
+    /// Runs `update` on the storage that an assignment to `target` writes to.
+    fn update_place(&mut self, target: &Expr, update: &mut Update) -> EvalResult<()> {
+        match target {
+            Expr::Var(name) => {
+                let Some(index) = self
+                    .scopes
+                    .iter()
+                    .rposition(|scope| scope.contains_key(name.as_ident()))
+                else {
+                    return Err(self.error(
+                        ErrorCode::UndefinedName,
+                        format!("Cannot find value `{}` in this scope.", name.ty),
+                        name.span,
+                    ));
+                };
+                update(self.scopes[index].get_mut(name.as_ident()).unwrap())
+            }
+            Expr::Field { object, field } => {
+                self.update_member(object, &mut |object| {
+                    // Objects are shared, so their fields are written through the reference.
+                    if let Value::Object(instance) = object {
+                        return update_field(&mut instance.borrow_mut(), field, &mut *update);
+                    }
+                    update_field(object, field, &mut *update)
+                })
+            }
+            Expr::Index { object, index } => {
+                // The index is evaluated once, before the element is looked up.
+                let index_value = self.eval_int(index)?;
+                let span = target.span();
+                self.update_member(object, &mut |object| {
+                    let Value::Array(elements) = object else {
+                        return Err(Unwind::Error(
+                            diag!(
+                                format!(
+                                    "Cannot index into a value of type `{}`.",
+                                    object.type_name()
+                                ),
+                                span
+                            )
+                            .with_code(ErrorCode::NotIndexable),
+                        ));
+                    };
+                    match element_index(elements, index_value) {
+                        Some(i) => update(&mut Rc::make_mut(elements)[i]),
+                        None => Err(out_of_bounds(elements, index_value, span).into()),
+                    }
+                })
+            }
+            target => Err(self.error(
+                ErrorCode::InvalidAssignmentTarget,
+                "Invalid assignment target.",
+                target.span(),
+            )),
+        }
+    }
+
+    /// Runs `update` on the value that holds a field or element being assigned to.
+    fn update_member(&mut self, object: &Expr, update: &mut Update) -> EvalResult<()> {
+        match object {
+            Expr::Var(_) | Expr::Field { .. } | Expr::Index { .. } => {
+                self.update_place(object, update)
+            }
+            object => {
+                let mut object = self.eval_expr(object)?;
+                update(&mut object)
+            }
+        }
+    }
+
+    fn lookup(&self, name: &Token) -> EvalResult<Value> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(value) = scope.get(name.as_ident()) {
+                return Ok(value.clone());
+            }
+        }
+        if let Some(value) = self.globals.get(name.as_ident()) {
+            return Ok(value.clone());
+        }
+
+        Err(self.error(
+            ErrorCode::UndefinedName,
+            format!("Cannot find value `{}` in this scope.", name.ty),
+            name.span,
+        ))
+    }
+
+    fn error(&self, code: ErrorCode, message: impl Into<String>, span: Span) -> Unwind {
+        Unwind::Error(diag!(message.into(), span).with_code(code))
+    }
+}
+
+/// Creates the value of the enum variant named by `path`, such as `Shape::Circle`.
+fn variant(path: &[Token], fields: Vec<Value>) -> Value {
+    Value::Enum {
+        name: path[0].as_ident().to_string(),
+        variant: path[path.len() - 1].as_ident().to_string(),
+        fields,
+    }
+}
+
+fn update_field(object: &mut Value, field: &Token, update: &mut Update) -> EvalResult<()> {
+    if object.field(field.as_ident()).is_none() {
+        return Err(no_field(field, object));
+    }
+    update(object.field_mut(field.as_ident()).unwrap())
+}
+
+fn no_field(field: &Token, object: &Value) -> Unwind {
+    Unwind::Error(
+        diag!(
+            format!("No field `{}` on type `{}`.", field.ty, object.type_name()),
+            field.span
+        )
+        .with_code(ErrorCode::UnknownField),
+    )
+}
@@ -0,0 +1,654 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{builtins::Builtin, types::Type, utils::Span, value::Value};
+
+/// A value in SSA form: defined exactly once, by an instruction or as a block parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ValueId(pub u32);
+
+impl Display for ValueId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub u32);
+
+impl Display for BlockId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "b{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinaryOp {
+    pub fn is_comparison(&self) -> bool {
+        !matches!(
+            self,
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div
+        )
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BinaryOp::Add => "add",
+            BinaryOp::Sub => "sub",
+            BinaryOp::Mul => "mul",
+            BinaryOp::Div => "div",
+            BinaryOp::Eq => "eq",
+            BinaryOp::Ne => "ne",
+            BinaryOp::Lt => "lt",
+            BinaryOp::Le => "le",
+            BinaryOp::Gt => "gt",
+            BinaryOp::Ge => "ge",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    /// Arithmetic negation, `-x`.
+    Neg,
+    /// Logical negation, `!x`.
+    Not,
+}
+
+impl Display for UnaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnaryOp::Neg => write!(f, "neg"),
+            UnaryOp::Not => write!(f, "not"),
+        }
+    }
+}
+
+/// The operation performed by an instruction. Arrays and structs are values, so
+/// updating one produces a new value; class instances are shared and their
+/// fields are written in place with `StoreField`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstKind {
+    /// An `int`, `float`, `bool`, `char`, `string` or `()` constant.
+    Const(Value),
+    /// Reads a global constant.
+    Global(String),
+    /// Initializes a global constant. Only used by the function that initializes the globals.
+    SetGlobal(String, ValueId),
+    Binary(BinaryOp, ValueId, ValueId),
+    Unary(UnaryOp, ValueId),
+    /// Calls a function or a method, named `Class.method`. A method takes its receiver first.
+    Call(String, Vec<ValueId>),
+    CallBuiltin(Builtin, Vec<ValueId>),
+    Array(Vec<ValueId>),
+    /// Reads an element, checking that the index is in bounds.
+    Index(ValueId, ValueId),
+    /// Returns a copy of an array with one element replaced.
+    InsertElement(ValueId, ValueId, ValueId),
+    /// Builds a struct from its fields in declaration order.
+    Struct(String, Vec<ValueId>),
+    /// Creates an instance of a class with default field values.
+    New(String),
+    /// Reads a field of a struct or an object.
+    Field(ValueId, String),
+    /// Returns a copy of a struct with one field replaced.
+    InsertField(ValueId, String, ValueId),
+    /// Writes a field of an object.
+    StoreField(ValueId, String, ValueId),
+    /// Builds an enum value from its enum, variant and payload.
+    Variant(String, String, Vec<ValueId>),
+    /// Tests whether an enum value is the given variant.
+    IsVariant(ValueId, String),
+    /// Reads a field of the payload of an enum value known to have one.
+    VariantField(ValueId, usize),
+}
+
+impl InstKind {
+    /// Returns the values that the instruction uses.
+    pub fn operands(&self) -> Vec<ValueId> {
+        match self {
+            InstKind::Const(_) | InstKind::Global(_) | InstKind::New(_) => Vec::new(),
+            InstKind::SetGlobal(_, value)
+            | InstKind::Unary(_, value)
+            | InstKind::Field(value, _)
+            | InstKind::IsVariant(value, _)
+            | InstKind::VariantField(value, _) => vec![*value],
+            InstKind::Binary(_, a, b)
+            | InstKind::Index(a, b)
+            | InstKind::InsertField(a, _, b)
+            | InstKind::StoreField(a, _, b) => vec![*a, *b],
+            InstKind::InsertElement(a, b, c) => vec![*a, *b, *c],
+            InstKind::Call(_, values)
+            | InstKind::CallBuiltin(_, values)
+            | InstKind::Array(values)
+            | InstKind::Struct(_, values)
+            | InstKind::Variant(_, _, values) => values.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inst {
+    /// The value the instruction defines. Instructions of type `()` define none.
+    pub result: Option<ValueId>,
+    pub kind: InstKind,
+    pub span: Span,
+}
+
+/// A jump to a block with the arguments for its parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub block: BlockId,
+    pub args: Vec<ValueId>,
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.block)?;
+        if !self.args.is_empty() {
+            write!(f, "({})", list(&self.args))?;
+        }
+        Ok(())
+    }
+}
+
+/// The instruction that ends a block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(Target),
+    /// Jumps to `then` if the `bool` condition is true and to `otherwise` if not.
+    Branch {
+        condition: ValueId,
+        then: Target,
+        otherwise: Target,
+    },
+    /// Returns from the function, with no value if it returns `()`.
+    Return(Option<ValueId>),
+    /// Stops with an error because no arm of a `match` matched the value.
+    NoMatch(ValueId),
+    /// Marks code that cannot be reached, such as the end of a function that returns on every path.
+    Unreachable,
+}
+
+impl Terminator {
+    pub fn targets(&self) -> Vec<&Target> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn targets_mut(&mut self) -> Vec<&mut Target> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the values that the terminator uses, including the arguments of its targets.
+    pub fn operands(&self) -> Vec<ValueId> {
+        let mut operands = match self {
+            Terminator::Branch { condition, .. } => vec![*condition],
+            Terminator::Return(Some(value)) | Terminator::NoMatch(value) => vec![*value],
+            _ => Vec::new(),
+        };
+        for target in self.targets() {
+            operands.extend(&target.args);
+        }
+        operands
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BasicBlock {
+    pub params: Vec<ValueId>,
+    pub insts: Vec<Inst>,
+    /// Every block ends with a terminator once its function is complete.
+    pub terminator: Option<(Terminator, Span)>,
+}
+
+/// A function in SSA form. Its parameters are the parameters of the entry block, `b0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub return_ty: Type,
+    pub blocks: Vec<BasicBlock>,
+    /// The type of every value, by id.
+    pub values: Vec<Type>,
+    pub span: Span,
+}
+
+impl Function {
+    pub fn new(name: impl Into<String>, return_ty: Type, span: Span) -> Self {
+        Self {
+            name: name.into(),
+            return_ty,
+            blocks: vec![BasicBlock::default()],
+            values: Vec::new(),
+            span,
+        }
+    }
+
+    pub fn entry(&self) -> BlockId {
+        BlockId(0)
+    }
+
+    pub fn params(&self) -> &[ValueId] {
+        &self.blocks[0].params
+    }
+
+    pub fn block(&self, block: BlockId) -> &BasicBlock {
+        &self.blocks[block.0 as usize]
+    }
+
+    pub fn block_mut(&mut self, block: BlockId) -> &mut BasicBlock {
+        &mut self.blocks[block.0 as usize]
+    }
+
+    pub fn add_block(&mut self) -> BlockId {
+        self.blocks.push(BasicBlock::default());
+        BlockId(self.blocks.len() as u32 - 1)
+    }
+
+    pub fn add_value(&mut self, ty: Type) -> ValueId {
+        self.values.push(ty);
+        ValueId(self.values.len() as u32 - 1)
+    }
+
+    pub fn add_param(&mut self, block: BlockId, ty: Type) -> ValueId {
+        let value = self.add_value(ty);
+        self.block_mut(block).params.push(value);
+        value
+    }
+
+    pub fn ty(&self, value: ValueId) -> &Type {
+        &self.values[value.0 as usize]
+    }
+
+    /// Returns the blocks that end with a jump or branch to each block, by block index.
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut predecessors = vec![Vec::new(); self.blocks.len()];
+        for (i, block) in self.blocks.iter().enumerate() {
+            if let Some((terminator, _)) = &block.terminator {
+                for target in terminator.targets() {
+                    if let Some(preds) = predecessors.get_mut(target.block.0 as usize) {
+                        preds.push(BlockId(i as u32));
+                    }
+                }
+            }
+        }
+        predecessors
+    }
+
+    /// Returns the blocks reachable from the entry block, in reverse postorder.
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        let mut visited = vec![false; self.blocks.len()];
+        let mut order = Vec::new();
+        // Each entry is a block and whether its successors have been pushed.
+        let mut stack = vec![(self.entry(), false)];
+        while let Some((block, done)) = stack.pop() {
+            if done {
+                order.push(block);
+                continue;
+            }
+            if visited[block.0 as usize] {
+                continue;
+            }
+            visited[block.0 as usize] = true;
+            stack.push((block, true));
+            if let Some((terminator, _)) = &self.block(block).terminator {
+                for target in terminator.targets().into_iter().rev() {
+                    if !visited[target.block.0 as usize] {
+                        stack.push((target.block, false));
+                    }
+                }
+            }
+        }
+        order.reverse();
+        order
+    }
+
+    /// Removes the parameters of blocks that always receive the same value,
+    /// apart from the parameter itself, and uses that value instead. Such
+    /// parameters appear where a variable keeps its value through a loop.
+    pub fn remove_trivial_params(&mut self) {
+        let reachable = self.reverse_postorder();
+        loop {
+            let mut replacements = HashMap::new();
+            for &block in reachable.iter().skip(1) {
+                for (i, &param) in self.block(block).params.iter().enumerate() {
+                    let mut incoming = Vec::new();
+                    for &predecessor in &reachable {
+                        let Some((terminator, _)) = &self.block(predecessor).terminator else {
+                            continue;
+                        };
+                        for target in terminator.targets() {
+                            // A jump with the wrong number of arguments is left for the verifier.
+                            match target.args.get(i) {
+                                _ if target.block != block => {}
+                                Some(&arg) if arg == param => {}
+                                Some(&arg) => incoming.push(Some(arg)),
+                                None => incoming.push(None),
+                            }
+                        }
+                    }
+                    incoming.sort();
+                    incoming.dedup();
+                    if let [Some(value)] = incoming[..] {
+                        replacements.insert(param, value);
+                    }
+                }
+            }
+            if replacements.is_empty() {
+                return;
+            }
+
+            let resolve = |mut value: ValueId| {
+                // A chain of replacements can only be as long as the number of replaced values.
+                for _ in 0..replacements.len() {
+                    match replacements.get(&value) {
+                        Some(replacement) => value = *replacement,
+                        None => break,
+                    }
+                }
+                value
+            };
+            let removed: Vec<Vec<bool>> = self
+                .blocks
+                .iter()
+                .map(|block| {
+                    block
+                        .params
+                        .iter()
+                        .map(|param| replacements.contains_key(param))
+                        .collect()
+                })
+                .collect();
+            for block in &mut self.blocks {
+                block
+                    .params
+                    .retain(|param| !replacements.contains_key(param));
+                for inst in &mut block.insts {
+                    inst.kind.map_operands(|value| *value = resolve(*value));
+                }
+                if let Some((terminator, _)) = &mut block.terminator {
+                    terminator.map_operands(|value| *value = resolve(*value));
+                    for target in terminator.targets_mut() {
+                        let removed = &removed[target.block.0 as usize];
+                        if target.args.len() == removed.len() {
+                            let mut i = 0;
+                            target.args.retain(|_| {
+                                i += 1;
+                                !removed[i - 1]
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes the blocks that cannot be reached from the entry block and
+    /// numbers the remaining blocks and values in order.
+    pub fn compact(&mut self) {
+        let mut reachable = self.reverse_postorder();
+        reachable.sort();
+        let mut block_ids = vec![None; self.blocks.len()];
+        for (new, old) in reachable.iter().enumerate() {
+            block_ids[old.0 as usize] = Some(BlockId(new as u32));
+        }
+
+        let blocks = std::mem::take(&mut self.blocks);
+        let mut values = Vec::new();
+        let mut value_ids = vec![None; self.values.len()];
+        let mut renumber = |value: ValueId, values: &mut Vec<Type>, types: &[Type]| {
+            let id = ValueId(values.len() as u32);
+            values.push(types[value.0 as usize].clone());
+            value_ids[value.0 as usize] = Some(id);
+        };
+        for old in &reachable {
+            let block = &blocks[old.0 as usize];
+            for &param in &block.params {
+                renumber(param, &mut values, &self.values);
+            }
+            for result in block.insts.iter().filter_map(|inst| inst.result) {
+                renumber(result, &mut values, &self.values);
+            }
+        }
+
+        // Values used without a definition stay out of range, for the verifier to report.
+        let map = |value: &mut ValueId| {
+            *value = value_ids
+                .get(value.0 as usize)
+                .copied()
+                .flatten()
+                .unwrap_or(ValueId(u32::MAX));
+        };
+        for old in &reachable {
+            let mut block = blocks[old.0 as usize].clone();
+            block.params.iter_mut().for_each(map);
+            for inst in &mut block.insts {
+                if let Some(result) = &mut inst.result {
+                    map(result);
+                }
+                inst.kind.map_operands(map);
+            }
+            if let Some((terminator, _)) = &mut block.terminator {
+                terminator.map_operands(map);
+                for target in terminator.targets_mut() {
+                    target.block = block_ids[target.block.0 as usize].unwrap();
+                }
+            }
+            self.blocks.push(block);
+        }
+        self.values = values;
+    }
+}
+
+impl InstKind {
+    /// Replaces every operand of the instruction with `map` applied to it.
+    pub fn map_operands(&mut self, mut map: impl FnMut(&mut ValueId)) {
+        match self {
+            InstKind::Const(_) | InstKind::Global(_) | InstKind::New(_) => {}
+            InstKind::SetGlobal(_, value)
+            | InstKind::Unary(_, value)
+            | InstKind::Field(value, _)
+            | InstKind::IsVariant(value, _)
+            | InstKind::VariantField(value, _) => map(value),
+            InstKind::Binary(_, a, b)
+            | InstKind::Index(a, b)
+            | InstKind::InsertField(a, _, b)
+            | InstKind::StoreField(a, _, b) => {
+                map(a);
+                map(b);
+            }
+            InstKind::InsertElement(a, b, c) => {
+                map(a);
+                map(b);
+                map(c);
+            }
+            InstKind::Call(_, values)
+            | InstKind::CallBuiltin(_, values)
+            | InstKind::Array(values)
+            | InstKind::Struct(_, values)
+            | InstKind::Variant(_, _, values) => values.iter_mut().for_each(map),
+        }
+    }
+}
+
+impl Terminator {
+    /// Replaces every value the terminator uses with `map` applied to it.
+    pub fn map_operands(&mut self, mut map: impl FnMut(&mut ValueId)) {
+        match self {
+            Terminator::Branch { condition, .. } => map(condition),
+            Terminator::Return(Some(value)) | Terminator::NoMatch(value) => map(value),
+            _ => {}
+        }
+        for target in self.targets_mut() {
+            target.args.iter_mut().for_each(&mut map);
+        }
+    }
+}
+
+/// A program in SSA form.
+#[derive(Debug, Default)]
+pub struct Module {
+    /// The global constants and their types, in declaration order.
+    pub globals: Vec<(String, Type)>,
+    /// The functions, starting with the one that initializes the globals.
+    pub functions: Vec<Function>,
+}
+
+impl Module {
+    /// The name of the function that initializes the global constants.
+    pub const INIT: &'static str = "<globals>";
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, ty) in &self.globals {
+            writeln!(f, "global @{}: {}", name, ty)?;
+        }
+        if !self.globals.is_empty() {
+            writeln!(f)?;
+        }
+        for (i, function) in self.functions.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params: Vec<String> = self
+            .params()
+            .iter()
+            .map(|param| format!("{}: {}", param, self.ty(*param)))
+            .collect();
+        writeln!(
+            f,
+            "func {}({}) -> {} {{",
+            self.name,
+            params.join(", "),
+            self.return_ty
+        )?;
+        for (i, block) in self.blocks.iter().enumerate() {
+            write!(f, "{}", BlockId(i as u32))?;
+            // The parameters of the entry block are printed as those of the function.
+            if i > 0 && !block.params.is_empty() {
+                let params: Vec<String> = block
+                    .params
+                    .iter()
+                    .map(|param| format!("{}: {}", param, self.ty(*param)))
+                    .collect();
+                write!(f, "({})", params.join(", "))?;
+            }
+            writeln!(f, ":")?;
+            for inst in &block.insts {
+                match inst.result {
+                    Some(result) => {
+                        writeln!(f, "    {}: {} = {}", result, self.ty(result), inst.kind)?
+                    }
+                    None => writeln!(f, "    {}", inst.kind)?,
+                }
+            }
+            match &block.terminator {
+                Some((terminator, _)) => writeln!(f, "    {}", terminator)?,
+                None => writeln!(f, "    <no terminator>")?,
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl Display for InstKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstKind::Const(Value::String(string)) => write!(f, "const {:?}", string),
+            InstKind::Const(Value::Char(ch)) => write!(f, "const {:?}", ch),
+            InstKind::Const(value) => write!(f, "const {}", value),
+            InstKind::Global(name) => write!(f, "global @{}", name),
+            InstKind::SetGlobal(name, value) => write!(f, "set_global @{}, {}", name, value),
+            InstKind::Binary(op, a, b) => write!(f, "{} {}, {}", op, a, b),
+            InstKind::Unary(op, value) => write!(f, "{} {}", op, value),
+            InstKind::Call(name, args) => write!(f, "call {}({})", name, list(args)),
+            InstKind::CallBuiltin(builtin, args) => {
+                write!(f, "call_builtin {:?}({})", builtin, list(args))
+            }
+            InstKind::Array(elements) => write!(f, "array [{}]", list(elements)),
+            InstKind::Index(array, index) => write!(f, "index {}, {}", array, index),
+            InstKind::InsertElement(array, index, value) => {
+                write!(f, "insert_element {}, {}, {}", array, index, value)
+            }
+            InstKind::Struct(name, fields) => write!(f, "struct {} {{{}}}", name, list(fields)),
+            InstKind::New(class) => write!(f, "new {}", class),
+            InstKind::Field(object, field) => write!(f, "field {}.{}", object, field),
+            InstKind::InsertField(object, field, value) => {
+                write!(f, "insert_field {}.{}, {}", object, field, value)
+            }
+            InstKind::StoreField(object, field, value) => {
+                write!(f, "store_field {}.{}, {}", object, field, value)
+            }
+            InstKind::Variant(name, variant, fields) => {
+                write!(f, "variant {}::{}", name, variant)?;
+                if !fields.is_empty() {
+                    write!(f, "({})", list(fields))?;
+                }
+                Ok(())
+            }
+            InstKind::IsVariant(value, variant) => write!(f, "is_variant {}, {}", value, variant),
+            InstKind::VariantField(value, i) => write!(f, "variant_field {}, {}", value, i),
+        }
+    }
+}
+
+impl Display for Terminator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Terminator::Jump(target) => write!(f, "jump {}", target),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => write!(f, "branch {}, {}, {}", condition, then, otherwise),
+            Terminator::Return(Some(value)) => write!(f, "return {}", value),
+            Terminator::Return(None) => write!(f, "return"),
+            Terminator::NoMatch(value) => write!(f, "no_match {}", value),
+            Terminator::Unreachable => write!(f, "unreachable"),
+        }
+    }
+}
+
+fn list(values: &[ValueId]) -> String {
+    let values: Vec<String> = values.iter().map(ValueId::to_string).collect();
+    values.join(", ")
+}
@@ -2,11 +2,14 @@ use crate::{
     compiler::Compiler,
     diag,
     diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
     utils::{Span, Token, TokenType as Ty},
 };
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct Lexer<'a> {
+    /// The byte offset of the next character, always on a character boundary.
     index: usize,
     source: &'a str,
     compiler: &'a Compiler,
@@ -19,7 +22,7 @@ impl<'a> Lexer<'a> {
     pub fn new(compiler: &'a Compiler) -> Self {
         Self {
             index: 0,
-            source: &compiler.curr_source,
+            source: compiler.get_curr_source(),
             compiler,
             match_paren: (0, 0),
             match_curly: (0, 0),
@@ -35,38 +38,49 @@ impl<'a> Lexer<'a> {
                 Err(diagnostic) => self.compiler.reporter.borrow_mut().add(diagnostic),
             }
         }
+        if tokens.last().is_none_or(|token: &Token| !token.is_eof()) {
+            let end = self.source.len();
+            tokens.push(Token::new(Ty::Eof, self.span(end, end)));
+        }
 
         tokens
     }
 
+    #[allow(dead_code)]
     fn start_paren(&mut self) {
         self.match_paren.0 += 1;
         self.match_paren.1 = self.index;
     }
 
+    #[allow(dead_code)]
     fn start_curly(&mut self) {
         self.match_curly.0 += 1;
         self.match_curly.1 = self.index;
     }
 
+    #[allow(dead_code)]
     fn start_brace(&mut self) {
         self.match_brace.0 += 1;
         self.match_brace.1 = self.index;
     }
 
+    #[allow(dead_code)]
     fn match_paren(&mut self) {
         self.match_paren.0 -= 1;
     }
 
+    #[allow(dead_code)]
     fn match_curly(&mut self) {
         self.match_curly.0 -= 1;
     }
 
+    #[allow(dead_code)]
     fn match_brace(&mut self) {
         self.match_brace.0 -= 1;
     }
 
     /// `check_brackets()` is a function that will do the checking of matching
+    #[allow(dead_code)]
     fn check_brackets(&self) -> bool {
         self.match_paren.0 > 0 || self.match_curly.0 > 0 || self.match_brace.0 > 0
     }
@@ -92,6 +106,20 @@ impl<'a> Lexer<'a> {
             "func" => Ty::KFunction,
             "struct" => Ty::KStruct,
             "class" => Ty::KClass,
+            "if" => Ty::KIf,
+            "else" => Ty::KElse,
+            "while" => Ty::KWhile,
+            "for" => Ty::KFor,
+            "in" => Ty::KIn,
+            "break" => Ty::KBreak,
+            "continue" => Ty::KContinue,
+            "enum" => Ty::KEnum,
+            "match" => Ty::KMatch,
+            "import" => Ty::KImport,
+            "use" => Ty::KUse,
+            "pub" => Ty::KPub,
+            "true" => Ty::Bool(true),
+            "false" => Ty::Bool(false),
             id => Ty::Identifier(id.to_string()),
         }
     }
@@ -102,12 +130,9 @@ impl<'a> Lexer<'a> {
         while let Some(ch) = self.peek() {
             match ch {
                 '0'..='9' => self.advance(),
-                '.' if !has_dot => {
-                    self.advance();
-                    if self.peek() == Some('.') {
-                        self.advance();
-                        return Ty::DoubleDot;
-                    }
+                // A dot only continues the number if a digit follows it, so that
+                // `0..10` lexes as `0`, `..`, `10`.
+                '.' if !has_dot && self.peek_next().is_some_and(|c| c.is_ascii_digit()) => {
                     has_dot = true;
                     self.advance();
                 }
@@ -137,62 +162,76 @@ impl<'a> Lexer<'a> {
                     Some('"') => result.push('"'),
                     Some('x') => {
                         // e.g., \x41 => 'A'
+                        self.advance();
                         let hex1 = self.peek();
+                        self.advance();
                         let hex2 = self.peek();
                         if let (Some(h1), Some(h2)) = (hex1, hex2) {
                             let hex_str = format!("{}{}", h1, h2);
                             if let Ok(byte) = u8::from_str_radix(&hex_str, 16) {
                                 result.push(byte as char);
                             } else {
-                                return Err(
-                                    self.error("Invalid hex escape", self.span(start, self.index))
-                                );
+                                return Err(self.error(
+                                    ErrorCode::UnknownEscape,
+                                    "Invalid hex escape",
+                                    self.span(start, self.index),
+                                ));
                             }
                         } else {
-                            return Err(
-                                self.error("Incomplete hex escape", self.span(start, self.index))
-                            );
+                            return Err(self.error(
+                                ErrorCode::UnknownEscape,
+                                "Incomplete hex escape",
+                                self.span(start, self.index),
+                            ));
                         }
                     }
                     Some('u') => {
                         // Unicode escapes: \u{1F600}
+                        self.advance();
                         if self.peek() != Some('{') {
-                            return Err(
-                                self.error("Expected '{' after \\u", self.span(start, self.index))
-                            );
+                            return Err(self.error(
+                                ErrorCode::UnknownEscape,
+                                "Expected '{' after \\u",
+                                self.span(start, self.index),
+                            ));
                         }
                         let mut unicode = String::new();
+                        self.advance();
                         while let Some(next) = self.peek() {
                             if next == '}' {
-                                self.peek();
                                 break;
                             }
                             unicode.push(next);
-                            self.peek();
+                            self.advance();
                         }
                         if let Ok(code_point) = u32::from_str_radix(&unicode, 16) {
                             if let Some(c) = char::from_u32(code_point) {
                                 result.push(c);
                             } else {
                                 return Err(self.error(
+                                    ErrorCode::UnknownEscape,
                                     "Invalid Unicode code point",
                                     self.span(start, self.index),
                                 ));
                             }
                         } else {
-                            return Err(
-                                self.error("Invalid Unicode escape", self.span(start, self.index))
-                            );
+                            return Err(self.error(
+                                ErrorCode::UnknownEscape,
+                                "Invalid Unicode escape",
+                                self.span(start, self.index),
+                            ));
                         }
                     }
                     Some(c) => {
                         return Err(self.error(
+                            ErrorCode::UnknownEscape,
                             format!("Unknown escape: \\{}", c),
                             self.span(start, self.index),
                         ))
                     }
                     None => {
                         return Err(self.error(
+                            ErrorCode::UnknownEscape,
                             "Unexpected end of input after \\",
                             self.span(start, self.index),
                         ))
@@ -212,15 +251,22 @@ impl<'a> Lexer<'a> {
     }
 
     fn peek(&self) -> Option<char> {
-        self.source.chars().nth(self.index)
+        self.source[self.index..].chars().next()
     }
 
+    fn peek_next(&self) -> Option<char> {
+        self.source[self.index..].chars().nth(1)
+    }
+
+    /// Moves past the next character, if there is one.
     fn advance(&mut self) {
-        self.index += 1;
+        if let Some(c) = self.peek() {
+            self.index += c.len_utf8();
+        }
     }
 
-    fn error(&mut self, message: impl Into<String>, span: Span) -> Diagnostic {
-        diag!(message.into(), span)
+    fn error(&mut self, code: ErrorCode, message: impl Into<String>, span: Span) -> Diagnostic {
+        diag!(message.into(), span).with_code(code)
     }
 }
 
@@ -237,7 +283,7 @@ impl<'a> Iterator for Lexer<'a> {
                     self.advance();
                     return Some(Ok(Token::new(
                         Ty::Eof,
-                        self.span(self.index - 1, self.index - 1),
+                        self.span(self.index - c.len_utf8(), self.index),
                     )));
                 }
                 break;
@@ -298,23 +344,74 @@ impl<'a> Iterator for Lexer<'a> {
                 '.' => match self.peek() {
                     Some('.') => {
                         self.advance();
-                        Ty::DoubleDot
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            Ty::DoubleDotEq
+                        } else {
+                            Ty::DoubleDot
+                        }
                     }
                     _ => Ty::Dot,
                 },
 
-                '+' => Ty::Plus,
+                '+' => match self.peek() {
+                    Some('=') => {
+                        self.advance();
+                        Ty::PlusEq
+                    }
+                    _ => Ty::Plus,
+                },
                 '-' => match self.peek() {
                     Some('>') => {
                         self.advance();
                         Ty::RightArrow
                     }
+                    Some('=') => {
+                        self.advance();
+                        Ty::MinusEq
+                    }
                     _ => Ty::Minus,
                 },
-                '*' => Ty::Asterisk,
-                '/' => Ty::Slash,
-                ':' => Ty::Colon,
+                '*' => match self.peek() {
+                    Some('=') => {
+                        self.advance();
+                        Ty::AsteriskEq
+                    }
+                    _ => Ty::Asterisk,
+                },
+                '/' => match self.peek() {
+                    Some('=') => {
+                        self.advance();
+                        Ty::SlashEq
+                    }
+                    _ => Ty::Slash,
+                },
+                ':' => match self.peek() {
+                    Some(':') => {
+                        self.advance();
+                        Ty::DoubleColon
+                    }
+                    _ => Ty::Colon,
+                },
                 ',' => Ty::Comma,
+                '#' => Ty::Hash,
+
+                '&' if self.peek() == Some('&') => {
+                    self.advance();
+                    Ty::DoubleAmpersand
+                }
+                '|' if self.peek() == Some('|') => {
+                    self.advance();
+                    Ty::DoublePipe
+                }
+                '&' | '|' => {
+                    return Some(Err(diag!(
+                        format!("{}: '{}'", "Unknown token used", c),
+                        format!("Did you mean `{}{}`?", c, c),
+                        self.span(start, self.index)
+                    )
+                    .with_code(ErrorCode::UnknownToken)))
+                }
 
                 '\'' => match self.peek() {
                     Some(c) if c != '\\' => {
@@ -325,11 +422,11 @@ impl<'a> Iterator for Lexer<'a> {
                                 Ty::Char(format!("{}", c))
                             }
                             _ => {
-                                self.error(
+                                return Some(Err(self.error(
+                                    ErrorCode::UnterminatedChar,
                                     "Expected end of char quote.",
                                     self.span(start, self.index),
-                                );
-                                Ty::Unknown
+                                )));
                             }
                         }
                     }
@@ -340,24 +437,24 @@ impl<'a> Iterator for Lexer<'a> {
                     Ok(string) => {
                         return Some(Ok(Token::new(
                             Ty::String(string),
-                            self.span(start, self.index - 3),
+                            self.span(start, self.index),
                         )))
                     }
                     Err(e) => return Some(Err(e)),
                 },
 
-                '_' | 'a'..='z' | 'A'..='Z' => self.identify_keyword_or_id(start),
+                c if c == '_' || c.is_alphabetic() => self.identify_keyword_or_id(start),
                 '0'..='9' => self.identify_number(start),
 
                 _ => {
                     return Some(Err(self.error(
+                        ErrorCode::UnknownToken,
                         format!("{}: '{}'", "Unknown token used", c),
-                        self.span(start, start),
+                        self.span(start, self.index),
                     )))
                 }
             };
         }
-        let end = self.index - 1;
-        Some(Ok(Token::new(ty, self.span(start, end))))
+        Some(Ok(Token::new(ty, self.span(start, self.index))))
     }
 }
@@ -0,0 +1,35 @@
+//! The compiler of the Glacier language: its front end, which checks a
+//! program, and the interpreter, VM and native backends that run or build it.
+//! The `olive` binary is the command line interface.
+
+pub mod builtins;
+pub mod bytecode;
+pub mod checker;
+#[cfg(feature = "cranelift")]
+pub mod codegen;
+pub mod compiler;
+pub mod diagnostic;
+pub mod emitter;
+pub mod error_codes;
+pub mod exhaustiveness;
+pub mod ir;
+pub mod json;
+pub mod lints;
+pub mod lower;
+pub mod modules;
+pub mod optimizer;
+pub mod printer;
+pub mod qualifier;
+pub mod transpiler;
+pub mod types;
+pub mod utils;
+pub mod value;
+pub mod verifier;
+pub mod vm;
+
+pub mod ast;
+pub mod interpreter;
+pub mod lexer;
+pub mod parser;
+pub mod resolver;
+pub mod source_map;
@@ -0,0 +1,471 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{ArmBody, Ast, Attribute, Block, Expr, Function, Item, Pattern, Statement},
+    compiler::Compiler,
+    diag,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    optimizer::diverges,
+    resolver::Resolutions,
+    utils::{FileId, Span, Token},
+};
+
+/// A check for code that is valid but probably not what was meant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum Lint {
+    /// A local variable that is never used.
+    UnusedVariable,
+    /// A function parameter that is never used.
+    UnusedParameter,
+    /// A statement after a `return`, `break` or `continue`.
+    UnreachableCode,
+    /// A local variable with the same name as one in an enclosing scope.
+    ShadowedBinding,
+    /// A function or method whose name is not in snake case.
+    NonSnakeCase,
+}
+
+impl Lint {
+    pub const ALL: &'static [Lint] = &[
+        Lint::UnusedVariable,
+        Lint::UnusedParameter,
+        Lint::UnreachableCode,
+        Lint::ShadowedBinding,
+        Lint::NonSnakeCase,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused_variable",
+            Lint::UnusedParameter => "unused_parameter",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::ShadowedBinding => "shadowed_binding",
+            Lint::NonSnakeCase => "non_snake_case",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.iter().copied().find(|lint| lint.name() == name)
+    }
+
+    /// The code of the lint's diagnostics, explained by `--explain`.
+    pub fn code(self) -> ErrorCode {
+        match self {
+            Lint::UnusedVariable => ErrorCode::UnusedVariable,
+            Lint::UnusedParameter => ErrorCode::UnusedParameter,
+            Lint::UnreachableCode => ErrorCode::UnreachableCode,
+            Lint::ShadowedBinding => ErrorCode::ShadowedBinding,
+            Lint::NonSnakeCase => ErrorCode::NonSnakeCase,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// How every lint is reported, as set on the command line with `-A`, `-W`
+/// and `-D`. Every lint warns by default. A lint given to several flags gets
+/// the strictest of their levels.
+#[derive(Debug, Default)]
+pub struct LintLevels {
+    levels: HashMap<Lint, Level>,
+}
+
+impl LintLevels {
+    pub fn new(allow: &[Lint], warn: &[Lint], deny: &[Lint]) -> Self {
+        let mut levels = HashMap::new();
+        for (lints, level) in [
+            (allow, Level::Allow),
+            (warn, Level::Warn),
+            (deny, Level::Deny),
+        ] {
+            for lint in lints {
+                levels.insert(*lint, level);
+            }
+        }
+        Self { levels }
+    }
+
+    fn level(&self, lint: Lint) -> Level {
+        self.levels.get(&lint).copied().unwrap_or(Level::Warn)
+    }
+
+    /// Explains why `lint` is reported the way it is.
+    fn reason(&self, lint: Lint) -> String {
+        match self.levels.get(&lint) {
+            Some(Level::Deny) => format!(
+                "`{}` is an error because of `-D {}`.",
+                lint.name(),
+                lint.name()
+            ),
+            Some(_) => format!(
+                "`{}` is a warning because of `-W {}`.",
+                lint.name(),
+                lint.name()
+            ),
+            None => format!(
+                "`{}` is a warning by default. Write `#allow({})` in the file to silence it.",
+                lint.name(),
+                lint.name()
+            ),
+        }
+    }
+}
+
+/// Reports the lints of a resolved program: unused variables and parameters,
+/// unreachable statements, shadowed bindings and function names that are not
+/// in snake case. A file silences lints with `#allow(...)`, which takes
+/// precedence over the command line.
+pub struct Linter<'a> {
+    compiler: &'a Compiler,
+    levels: &'a LintLevels,
+    /// The declarations that are used somewhere in the program.
+    used: HashSet<Span>,
+    /// The lints allowed by every file.
+    allowed: HashMap<FileId, HashSet<Lint>>,
+    /// The lints that were reported already, whose level has been explained.
+    explained: HashSet<Lint>,
+    /// The local bindings of every enclosing scope of the current function.
+    scopes: Vec<HashMap<String, Span>>,
+}
+
+impl<'a> Linter<'a> {
+    pub fn new(compiler: &'a Compiler, resolutions: &Resolutions, levels: &'a LintLevels) -> Self {
+        Self {
+            compiler,
+            levels,
+            used: resolutions.used_declarations(),
+            allowed: HashMap::new(),
+            explained: HashSet::new(),
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn run(mut self, ast: &Ast) {
+        for item in &ast.items {
+            if let Item::Attribute(attribute) = item {
+                self.allow(attribute);
+            }
+        }
+        for item in &ast.items {
+            match item {
+                Item::Func(function) => self.function(function),
+                Item::Class(class) => {
+                    for method in &class.methods {
+                        self.function(method);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn allow(&mut self, attribute: &Attribute) {
+        let file_id = attribute.name.span.file_id;
+        for arg in &attribute.args {
+            match Lint::from_name(arg.as_ident()) {
+                Some(lint) => {
+                    self.allowed.entry(file_id).or_default().insert(lint);
+                }
+                None if !arg.is_eof() => {
+                    let lints = Lint::ALL
+                        .iter()
+                        .map(|lint| format!("`{}`", lint.name()))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    self.compiler.reporter.borrow_mut().add(
+                        diag!(
+                            DiagnosticKind::Warning,
+                            format!("Unknown lint `{}`.", arg.ty),
+                            Some(String::from("This lint does not exist.")),
+                            arg.span
+                        )
+                        .with_code(ErrorCode::UnknownLint)
+                        .with_help(format!("The lints are {}.", lints)),
+                    );
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn function(&mut self, function: &Function) {
+        let name = function.name.as_ident();
+        let snake_case = to_snake_case(name);
+        if snake_case != name {
+            self.report(
+                Lint::NonSnakeCase,
+                diag!(
+                    DiagnosticKind::Warning,
+                    format!("Function `{}` should have a snake case name.", name),
+                    Some(String::from("Not in snake case.")),
+                    function.name.span
+                )
+                .with_help(format!("Rename it to `{}`.", snake_case)),
+            );
+        }
+
+        self.scopes.push(HashMap::new());
+        for param in &function.params {
+            if self.is_unused(&param.name) {
+                self.report(
+                    Lint::UnusedParameter,
+                    diag!(
+                        DiagnosticKind::Warning,
+                        format!("Unused parameter `{}`.", param.name.ty),
+                        Some(String::from("Never used in the body of the function.")),
+                        param.name.span
+                    )
+                    .with_help(format!(
+                        "If this is intentional, name it `_{}`.",
+                        param.name.ty
+                    )),
+                );
+            }
+            self.bind(&param.name);
+        }
+        self.block(&function.body);
+        self.scopes.pop();
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.scopes.push(HashMap::new());
+        if let Some(i) = block.0.iter().position(diverges) {
+            if let Some(next) = block.0.get(i + 1) {
+                let exit = &block.0[i];
+                let label = match exit {
+                    Statement::Return { keyword, .. }
+                    | Statement::Break(keyword)
+                    | Statement::Continue(keyword) => {
+                        format!("Any code after this `{}` is unreachable.", keyword.ty)
+                    }
+                    _ => String::from("Every branch of this `if` leaves the block."),
+                };
+                self.report(
+                    Lint::UnreachableCode,
+                    diag!(
+                        DiagnosticKind::Warning,
+                        "Unreachable statement.",
+                        Some(String::from("This statement never runs.")),
+                        next.span()
+                    )
+                    .with_label(exit.span(), label),
+                );
+            }
+        }
+        for stmt in &block.0 {
+            self.stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::VarDecl { name, expr, .. } => {
+                self.expr(expr);
+                self.declare(name, "variable");
+            }
+            Statement::Const(constant) => {
+                self.expr(&constant.expr);
+                self.declare(&constant.name, "constant");
+            }
+            Statement::Return { expr, .. } | Statement::Expression(expr) => self.expr(expr),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.block(else_branch);
+                }
+            }
+            Statement::While { condition, body } => {
+                self.expr(condition);
+                self.block(body);
+            }
+            Statement::For { var, range, body } => {
+                self.expr(range);
+                self.scopes.push(HashMap::new());
+                self.declare(var, "variable");
+                self.block(body);
+                self.scopes.pop();
+            }
+            Statement::Break(_) | Statement::Continue(_) | Statement::Unknown => {}
+        }
+    }
+
+    /// Looks for the blocks in `expr`, which are only found in the arms of a `match`.
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::Unary { rhs: expr, .. }
+            | Expr::Grouping(expr)
+            | Expr::Field { object: expr, .. } => self.expr(expr),
+            Expr::Call { callee, args } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Range { start, end, .. } => {
+                self.expr(start);
+                self.expr(end);
+            }
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::Index { object, index } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.scopes.push(HashMap::new());
+                    self.pattern(&arm.pattern);
+                    match &arm.body {
+                        ArmBody::Expr(expr) => self.expr(expr),
+                        ArmBody::Block(block) => self.block(block),
+                    }
+                    self.scopes.pop();
+                }
+            }
+            Expr::StructLiteral { fields, .. } => {
+                for field in fields {
+                    self.expr(&field.value);
+                }
+            }
+            Expr::Assign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Expr::Literal(_) | Expr::Var(_) | Expr::Path(_) | Expr::None | Expr::Unknown => {}
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding(name) => self.declare(name, "variable"),
+            Pattern::Variant { fields, .. } => {
+                for field in fields {
+                    self.pattern(field);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+        }
+    }
+
+    /// Checks the declaration of a local variable or constant, named by `kind`
+    /// in the diagnostics, then adds it to the current scope.
+    fn declare(&mut self, name: &Token, kind: &str) {
+        let (current, enclosing) = self.scopes.split_last().unwrap();
+        let shadowed = match current.contains_key(name.as_ident()) {
+            // A duplicate definition in the same scope is an error of its own.
+            true => None,
+            false => enclosing
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name.as_ident()).copied()),
+        };
+        if let Some(shadowed) = shadowed {
+            self.report(
+                Lint::ShadowedBinding,
+                diag!(
+                    DiagnosticKind::Warning,
+                    format!("`{}` shadows an earlier binding.", name.ty),
+                    Some(String::from("This declares a new variable.")),
+                    name.span
+                )
+                .with_label(
+                    shadowed,
+                    format!("The shadowed `{}` is declared here.", name.ty),
+                ),
+            );
+        }
+
+        if self.is_unused(name) {
+            self.report(
+                Lint::UnusedVariable,
+                diag!(
+                    DiagnosticKind::Warning,
+                    format!("Unused {} `{}`.", kind, name.ty),
+                    Some(String::from("Never used after this declaration.")),
+                    name.span
+                )
+                .with_help(format!("If this is intentional, name it `_{}`.", name.ty)),
+            );
+        }
+        self.bind(name);
+    }
+
+    fn bind(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.as_ident().to_string(), name.span);
+        }
+    }
+
+    /// Returns true if the binding `name` is never used. Names starting with
+    /// `_` are meant to be unused.
+    fn is_unused(&self, name: &Token) -> bool {
+        !name.as_ident().starts_with('_') && !self.used.contains(&name.span)
+    }
+
+    /// Reports `diagnostic` at the level of `lint` in the file it points into.
+    fn report(&mut self, lint: Lint, mut diagnostic: Diagnostic) {
+        let allowed = diagnostic
+            .span
+            .and_then(|span| self.allowed.get(&span.file_id))
+            .is_some_and(|allowed| allowed.contains(&lint));
+        let kind = match self.levels.level(lint) {
+            _ if allowed => return,
+            Level::Allow => return,
+            Level::Warn => DiagnosticKind::Warning,
+            Level::Deny => DiagnosticKind::Error,
+        };
+        diagnostic.kind = kind;
+        diagnostic = diagnostic.with_code(lint.code());
+        if self.explained.insert(lint) {
+            diagnostic = diagnostic.with_note(self.levels.reason(lint));
+        }
+        self.compiler.reporter.borrow_mut().add(diagnostic);
+    }
+}
+
+/// Converts a name such as `parseHTTPHeader` to `parse_http_header`.
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() {
+            let previous = i.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(i + 1);
+            // A word starts after a lowercase letter or a digit, and before
+            // the last capital of an acronym followed by a lowercase letter.
+            let after_word = previous.is_some_and(|c| c.is_lowercase() || c.is_ascii_digit());
+            let ends_acronym =
+                previous.is_some_and(char::is_uppercase) && next.is_some_and(|c| c.is_lowercase());
+            if after_word || ends_acronym {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
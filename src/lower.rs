@@ -0,0 +1,993 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{self, ArmBody, Ast, BinOp, Block, Expr, Item, MatchArm, Pattern, Statement},
+    builtins::Builtin,
+    ir::{
+        BinaryOp, BlockId, Function, Inst, InstKind, Module, Target, Terminator, UnaryOp, ValueId,
+    },
+    resolver::{Resolutions, SymbolKind},
+    types::{Type, TypeInfo},
+    utils::{Span, Token, TokenType as Ty},
+    value::Value,
+};
+
+/// The blocks that `continue` and `break` jump to inside a loop.
+struct Loop {
+    next: BlockId,
+    exit: BlockId,
+}
+
+/// A step from a value to one of its parts on the way to an assignment target.
+enum Step {
+    Field(String),
+    /// An element, by its already evaluated index.
+    Index(ValueId),
+}
+
+/// Lowers a type checked program to SSA form. Local variables become SSA values
+/// as the code is lowered, following "Simple and Efficient Construction of
+/// Static Single Assignment Form" (Braun et al.): a variable read in a block
+/// with several predecessors becomes a block parameter.
+pub struct Lowering<'a> {
+    resolutions: &'a Resolutions,
+    types: &'a TypeInfo,
+    module: Module,
+    /// The names of the global constants, by the span of their name.
+    globals: HashMap<Span, String>,
+    function: Function,
+    current: BlockId,
+    /// The value of every local in the blocks that define it, by the span of the declaring name.
+    defs: HashMap<(Span, BlockId), ValueId>,
+    /// Blocks whose predecessors are all known.
+    sealed: HashSet<BlockId>,
+    /// Parameters added to blocks before they were sealed, whose arguments are still missing.
+    incomplete: HashMap<BlockId, Vec<(Span, ValueId)>>,
+    predecessors: HashMap<BlockId, Vec<BlockId>>,
+    loops: Vec<Loop>,
+}
+
+impl<'a> Lowering<'a> {
+    pub fn new(resolutions: &'a Resolutions, types: &'a TypeInfo) -> Self {
+        Self {
+            resolutions,
+            types,
+            module: Module::default(),
+            globals: HashMap::new(),
+            function: Function::new(Module::INIT, Type::Unit, Span::default()),
+            current: BlockId(0),
+            defs: HashMap::new(),
+            sealed: HashSet::new(),
+            incomplete: HashMap::new(),
+            predecessors: HashMap::new(),
+            loops: Vec::new(),
+        }
+    }
+
+    pub fn lower(mut self, ast: &Ast) -> Module {
+        for item in &ast.items {
+            if let Item::Const(constant) = item {
+                let name = constant.name.as_ident().to_string();
+                let ty = self.types.binding(&constant.name).clone();
+                self.globals.insert(constant.name.span, name.clone());
+                self.module.globals.push((name, ty));
+            }
+        }
+
+        self.begin_function(Module::INIT, Type::Unit, Span::default());
+        for item in &ast.items {
+            if let Item::Const(constant) = item {
+                let value = self.value(&constant.expr);
+                let name = self.globals[&constant.name.span].clone();
+                self.inst(InstKind::SetGlobal(name, value), constant.name.span);
+            }
+        }
+        self.end_function();
+
+        for item in &ast.items {
+            match item {
+                Item::Func(function) => {
+                    let name = function.name.as_ident();
+                    let return_ty = self.types.signature(name).unwrap().return_ty.clone();
+                    self.function(function, name.to_string(), return_ty);
+                }
+                Item::Class(class) => {
+                    let info = self.types.class(class.name.as_ident()).unwrap();
+                    for method in &class.methods {
+                        let return_ty = info.methods[method.name.as_ident()].return_ty.clone();
+                        let name = format!("{}.{}", class.name.as_ident(), method.name.as_ident());
+                        self.function(method, name, return_ty);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.module
+    }
+
+    fn function(&mut self, function: &ast::Function, name: String, return_ty: Type) {
+        self.begin_function(&name, return_ty, function.name.span);
+        let params = function
+            .self_param
+            .iter()
+            .chain(function.params.iter().map(|param| &param.name));
+        for param in params {
+            let ty = self.types.binding(param).clone();
+            let value = self.function.add_param(BlockId(0), ty);
+            self.write_var(param.span, self.current, value);
+        }
+        self.block(&function.body);
+        self.end_function();
+    }
+
+    fn begin_function(&mut self, name: &str, return_ty: Type, span: Span) {
+        self.function = Function::new(name, return_ty, span);
+        self.current = self.function.entry();
+        self.defs.clear();
+        self.sealed.clear();
+        self.incomplete.clear();
+        self.predecessors.clear();
+        self.sealed.insert(self.current);
+    }
+
+    /// Ends the function at the end of its body and adds it to the module.
+    fn end_function(&mut self) {
+        let span = self.function.span;
+        let terminator = match self.function.return_ty {
+            Type::Unit => Terminator::Return(None),
+            // The type checker makes sure that a function returning a value
+            // never reaches the end of its body.
+            _ => Terminator::Unreachable,
+        };
+        self.terminate(terminator, span);
+
+        let mut function = std::mem::replace(
+            &mut self.function,
+            Function::new("", Type::Unit, Span::default()),
+        );
+        for block in &mut function.blocks {
+            if block.terminator.is_none() {
+                block.terminator = Some((Terminator::Unreachable, span));
+            }
+        }
+        function.remove_trivial_params();
+        function.compact();
+        self.module.functions.push(function);
+    }
+
+    fn block(&mut self, block: &Block) {
+        for stmt in &block.0 {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::VarDecl { name, expr, .. } => {
+                let value = match expr {
+                    Expr::None => self.default_value(self.types.binding(name).clone(), name.span),
+                    expr => self.value(expr),
+                };
+                self.write_var(name.span, self.current, value);
+            }
+            Statement::Const(constant) => {
+                let value = self.value(&constant.expr);
+                self.write_var(constant.name.span, self.current, value);
+            }
+            Statement::Return { keyword, expr } => {
+                let value = match expr {
+                    Expr::None => None,
+                    expr => self.expr(expr),
+                };
+                self.terminate(Terminator::Return(value), keyword.span);
+            }
+            Statement::Expression(expr) => {
+                self.expr(expr);
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let span = condition.span();
+                let condition = self.value(condition);
+                let then_block = self.new_block();
+                let else_block = self.new_block();
+                let merge = self.new_block();
+                self.branch(condition, then_block, else_block, span);
+                self.seal(then_block);
+                self.seal(else_block);
+
+                self.switch_to(then_block);
+                self.block(then_branch);
+                self.jump(merge, Vec::new(), span);
+                self.switch_to(else_block);
+                if let Some(else_branch) = else_branch {
+                    self.block(else_branch);
+                }
+                self.jump(merge, Vec::new(), span);
+                self.seal(merge);
+                self.switch_to(merge);
+            }
+            Statement::While { condition, body } => {
+                let span = condition.span();
+                let header = self.new_block();
+                let body_block = self.new_block();
+                let exit = self.new_block();
+                self.jump(header, Vec::new(), span);
+
+                self.switch_to(header);
+                let condition = self.value(condition);
+                self.branch(condition, body_block, exit, span);
+                self.seal(body_block);
+                self.switch_to(body_block);
+                self.loop_body(body, header, exit);
+                self.jump(header, Vec::new(), span);
+                self.seal(header);
+                self.seal(exit);
+                self.switch_to(exit);
+            }
+            Statement::For { var, range, body } => {
+                let Expr::Range {
+                    start,
+                    end,
+                    inclusive,
+                } = range
+                else {
+                    unreachable!()
+                };
+                let span = range.span();
+                let start = self.value(start);
+                let end = self.value(end);
+                self.write_var(var.span, self.current, start);
+
+                let header = self.new_block();
+                let body_block = self.new_block();
+                let next = self.new_block();
+                let exit = self.new_block();
+                self.jump(header, Vec::new(), span);
+
+                self.switch_to(header);
+                let i = self.read_var(var.span, header);
+                let compare = if *inclusive {
+                    BinaryOp::Le
+                } else {
+                    BinaryOp::Lt
+                };
+                let in_range = self.binary(compare, i, end, Type::Bool, span);
+                self.branch(in_range, body_block, exit, span);
+                self.seal(body_block);
+                self.switch_to(body_block);
+                self.loop_body(body, next, exit);
+                self.jump(next, Vec::new(), span);
+                self.seal(next);
+
+                // An inclusive range stops at its end without computing the
+                // next value, which could overflow.
+                self.switch_to(next);
+                let i = self.read_var(var.span, next);
+                if *inclusive {
+                    let increment = self.new_block();
+                    let at_end = self.binary(BinaryOp::Eq, i, end, Type::Bool, span);
+                    self.branch(at_end, exit, increment, span);
+                    self.seal(increment);
+                    self.switch_to(increment);
+                }
+                let one = self.constant(Value::Int(1), var.span);
+                let i = self.binary(BinaryOp::Add, i, one, Type::Int, var.span);
+                self.write_var(var.span, self.current, i);
+                self.jump(header, Vec::new(), span);
+                self.seal(header);
+                self.seal(exit);
+                self.switch_to(exit);
+            }
+            Statement::Break(keyword) => {
+                let exit = self.loops.last().unwrap().exit;
+                self.jump(exit, Vec::new(), keyword.span);
+            }
+            Statement::Continue(keyword) => {
+                let next = self.loops.last().unwrap().next;
+                self.jump(next, Vec::new(), keyword.span);
+            }
+            Statement::Unknown => {}
+        }
+    }
+
+    fn loop_body(&mut self, body: &Block, next: BlockId, exit: BlockId) {
+        self.loops.push(Loop { next, exit });
+        self.block(body);
+        self.loops.pop();
+    }
+
+    /// Lowers `expr`, producing a value even if its type is `()`.
+    fn value(&mut self, expr: &Expr) -> ValueId {
+        match self.expr(expr) {
+            Some(value) => value,
+            None => self.constant(Value::Unit, expr.span()),
+        }
+    }
+
+    /// Lowers `expr` and returns its value, or `None` if it has type `()`.
+    fn expr(&mut self, expr: &Expr) -> Option<ValueId> {
+        let span = expr.span();
+        match expr {
+            Expr::Binary {
+                lhs,
+                op: op @ (BinOp::And | BinOp::Or),
+                rhs,
+            } => {
+                let lhs = self.value(lhs);
+                let rhs_block = self.new_block();
+                let merge = self.new_block();
+                let result = self.function.add_param(merge, Type::Bool);
+                match op {
+                    BinOp::And => self.terminate(
+                        Terminator::Branch {
+                            condition: lhs,
+                            then: target(rhs_block, Vec::new()),
+                            otherwise: target(merge, vec![lhs]),
+                        },
+                        span,
+                    ),
+                    _ => self.terminate(
+                        Terminator::Branch {
+                            condition: lhs,
+                            then: target(merge, vec![lhs]),
+                            otherwise: target(rhs_block, Vec::new()),
+                        },
+                        span,
+                    ),
+                }
+                self.seal(rhs_block);
+                self.switch_to(rhs_block);
+                let rhs = self.value(rhs);
+                self.jump(merge, vec![rhs], span);
+                self.seal(merge);
+                self.switch_to(merge);
+                Some(result)
+            }
+            Expr::Binary { lhs, op, rhs } => {
+                let ty = self.type_of(expr);
+                let lhs = self.value(lhs);
+                let rhs = self.value(rhs);
+                Some(self.binary(binary_op(op), lhs, rhs, ty, span))
+            }
+            Expr::Unary { op, rhs } => {
+                let ty = self.type_of(rhs);
+                let value = self.value(rhs);
+                let op = match op {
+                    ast::UnaryOp::Negative => UnaryOp::Neg,
+                    ast::UnaryOp::Negate => UnaryOp::Not,
+                    ast::UnaryOp::Unknown => return Some(value),
+                };
+                self.inst_with_type(InstKind::Unary(op, value), ty, span)
+            }
+            Expr::Literal(literal) => Some(self.constant(Value::from_literal(literal), span)),
+            Expr::Var(name) => {
+                let symbol = *self.resolutions.get(name)?;
+                match self.globals.get(&symbol.span) {
+                    Some(global) => {
+                        let ty = self.types.binding_by_span(symbol.span).clone();
+                        self.inst_with_type(InstKind::Global(global.clone()), ty, span)
+                    }
+                    None => Some(self.read_var(symbol.span, self.current)),
+                }
+            }
+            Expr::Grouping(expr) => self.expr(expr),
+            Expr::Call { callee, args } => self.call(expr, callee, args),
+            Expr::Path(path) => {
+                let (name, variant) = variant_path(path);
+                self.inst_with_type(
+                    InstKind::Variant(name.clone(), variant, Vec::new()),
+                    Type::Enum(name),
+                    span,
+                )
+            }
+            Expr::Match {
+                keyword,
+                scrutinee,
+                arms,
+            } => self.match_expr(expr, keyword, scrutinee, arms),
+            Expr::StructLiteral { name, fields } => {
+                let mut values = HashMap::new();
+                for field in fields {
+                    let value = self.value(&field.value);
+                    values.insert(field.name.as_ident(), value);
+                }
+                // Fields are evaluated in the order they are written and stored in declaration order.
+                let order = self
+                    .types
+                    .struct_fields(name.as_ident())
+                    .unwrap_or_default();
+                let mut ordered = Vec::new();
+                for (field, _) in order {
+                    let value = match values.get(field.as_str()) {
+                        Some(value) => *value,
+                        None => self.constant(Value::Unit, span),
+                    };
+                    ordered.push(value);
+                }
+                let name = name.as_ident().to_string();
+                self.inst_with_type(
+                    InstKind::Struct(name.clone(), ordered),
+                    Type::Struct(name),
+                    span,
+                )
+            }
+            Expr::Field { object, field } => {
+                let ty = self.type_of(expr);
+                let object = self.value(object);
+                let field = field.as_ident().to_string();
+                self.inst_with_type(InstKind::Field(object, field), ty, span)
+            }
+            Expr::Array { elements, .. } => {
+                let ty = self.type_of(expr);
+                let elements: Vec<ValueId> =
+                    elements.iter().map(|element| self.value(element)).collect();
+                self.inst_with_type(InstKind::Array(elements), ty, span)
+            }
+            Expr::Index { object, index } => {
+                let ty = self.type_of(expr);
+                let object = self.value(object);
+                let index = self.value(index);
+                self.inst_with_type(InstKind::Index(object, index), ty, span)
+            }
+            Expr::Assign { target, op, value } => {
+                self.assign(target, op.as_ref(), value, span);
+                None
+            }
+            Expr::Range { .. } | Expr::None | Expr::Unknown => None,
+        }
+    }
+
+    /// Calls are located at the name of the callee, like the errors that the
+    /// interpreter reports in them.
+    fn call(&mut self, expr: &Expr, callee: &Expr, args: &[Expr]) -> Option<ValueId> {
+        let span = expr.span();
+        let ty = self.type_of(expr);
+        match callee {
+            Expr::Var(name) => match self.resolutions.get(name).map(|symbol| symbol.kind) {
+                Some(SymbolKind::Function) => {
+                    let args = self.args(args);
+                    let name = name.as_ident().to_string();
+                    self.inst_with_type(InstKind::Call(name, args), ty, callee.span())
+                }
+                Some(SymbolKind::Class) => {
+                    let class = name.as_ident().to_string();
+                    let object = self.inst_with_type(InstKind::New(class.clone()), ty, span)?;
+                    let init = self
+                        .types
+                        .class(&class)
+                        .is_some_and(|info| info.methods.contains_key("init"));
+                    if init {
+                        let mut init_args = vec![object];
+                        init_args.extend(self.args(args));
+                        self.inst_with_type(
+                            InstKind::Call(format!("{}.init", class), init_args),
+                            Type::Unit,
+                            callee.span(),
+                        );
+                    }
+                    Some(object)
+                }
+                Some(SymbolKind::Builtin) => {
+                    let builtin = Builtin::from_name(name.as_ident()).unwrap();
+                    let args = self.args(args);
+                    self.inst_with_type(InstKind::CallBuiltin(builtin, args), ty, span)
+                }
+                _ => None,
+            },
+            Expr::Field { object, field } => {
+                let Type::Class(class) = self.type_of(object) else {
+                    return None;
+                };
+                let mut method_args = vec![self.value(object)];
+                method_args.extend(self.args(args));
+                let name = format!("{}.{}", class, field.as_ident());
+                self.inst_with_type(InstKind::Call(name, method_args), ty, field.span)
+            }
+            Expr::Path(path) => {
+                let (name, variant) = variant_path(path);
+                let args = self.args(args);
+                self.inst_with_type(InstKind::Variant(name, variant, args), ty, span)
+            }
+            _ => None,
+        }
+    }
+
+    fn args(&mut self, args: &[Expr]) -> Vec<ValueId> {
+        args.iter().map(|arg| self.value(arg)).collect()
+    }
+
+    /// Lowers an assignment. The new value and every index along the target are
+    /// evaluated first. Then the parts of the target are read from the root
+    /// variable outwards and rebuilt with the new value from the inside out,
+    /// stopping at the first object, whose field is written in place.
+    fn assign(&mut self, target: &Expr, op: Option<&BinOp>, value: &Expr, span: Span) {
+        let value = self.value(value);
+
+        let mut steps = Vec::new();
+        let mut place = target;
+        loop {
+            match place {
+                Expr::Field { object, field } => {
+                    steps.push((Step::Field(field.as_ident().to_string()), place));
+                    place = object;
+                }
+                Expr::Index { object, index } => {
+                    let index = self.value(index);
+                    steps.push((Step::Index(index), place));
+                    place = object;
+                }
+                _ => break,
+            }
+        }
+        steps.reverse();
+
+        let root = match place {
+            Expr::Var(name) => self
+                .resolutions
+                .get(name)
+                .filter(|symbol| !self.globals.contains_key(&symbol.span))
+                .map(|symbol| symbol.span),
+            _ => None,
+        };
+        // `containers[i]` holds the value that step `i` is applied to. Only a
+        // compound assignment reads the current value of the target itself.
+        let reads = steps.len() + usize::from(op.is_some());
+        let mut containers = Vec::new();
+        if reads > 0 {
+            containers.push(self.value(place));
+        }
+        for (step, part) in steps.iter().take(reads.saturating_sub(1)) {
+            let container = *containers.last().unwrap();
+            let ty = self.type_of(part);
+            let value = match step {
+                Step::Field(field) => InstKind::Field(container, field.clone()),
+                Step::Index(index) => InstKind::Index(container, *index),
+            };
+            let value = self.inst_with_type(value, ty, span).unwrap();
+            containers.push(value);
+        }
+
+        let mut value = match op {
+            Some(op) => {
+                let current = containers.pop().unwrap();
+                let ty = self.function.ty(current).clone();
+                self.binary(binary_op(op), current, value, ty, span)
+            }
+            None => value,
+        };
+        for ((step, _), container) in steps.iter().zip(containers).rev() {
+            let ty = self.function.ty(container).clone();
+            match (step, &ty) {
+                (Step::Field(field), Type::Class(_)) => {
+                    self.inst(InstKind::StoreField(container, field.clone(), value), span);
+                    return;
+                }
+                (Step::Field(field), _) => {
+                    value = self
+                        .inst_with_type(
+                            InstKind::InsertField(container, field.clone(), value),
+                            ty,
+                            span,
+                        )
+                        .unwrap();
+                }
+                (Step::Index(index), _) => {
+                    value = self
+                        .inst_with_type(InstKind::InsertElement(container, *index, value), ty, span)
+                        .unwrap();
+                }
+            }
+        }
+        if let Some(root) = root {
+            self.write_var(root, self.current, value);
+        }
+    }
+
+    fn match_expr(
+        &mut self,
+        expr: &Expr,
+        keyword: &Token,
+        scrutinee: &Expr,
+        arms: &[MatchArm],
+    ) -> Option<ValueId> {
+        let ty = self.type_of(expr);
+        let scrutinee = self.value(scrutinee);
+        let end = self.new_block();
+        let result = match ty {
+            Type::Unit => None,
+            ref ty => Some(self.function.add_param(end, ty.clone())),
+        };
+
+        for arm in arms {
+            let next_arm = self.new_block();
+            let span = arm.pattern.span();
+            self.pattern(&arm.pattern, scrutinee, next_arm);
+            let value = match &arm.body {
+                ArmBody::Expr(expr) => self.expr(expr),
+                ArmBody::Block(block) => {
+                    self.block(block);
+                    None
+                }
+            };
+            let args = match (result, value) {
+                (Some(_), Some(value)) => vec![value],
+                (Some(_), None) => vec![self.constant(Value::Unit, span)],
+                (None, _) => Vec::new(),
+            };
+            self.jump(end, args, span);
+            self.seal(next_arm);
+            self.switch_to(next_arm);
+        }
+
+        self.terminate(Terminator::NoMatch(scrutinee), keyword.span);
+        self.seal(end);
+        self.switch_to(end);
+        result
+    }
+
+    /// Lowers the test of whether `value` matches `pattern`, jumping to
+    /// `next_arm` if it does not, and binds the names in the pattern.
+    fn pattern(&mut self, pattern: &Pattern, value: ValueId, next_arm: BlockId) {
+        let span = pattern.span();
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding(name) => self.write_var(name.span, self.current, value),
+            Pattern::Literal(expr) => {
+                let literal = self.value(expr);
+                let matches = self.binary(BinaryOp::Eq, value, literal, Type::Bool, span);
+                self.test(matches, next_arm, span);
+            }
+            Pattern::Variant { path, fields } => {
+                let (name, variant) = variant_path(path);
+                let is_variant = self
+                    .inst_with_type(
+                        InstKind::IsVariant(value, variant.clone()),
+                        Type::Bool,
+                        span,
+                    )
+                    .unwrap();
+                self.test(is_variant, next_arm, span);
+                let field_types = self
+                    .types
+                    .enum_variants(&name)
+                    .unwrap_or_default()
+                    .iter()
+                    .find(|(candidate, _)| *candidate == variant)
+                    .map(|(_, fields)| fields.clone())
+                    .unwrap_or_default();
+                for (i, field) in fields.iter().enumerate() {
+                    if let Pattern::Wildcard(_) = field {
+                        continue;
+                    }
+                    let ty = field_types.get(i).cloned().unwrap_or(Type::Error);
+                    let field_value = self
+                        .inst_with_type(InstKind::VariantField(value, i), ty, field.span())
+                        .unwrap();
+                    self.pattern(field, field_value, next_arm);
+                }
+            }
+        }
+    }
+
+    /// Continues in a new block if `condition` is true and jumps to `otherwise` if not.
+    fn test(&mut self, condition: ValueId, otherwise: BlockId, span: Span) {
+        let then = self.new_block();
+        self.branch(condition, then, otherwise, span);
+        self.seal(then);
+        self.switch_to(then);
+    }
+
+    /// Returns the type of `expr`, which the type checker has already established.
+    fn type_of(&self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Binary { lhs, op, .. } => match binary_op_checked(op) {
+                Some(op) if !op.is_comparison() => self.type_of(lhs),
+                _ => Type::Bool,
+            },
+            Expr::Unary { rhs, .. } => self.type_of(rhs),
+            Expr::Literal(literal) => match &literal.ty {
+                Ty::Integer(_) => Type::Int,
+                Ty::Float(_) => Type::Float,
+                Ty::Char(_) => Type::Char,
+                Ty::String(_) => Type::String,
+                Ty::Bool(_) => Type::Bool,
+                _ => Type::Error,
+            },
+            Expr::Var(name) => match self.resolutions.get(name) {
+                Some(symbol) => self.types.binding_by_span(symbol.span).clone(),
+                None => Type::Error,
+            },
+            Expr::Grouping(expr) => self.type_of(expr),
+            Expr::Call { callee, .. } => match callee.as_ref() {
+                Expr::Var(name) => match self.resolutions.get(name).map(|symbol| symbol.kind) {
+                    Some(SymbolKind::Function) => self
+                        .types
+                        .signature(name.as_ident())
+                        .map(|signature| signature.return_ty.clone())
+                        .unwrap_or(Type::Error),
+                    Some(SymbolKind::Class) => Type::Class(name.as_ident().to_string()),
+                    Some(SymbolKind::Builtin) => match Builtin::from_name(name.as_ident()) {
+                        Some(Builtin::Len) => Type::Int,
+                        _ => Type::Unit,
+                    },
+                    _ => Type::Error,
+                },
+                Expr::Field { object, field } => match self.type_of(object) {
+                    Type::Class(class) => self
+                        .types
+                        .class(&class)
+                        .and_then(|info| info.methods.get(field.as_ident()))
+                        .map(|signature| signature.return_ty.clone())
+                        .unwrap_or(Type::Error),
+                    _ => Type::Error,
+                },
+                Expr::Path(path) => Type::Enum(path[0].as_ident().to_string()),
+                _ => Type::Error,
+            },
+            Expr::Path(path) => Type::Enum(path[0].as_ident().to_string()),
+            Expr::Match { arms, .. } => arms
+                .iter()
+                .find_map(|arm| match &arm.body {
+                    ArmBody::Expr(expr) => Some(self.type_of(expr)),
+                    ArmBody::Block(_) => None,
+                })
+                .unwrap_or(Type::Unit),
+            Expr::StructLiteral { name, .. } => Type::Struct(name.as_ident().to_string()),
+            Expr::Field { object, field } => self
+                .types
+                .fields(&self.type_of(object))
+                .and_then(|fields| {
+                    fields
+                        .iter()
+                        .find(|(name, _)| name == field.as_ident())
+                        .map(|(_, ty)| ty.clone())
+                })
+                .unwrap_or(Type::Error),
+            Expr::Array { elements, .. } => match elements.first() {
+                Some(element) => Type::Array(Box::new(self.type_of(element))),
+                None => Type::Array(Box::new(Type::Error)),
+            },
+            Expr::Index { object, .. } => match self.type_of(object) {
+                Type::Array(element) => *element,
+                _ => Type::Error,
+            },
+            Expr::Assign { .. } | Expr::Range { .. } | Expr::None | Expr::Unknown => Type::Unit,
+        }
+    }
+
+    /// Returns the value a variable of type `ty` holds when it is declared without an initializer.
+    fn default_value(&mut self, ty: Type, span: Span) -> ValueId {
+        match ty {
+            // A class variable holds no object until one is assigned.
+            Type::Class(_) => {
+                let result = self.function.add_value(ty);
+                self.push(Some(result), InstKind::Const(Value::Unit), span);
+                result
+            }
+            ty => self.constant(Value::default_for(&ty, self.types), span),
+        }
+    }
+
+    fn constant(&mut self, value: Value, span: Span) -> ValueId {
+        let ty = match &value {
+            Value::Int(_) => Type::Int,
+            Value::Float(_) => Type::Float,
+            Value::Bool(_) => Type::Bool,
+            Value::Char(_) => Type::Char,
+            Value::String(_) => Type::String,
+            Value::Unit => Type::Unit,
+            // Default values of arrays, structs and enums are built from their parts.
+            value => return self.aggregate(value.clone(), span),
+        };
+        let result = self.function.add_value(ty);
+        self.push(Some(result), InstKind::Const(value), span);
+        result
+    }
+
+    /// Builds a default value that is not a scalar, such as an empty array or a
+    /// struct whose fields have their default values.
+    fn aggregate(&mut self, value: Value, span: Span) -> ValueId {
+        let (kind, ty) = match value {
+            Value::Array(_) => (
+                InstKind::Array(Vec::new()),
+                Type::Array(Box::new(Type::Error)),
+            ),
+            Value::Struct { name, fields } => {
+                let fields = fields
+                    .into_iter()
+                    .map(|(_, value)| self.constant(value, span))
+                    .collect();
+                (InstKind::Struct(name.clone(), fields), Type::Struct(name))
+            }
+            Value::Enum {
+                name,
+                variant,
+                fields,
+            } => {
+                let fields = fields
+                    .into_iter()
+                    .map(|value| self.constant(value, span))
+                    .collect();
+                (
+                    InstKind::Variant(name.clone(), variant, fields),
+                    Type::Enum(name),
+                )
+            }
+            _ => unreachable!(),
+        };
+        let result = self.function.add_value(ty);
+        self.push(Some(result), kind, span);
+        result
+    }
+
+    fn binary(
+        &mut self,
+        op: BinaryOp,
+        lhs: ValueId,
+        rhs: ValueId,
+        ty: Type,
+        span: Span,
+    ) -> ValueId {
+        let result = self.function.add_value(ty);
+        self.push(Some(result), InstKind::Binary(op, lhs, rhs), span);
+        result
+    }
+
+    /// Adds an instruction that defines no value.
+    fn inst(&mut self, kind: InstKind, span: Span) {
+        self.push(None, kind, span);
+    }
+
+    /// Adds an instruction whose result has type `ty`, and returns the result unless it is `()`.
+    fn inst_with_type(&mut self, kind: InstKind, ty: Type, span: Span) -> Option<ValueId> {
+        let result = match ty {
+            Type::Unit => None,
+            ty => Some(self.function.add_value(ty)),
+        };
+        self.push(result, kind, span);
+        result
+    }
+
+    fn push(&mut self, result: Option<ValueId>, kind: InstKind, span: Span) {
+        let current = self.current;
+        self.function
+            .block_mut(current)
+            .insts
+            .push(Inst { result, kind, span });
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.function.add_block()
+    }
+
+    fn switch_to(&mut self, block: BlockId) {
+        self.current = block;
+    }
+
+    fn jump(&mut self, block: BlockId, args: Vec<ValueId>, span: Span) {
+        self.terminate(Terminator::Jump(target(block, args)), span);
+    }
+
+    fn branch(&mut self, condition: ValueId, then: BlockId, otherwise: BlockId, span: Span) {
+        self.terminate(
+            Terminator::Branch {
+                condition,
+                then: target(then, Vec::new()),
+                otherwise: target(otherwise, Vec::new()),
+            },
+            span,
+        );
+    }
+
+    /// Ends the current block. Code that follows, such as the rest of a block
+    /// after `return`, goes into a new block without predecessors, which is
+    /// removed once the function is complete.
+    fn terminate(&mut self, terminator: Terminator, span: Span) {
+        let current = self.current;
+        // Jumps out of a block that cannot be reached add no predecessors, so
+        // that they do not add parameters to the blocks they jump to.
+        let reachable =
+            current == self.function.entry() || self.predecessors.contains_key(&current);
+        for target in terminator.targets().into_iter().filter(|_| reachable) {
+            self.predecessors
+                .entry(target.block)
+                .or_default()
+                .push(current);
+        }
+        self.function.block_mut(current).terminator = Some((terminator, span));
+
+        let dead = self.new_block();
+        self.seal(dead);
+        self.switch_to(dead);
+    }
+
+    fn write_var(&mut self, var: Span, block: BlockId, value: ValueId) {
+        self.defs.insert((var, block), value);
+    }
+
+    /// Returns the value of the local declared at `var` at the end of `block`.
+    fn read_var(&mut self, var: Span, block: BlockId) -> ValueId {
+        if let Some(value) = self.defs.get(&(var, block)) {
+            return *value;
+        }
+
+        let ty = self.types.binding_by_span(var).clone();
+        let predecessors = self.predecessors.get(&block).cloned().unwrap_or_default();
+        let value = if !self.sealed.contains(&block) {
+            let param = self.function.add_param(block, ty);
+            self.incomplete.entry(block).or_default().push((var, param));
+            param
+        } else if predecessors.len() == 1 {
+            self.read_var(var, predecessors[0])
+        } else {
+            // The parameter is defined first, so that loops reading the variable end here.
+            let param = self.function.add_param(block, ty);
+            self.write_var(var, block, param);
+            self.add_param_args(var, block);
+            param
+        };
+        self.write_var(var, block, value);
+        value
+    }
+
+    /// Passes the value of `var` at the end of every predecessor of `block` to its newest parameter.
+    fn add_param_args(&mut self, var: Span, block: BlockId) {
+        let mut predecessors = self.predecessors.get(&block).cloned().unwrap_or_default();
+        predecessors.dedup();
+        for predecessor in predecessors {
+            let value = self.read_var(var, predecessor);
+            if let Some((terminator, _)) = &mut self.function.block_mut(predecessor).terminator {
+                for target in terminator.targets_mut() {
+                    if target.block == block {
+                        target.args.push(value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records that every predecessor of `block` is known, and completes the
+    /// parameters that were added to it before.
+    fn seal(&mut self, block: BlockId) {
+        for (var, _) in self.incomplete.remove(&block).unwrap_or_default() {
+            self.add_param_args(var, block);
+        }
+        self.sealed.insert(block);
+    }
+}
+
+fn target(block: BlockId, args: Vec<ValueId>) -> Target {
+    Target { block, args }
+}
+
+fn variant_path(path: &[Token]) -> (String, String) {
+    (
+        path[0].as_ident().to_string(),
+        path[path.len() - 1].as_ident().to_string(),
+    )
+}
+
+fn binary_op(op: &BinOp) -> BinaryOp {
+    binary_op_checked(op).unwrap()
+}
+
+/// Returns the IR operator for `op`, or `None` for the short-circuiting `&&` and `||`.
+fn binary_op_checked(op: &BinOp) -> Option<BinaryOp> {
+    Some(match op {
+        BinOp::Add => BinaryOp::Add,
+        BinOp::Subtract => BinaryOp::Sub,
+        BinOp::Multiply => BinaryOp::Mul,
+        BinOp::Divide => BinaryOp::Div,
+        BinOp::Eq => BinaryOp::Eq,
+        BinOp::NotEq => BinaryOp::Ne,
+        BinOp::LT => BinaryOp::Lt,
+        BinOp::LTOrEq => BinaryOp::Le,
+        BinOp::GT => BinaryOp::Gt,
+        BinOp::GTOrEq => BinaryOp::Ge,
+        BinOp::And | BinOp::Or => return None,
+    })
+}
@@ -1,39 +1,277 @@
-use clap::{Arg, Command, Parser};
-use compiler::Compiler;
-use lexer::Lexer;
+use std::path::{Path, PathBuf};
 
-mod compiler;
-mod diagnostic;
-mod printer;
-mod utils;
+use clap::Parser;
+use olive::{
+    ast::Ast,
+    checker::TypeChecker,
+    compiler::Compiler,
+    diagnostic::ErrorFormat,
+    emitter::Emitter,
+    error_codes::ErrorCode,
+    interpreter::Interpreter,
+    ir,
+    lints::{Lint, LintLevels, Linter},
+    lower::Lowering,
+    modules::ModuleLoader,
+    optimizer::Optimizer,
+    qualifier::Qualifier,
+    resolver::{Resolutions, Resolver},
+    transpiler::{self, Transpiler},
+    types::TypeInfo,
+    verifier::Verifier,
+    vm::Vm,
+};
 
-mod ast;
-mod lexer;
-mod parser;
+#[cfg(feature = "cranelift")]
+use olive::codegen;
 
 #[derive(clap::Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct OliveArgs {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArgs,
+
+    /// How to report errors and warnings.
+    #[arg(long, value_enum, global = true, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+
+    /// Do not report a lint.
+    #[arg(short = 'A', value_name = "LINT", global = true)]
+    allow: Vec<Lint>,
+
+    /// Report a lint as a warning, which is the default for every lint.
+    #[arg(short = 'W', value_name = "LINT", global = true)]
+    warn: Vec<Lint>,
+
+    /// Report a lint as an error.
+    #[arg(short = 'D', value_name = "LINT", global = true)]
+    deny: Vec<Lint>,
+
+    /// Explain an error or lint code, such as E0003 or W0001, with an example and its correction.
+    #[arg(long, value_name = "CODE", exclusive = true, value_parser = parse_error_code)]
+    explain: Option<ErrorCode>,
+}
+
+fn parse_error_code(code: &str) -> Result<ErrorCode, String> {
+    ErrorCode::parse(code).ok_or_else(|| format!("`{}` is not a known error code", code))
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Run a program. This is the default when only a FILE is given.
+    Run(RunArgs),
+    /// Compile a program to a native executable through C, using the system `cc`.
+    Build(BuildArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
     #[arg(value_name = "FILE", required = true)]
-    file: String,
+    file: Option<String>,
 
     #[arg(long)]
     dump_ast: bool,
+
+    /// Print the bytecode of the program with the source span of every instruction.
+    #[arg(long)]
+    dump_bytecode: bool,
+
+    /// Print the SSA intermediate representation of the program after checking that it is well formed.
+    #[arg(long)]
+    dump_ir: bool,
+
+    /// The optimization level: 0 runs the program as written, 1 folds constant
+    /// expressions and 2 also removes unreachable statements and unused variables.
+    #[arg(short = 'O', value_name = "LEVEL", default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=2))]
+    opt_level: u8,
+
+    /// Run the program on the bytecode VM instead of the tree-walking interpreter.
+    #[arg(long)]
+    vm: bool,
+
+    /// Compile the program to machine code with Cranelift and run it in-process.
+    #[cfg(feature = "cranelift")]
+    #[arg(long, conflicts_with = "vm")]
+    jit: bool,
 }
 
+#[derive(clap::Args, Debug)]
+struct BuildArgs {
+    #[arg(value_name = "FILE")]
+    file: String,
+
+    /// Where to write the executable. Defaults to the name of FILE without its extension.
+    #[arg(short, long, value_name = "OUT")]
+    output: Option<String>,
+
+    /// The optimization level, as for `run`.
+    #[arg(short = 'O', value_name = "LEVEL", default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=2))]
+    opt_level: u8,
+
+    /// Also write the generated C source next to the executable, as `OUT.c`.
+    /// An existing file is not replaced.
+    #[arg(long)]
+    emit_c: bool,
+
+    /// Generate machine code with Cranelift instead of going through C. The
+    /// object file is kept as `OUT.o`, which must not exist yet.
+    #[cfg(feature = "cranelift")]
+    #[arg(long, conflicts_with = "emit_c")]
+    object: bool,
+}
+
+/// The stack size of the thread that compiles and runs the program. The
+/// interpreter uses the stack of this thread for the calls of the program, up
+/// to `value::MAX_CALL_DEPTH` of them.
+const STACK_SIZE: usize = 512 * 1024 * 1024;
+
 fn main() {
-    // let cmd = Command::new("glacier").arg(Arg::new("file").required(true));
-    // args.get_matches()
-    //     .get_one::<String>("file")
-    //     .expect("Expected a filename!"),
+    let thread = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(olive)
+        .expect("Failed to start the compiler thread.");
+    if let Err(panic) = thread.join() {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+fn olive() {
     let args = OliveArgs::parse();
+    if let Some(code) = args.explain {
+        code.explain();
+        return;
+    }
+    let error_format = args.error_format;
+    let lints = LintLevels::new(&args.allow, &args.warn, &args.deny);
+    let compiler = match args {
+        OliveArgs {
+            command: Some(Command::Build(args)),
+            ..
+        } => build(args, &lints),
+        OliveArgs {
+            command: Some(Command::Run(args)),
+            ..
+        } => run(args, &lints, error_format),
+        OliveArgs { run: args, .. } => run(args, &lints, error_format),
+    };
+
+    compiler.print_error(error_format);
+    if compiler.reporter.borrow().has_error() {
+        std::process::exit(1);
+    }
+}
+
+/// Parses the program and the modules it imports, resolves, lints and type checks it, stopping at the first stage that reports an error.
+fn check(compiler: &mut Compiler, lints: &LintLevels) -> Option<(Ast, Resolutions, TypeInfo)> {
+    let mut ast = ModuleLoader::new(compiler).load();
+    compiler.dump_ast(&ast);
+    if compiler.reporter.borrow().has_error() {
+        return None;
+    }
+    let resolutions = Resolver::new(compiler).resolve(&ast);
+    if compiler.reporter.borrow().has_error() {
+        return None;
+    }
+    Linter::new(compiler, &resolutions, lints).run(&ast);
+    Qualifier::new(&resolutions).qualify(&mut ast);
+    let types = TypeChecker::new(compiler, &resolutions).check(&ast);
+    if compiler.reporter.borrow().has_error() {
+        return None;
+    }
+    Some((ast, resolutions, types))
+}
+
+/// Lowers the program to SSA form and verifies it, reporting any invalid IR.
+fn lower(
+    compiler: &Compiler,
+    resolutions: &Resolutions,
+    types: &TypeInfo,
+    ast: &Ast,
+) -> Option<ir::Module> {
+    let module = Lowering::new(resolutions, types).lower(ast);
+    let errors = Verifier::new(&module).verify();
+    if errors.is_empty() {
+        return Some(module);
+    }
+    for error in errors {
+        compiler.reporter.borrow_mut().add(error);
+    }
+    None
+}
+
+fn run(args: RunArgs, lints: &LintLevels, error_format: ErrorFormat) -> Compiler {
+    let file = args.file.unwrap();
+    let mut compiler = Compiler::new(&file, args.dump_ast, args.dump_bytecode, args.dump_ir);
+    let Some((mut ast, resolutions, types)) = check(&mut compiler, lints) else {
+        return compiler;
+    };
+    Optimizer::new(&compiler, &resolutions, args.opt_level).optimize(&mut ast);
+    if args.dump_ir {
+        let Some(module) = lower(&compiler, &resolutions, &types, &ast) else {
+            return compiler;
+        };
+        compiler.dump_ir(&module);
+    }
+    // Warnings are shown before the output of the program, not after it.
+    compiler.flush_errors(error_format);
+
+    #[cfg(feature = "cranelift")]
+    if args.jit {
+        if let Some(module) = lower(&compiler, &resolutions, &types, &ast) {
+            codegen::run_jit(&compiler, &module);
+        }
+        return compiler;
+    }
+    if args.vm || args.dump_bytecode {
+        let program = Emitter::new(&resolutions, &types).emit(&ast);
+        compiler.dump_bytecode(&program);
+        if args.vm {
+            Vm::new(&compiler, &program).run();
+            return compiler;
+        }
+    }
+    Interpreter::new(&compiler, &resolutions, &types).run(&ast);
+    compiler
+}
+
+fn build(args: BuildArgs, lints: &LintLevels) -> Compiler {
+    let mut compiler = Compiler::new(&args.file, false, false, false);
+    let Some((mut ast, resolutions, types)) = check(&mut compiler, lints) else {
+        return compiler;
+    };
+    Optimizer::new(&compiler, &resolutions, args.opt_level).optimize(&mut ast);
+    let output = match args.output {
+        Some(output) => PathBuf::from(output),
+        None => PathBuf::from(Path::new(&args.file).file_stem().unwrap_or_default()),
+    };
 
-    let compiler = Compiler::new(&args.file, args.dump_ast);
+    #[cfg(feature = "cranelift")]
+    if args.object {
+        let Some(object) = lower(&compiler, &resolutions, &types, &ast)
+            .and_then(|module| codegen::emit_object(&compiler, &module))
+        else {
+            return compiler;
+        };
+        if let Err(diagnostic) = codegen::build_executable(&object, &output) {
+            compiler.reporter.borrow_mut().add(diagnostic);
+        }
+        return compiler;
+    }
 
-    let tokens = Lexer::new(&compiler).identify_tokens();
-    // // println!("{:#?}", tokens);
-    let mut parser = parser::Parser::new(&compiler, tokens);
-    let ast = parser.parse();
-    compiler.dump_ast(ast);
-    compiler.print_error();
+    let Some(source) = Transpiler::new(&compiler, &resolutions, &types).transpile(&ast) else {
+        return compiler;
+    };
+    if let Err(diagnostic) = transpiler::build_executable(&source, &output, args.emit_c) {
+        compiler.reporter.borrow_mut().add(diagnostic);
+    }
+    compiler
 }
@@ -0,0 +1,163 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{
+    ast::{Ast, Item},
+    compiler::Compiler,
+    diag,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    lexer::Lexer,
+    parser::Parser,
+    utils::{FileId, Span},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    Active,
+    Done,
+}
+
+/// Loads the main file and every file it imports, directly or not, and merges
+/// their items into one program. Every file is lexed and parsed once, however
+/// many times it is imported. The resolver keeps the items of every file in
+/// their own namespace.
+pub struct ModuleLoader<'a> {
+    compiler: &'a mut Compiler,
+    /// The parsed items of every file, indexed by its file id.
+    items: Vec<Vec<Item>>,
+    /// The files every file imports, with the span of the import.
+    imports: Vec<Vec<(FileId, Span)>>,
+    /// The file id of every loaded file, keyed by its canonical path.
+    loaded: HashMap<PathBuf, FileId>,
+}
+
+impl<'a> ModuleLoader<'a> {
+    pub fn new(compiler: &'a mut Compiler) -> Self {
+        Self {
+            compiler,
+            items: Vec::new(),
+            imports: Vec::new(),
+            loaded: HashMap::new(),
+        }
+    }
+
+    pub fn load(mut self) -> Ast {
+        if let Ok(path) = self.compiler.get_module_filepath(0).canonicalize() {
+            self.loaded.insert(path, 0);
+        }
+        loop {
+            let tokens = Lexer::new(self.compiler).identify_tokens();
+            let ast = Parser::new(self.compiler, tokens).parse();
+            self.add_file(ast);
+            if !self.compiler.next_file() {
+                break;
+            }
+        }
+
+        // Imported files come before the files importing them, so the items
+        // of a module are declared before anything that depends on them.
+        let mut visits = vec![Visit::New; self.items.len()];
+        let mut order = Vec::new();
+        self.visit(0, &mut visits, &mut Vec::new(), &mut order);
+
+        let mut ast = Ast::new();
+        for file_id in order {
+            ast.items.append(&mut self.items[file_id]);
+        }
+        ast
+    }
+
+    /// Records the items of the current file and registers the files it imports.
+    fn add_file(&mut self, mut ast: Ast) {
+        let file_id = self.compiler.get_curr_file_id();
+        let dir = self
+            .compiler
+            .get_module_filepath(file_id)
+            .parent()
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_default();
+
+        let mut imports = Vec::new();
+        for item in &mut ast.items {
+            let Item::Import(import) = item else {
+                continue;
+            };
+            if import.path.as_os_str().is_empty() {
+                continue;
+            }
+            let path = dir.join(&import.path);
+            let Ok(canonical) = path.canonicalize() else {
+                self.compiler.reporter.borrow_mut().add(
+                    diag!(
+                        format!("Cannot find the module `{}`.", import.path.display()),
+                        format!("There is no file at `{}`.", path.display()),
+                        import.span
+                    )
+                    .with_code(ErrorCode::ModuleNotFound),
+                );
+                continue;
+            };
+            let imported = match self.loaded.get(&canonical) {
+                Some(imported) => *imported,
+                None => {
+                    let imported = self.compiler.add_module(path);
+                    self.loaded.insert(canonical, imported);
+                    imported
+                }
+            };
+            import.module = Some(imported);
+            imports.push((imported, import.span));
+        }
+
+        self.items.push(ast.items);
+        self.imports.push(imports);
+    }
+
+    /// Orders the files reachable from `file_id` so that every file comes
+    /// after the files it imports, reporting the imports that form a cycle.
+    fn visit(
+        &self,
+        file_id: FileId,
+        visits: &mut [Visit],
+        stack: &mut Vec<FileId>,
+        order: &mut Vec<FileId>,
+    ) {
+        visits[file_id] = Visit::Active;
+        stack.push(file_id);
+        for &(imported, span) in &self.imports[file_id] {
+            match visits[imported] {
+                Visit::New => self.visit(imported, visits, stack, order),
+                Visit::Active => self.report_cycle(stack, imported, span),
+                Visit::Done => {}
+            }
+        }
+        stack.pop();
+        visits[file_id] = Visit::Done;
+        order.push(file_id);
+    }
+
+    fn report_cycle(&self, stack: &[FileId], imported: FileId, span: Span) {
+        let start = stack.iter().position(|&id| id == imported).unwrap();
+        let cycle = stack[start..]
+            .iter()
+            .chain(std::iter::once(&imported))
+            .map(|&id| {
+                format!(
+                    "`{}`",
+                    self.compiler.get_module_filename(id).to_string_lossy()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        self.compiler.reporter.borrow_mut().add(
+            diag!(
+                "Modules cannot import each other in a cycle.",
+                "This import closes the cycle.",
+                span
+            )
+            .with_code(ErrorCode::ImportCycle)
+            .with_note(format!("The imports go around {}.", cycle)),
+        );
+    }
+}
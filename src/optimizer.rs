@@ -0,0 +1,502 @@
+use std::collections::HashSet;
+
+use crate::{
+    ast::{ArmBody, Ast, BinOp, Block, Expr, Function, Item, Statement, UnaryOp},
+    compiler::Compiler,
+    diag,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    resolver::Resolutions,
+    utils::{Span, Token, TokenType as Ty},
+    value::{self, Value},
+};
+
+/// Simplifies a type checked program before it runs. The level is set with `-O`:
+///
+/// - `0` leaves the program as written.
+/// - `1` folds operators whose operands are constants into a single literal.
+/// - `2` also removes statements that can never run and local variables that are never read.
+pub struct Optimizer<'a> {
+    compiler: &'a Compiler,
+    resolutions: &'a Resolutions,
+    level: u8,
+}
+
+impl<'a> Optimizer<'a> {
+    pub fn new(compiler: &'a Compiler, resolutions: &'a Resolutions, level: u8) -> Self {
+        Self {
+            compiler,
+            resolutions,
+            level,
+        }
+    }
+
+    pub fn optimize(&self, ast: &mut Ast) {
+        if self.level == 0 {
+            return;
+        }
+        for item in &mut ast.items {
+            match item {
+                Item::Func(function) => self.function(function),
+                Item::Class(class) => {
+                    for method in &mut class.methods {
+                        self.function(method);
+                    }
+                }
+                Item::Const(constant) => self.fold(&mut constant.expr),
+                _ => {}
+            }
+        }
+    }
+
+    fn function(&self, function: &mut Function) {
+        self.fold_block(&mut function.body);
+        if self.level >= 2 {
+            remove_unreachable(&mut function.body);
+            self.remove_unused_locals(&mut function.body);
+        }
+    }
+
+    fn fold_block(&self, block: &mut Block) {
+        for stmt in &mut block.0 {
+            self.fold_stmt(stmt);
+        }
+    }
+
+    fn fold_stmt(&self, stmt: &mut Statement) {
+        match stmt {
+            Statement::VarDecl { expr, .. }
+            | Statement::Return { expr, .. }
+            | Statement::Expression(expr) => self.fold(expr),
+            Statement::Const(constant) => self.fold(&mut constant.expr),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.fold(condition);
+                self.fold_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.fold_block(else_branch);
+                }
+            }
+            Statement::While { condition, body } => {
+                self.fold(condition);
+                self.fold_block(body);
+            }
+            Statement::For { range, body, .. } => {
+                self.fold(range);
+                self.fold_block(body);
+            }
+            Statement::Break(_) | Statement::Continue(_) | Statement::Unknown => {}
+        }
+    }
+
+    /// Replaces the constant parts of `expr` with literals. Operations that
+    /// would fail at runtime, such as dividing an integer by zero, are kept so
+    /// that they still report their error when they run.
+    fn fold(&self, expr: &mut Expr) {
+        let span = expr.span();
+        match expr {
+            Expr::Binary { lhs, op, rhs } => {
+                self.fold(lhs);
+                self.fold(rhs);
+                let folded = match (op, constant(lhs), constant(rhs)) {
+                    // `&&` and `||` only evaluate their right operand when needed.
+                    (BinOp::And, Some(Value::Bool(false)), _) => Some(Value::Bool(false)),
+                    (BinOp::Or, Some(Value::Bool(true)), _) => Some(Value::Bool(true)),
+                    (BinOp::And | BinOp::Or, Some(Value::Bool(_)), _) => {
+                        *expr = std::mem::replace(rhs.as_mut(), Expr::None);
+                        return;
+                    }
+                    (BinOp::And | BinOp::Or, ..) => None,
+                    (BinOp::Divide, _, Some(Value::Int(0))) => {
+                        self.warn_division_by_zero(span);
+                        None
+                    }
+                    (op, Some(lhs), Some(rhs)) => value::binary_op(op, lhs, rhs, span).ok(),
+                    _ => None,
+                };
+                self.replace(expr, folded, span);
+            }
+            Expr::Unary { op, rhs } => {
+                self.fold(rhs);
+                let folded = match (op, constant(rhs)) {
+                    (UnaryOp::Negative, Some(Value::Int(int))) => {
+                        Some(Value::Int(int.wrapping_neg()))
+                    }
+                    (UnaryOp::Negative, Some(Value::Float(float))) => Some(Value::Float(-float)),
+                    (UnaryOp::Negate, Some(Value::Bool(bool))) => Some(Value::Bool(!bool)),
+                    _ => None,
+                };
+                self.replace(expr, folded, span);
+            }
+            Expr::Grouping(inner) => {
+                self.fold(inner);
+                if let Expr::Literal(_) = inner.as_ref() {
+                    *expr = std::mem::replace(inner.as_mut(), Expr::None);
+                }
+            }
+            Expr::Call { callee, args } => {
+                self.fold(callee);
+                for arg in args {
+                    self.fold(arg);
+                }
+            }
+            Expr::Range { start, end, .. } => {
+                self.fold(start);
+                self.fold(end);
+            }
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    self.fold(element);
+                }
+            }
+            Expr::Index { object, index } => {
+                self.fold(object);
+                self.fold(index);
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.fold(scrutinee);
+                for arm in arms {
+                    match &mut arm.body {
+                        ArmBody::Expr(expr) => self.fold(expr),
+                        ArmBody::Block(block) => self.fold_block(block),
+                    }
+                }
+            }
+            Expr::StructLiteral { fields, .. } => {
+                for field in fields {
+                    self.fold(&mut field.value);
+                }
+            }
+            Expr::Field { object, .. } => self.fold(object),
+            Expr::Assign { target, value, .. } => {
+                self.fold(target);
+                self.fold(value);
+            }
+            Expr::Literal(_) | Expr::Var(_) | Expr::Path(_) | Expr::None | Expr::Unknown => {}
+        }
+    }
+
+    fn replace(&self, expr: &mut Expr, folded: Option<Value>, span: Span) {
+        if let Some(literal) = folded.and_then(|value| value.to_literal(span)) {
+            *expr = Expr::Literal(literal);
+        }
+    }
+
+    fn warn_division_by_zero(&self, span: Span) {
+        self.compiler.reporter.borrow_mut().add(
+            diag!(
+                DiagnosticKind::Warning,
+                "This expression always divides by zero.",
+                Some(String::from(
+                    "The divisor is zero, so evaluating this stops the program with an error."
+                )),
+                span
+            )
+            .with_code(ErrorCode::DivisionByZero),
+        );
+    }
+
+    /// Removes the declarations of local variables that are never read, along
+    /// with the statements that only assign to them. Initializers and assigned
+    /// values that may have side effects are kept as expression statements.
+    /// Removing one variable can leave another unread, so this repeats until
+    /// nothing changes.
+    fn remove_unused_locals(&self, body: &mut Block) {
+        loop {
+            let mut read = HashSet::new();
+            self.reads_in_block(body, true, &mut read);
+            if !self.remove_unread(body, &read) {
+                break;
+            }
+        }
+    }
+
+    /// Collects the declarations of the variables read in `block`. With
+    /// `removable`, a variable that is only assigned to, by a statement such as
+    /// `x = 1;` or `x += 1;`, is not read, since those statements are removed
+    /// along with the variable. A division such as `x /= y;` reads `x`, since
+    /// it is kept in case it fails.
+    fn reads_in_block(&self, block: &Block, removable: bool, read: &mut HashSet<Span>) {
+        for stmt in &block.0 {
+            match stmt {
+                Statement::VarDecl { expr, .. } | Statement::Return { expr, .. } => {
+                    self.reads(expr, read)
+                }
+                Statement::Const(constant) => self.reads(&constant.expr, read),
+                Statement::Expression(expr) => match expr {
+                    Expr::Assign { target, op, value }
+                        if removable
+                            && matches!(target.as_ref(), Expr::Var(_))
+                            && removable_assignment(op.as_ref(), value) =>
+                    {
+                        self.reads(value, read)
+                    }
+                    expr => self.reads(expr, read),
+                },
+                Statement::If {
+                    condition,
+                    then_branch,
+                    else_branch,
+                } => {
+                    self.reads(condition, read);
+                    self.reads_in_block(then_branch, removable, read);
+                    if let Some(else_branch) = else_branch {
+                        self.reads_in_block(else_branch, removable, read);
+                    }
+                }
+                Statement::While { condition, body } => {
+                    self.reads(condition, read);
+                    self.reads_in_block(body, removable, read);
+                }
+                Statement::For { range, body, .. } => {
+                    self.reads(range, read);
+                    self.reads_in_block(body, removable, read);
+                }
+                Statement::Break(_) | Statement::Continue(_) | Statement::Unknown => {}
+            }
+        }
+    }
+
+    fn reads(&self, expr: &Expr, read: &mut HashSet<Span>) {
+        match expr {
+            Expr::Var(name) => {
+                if let Some(symbol) = self.resolutions.get(name) {
+                    read.insert(symbol.span);
+                }
+            }
+            Expr::Binary { lhs, rhs, .. } => {
+                self.reads(lhs, read);
+                self.reads(rhs, read);
+            }
+            Expr::Unary { rhs: expr, .. }
+            | Expr::Grouping(expr)
+            | Expr::Field { object: expr, .. } => self.reads(expr, read),
+            Expr::Call { callee, args } => {
+                self.reads(callee, read);
+                for arg in args {
+                    self.reads(arg, read);
+                }
+            }
+            Expr::Range { start, end, .. } => {
+                self.reads(start, read);
+                self.reads(end, read);
+            }
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    self.reads(element, read);
+                }
+            }
+            Expr::Index { object, index } => {
+                self.reads(object, read);
+                self.reads(index, read);
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.reads(scrutinee, read);
+                for arm in arms {
+                    match &arm.body {
+                        ArmBody::Expr(expr) => self.reads(expr, read),
+                        ArmBody::Block(block) => self.reads_in_block(block, false, read),
+                    }
+                }
+            }
+            Expr::StructLiteral { fields, .. } => {
+                for field in fields {
+                    self.reads(&field.value, read);
+                }
+            }
+            Expr::Assign { target, value, .. } => {
+                self.reads(target, read);
+                self.reads(value, read);
+            }
+            Expr::Literal(_) | Expr::Path(_) | Expr::None | Expr::Unknown => {}
+        }
+    }
+
+    /// Removes the variables declared in `block` that are not in `read`, and
+    /// returns true if anything was removed.
+    fn remove_unread(&self, block: &mut Block, read: &HashSet<Span>) -> bool {
+        let mut changed = false;
+        let statements = std::mem::take(&mut block.0);
+        for mut stmt in statements {
+            let unread = |name: &Token| !read.contains(&name.span);
+            let kept = match stmt {
+                Statement::VarDecl { ref name, expr, .. } if unread(name) => side_effects(expr),
+                Statement::Const(constant) if unread(&constant.name) => side_effects(constant.expr),
+                Statement::Expression(Expr::Assign { target, op, value })
+                    if self.assigns_unread(&target, read)
+                        && removable_assignment(op.as_ref(), &value) =>
+                {
+                    side_effects(*value)
+                }
+                _ => {
+                    changed |= self.remove_unread_in(&mut stmt, read);
+                    block.0.push(stmt);
+                    continue;
+                }
+            };
+            changed = true;
+            block.0.extend(kept);
+        }
+        changed
+    }
+
+    fn remove_unread_in(&self, stmt: &mut Statement, read: &HashSet<Span>) -> bool {
+        match stmt {
+            Statement::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let mut changed = self.remove_unread(then_branch, read);
+                if let Some(else_branch) = else_branch {
+                    changed |= self.remove_unread(else_branch, read);
+                }
+                changed
+            }
+            Statement::While { body, .. } | Statement::For { body, .. } => {
+                self.remove_unread(body, read)
+            }
+            _ => false,
+        }
+    }
+
+    fn assigns_unread(&self, target: &Expr, read: &HashSet<Span>) -> bool {
+        match target {
+            Expr::Var(name) => self
+                .resolutions
+                .get(name)
+                .is_some_and(|symbol| !read.contains(&symbol.span)),
+            _ => false,
+        }
+    }
+}
+
+/// Returns the statement that keeps the side effects of evaluating `expr`, if it may have any.
+fn side_effects(expr: Expr) -> Option<Statement> {
+    match is_pure(&expr) {
+        true => None,
+        false => Some(Statement::Expression(expr)),
+    }
+}
+
+/// Returns true if evaluating `expr` can neither fail nor have an effect.
+fn is_pure(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(_) | Expr::Var(_) | Expr::Path(_) | Expr::None => true,
+        Expr::Grouping(expr) | Expr::Unary { rhs: expr, .. } => is_pure(expr),
+        Expr::Binary {
+            lhs,
+            op: BinOp::Divide,
+            rhs,
+        } => is_pure(lhs) && nonzero_divisor(rhs),
+        Expr::Binary { lhs, rhs, .. } => is_pure(lhs) && is_pure(rhs),
+        Expr::Array { elements, .. } => elements.iter().all(is_pure),
+        Expr::StructLiteral { fields, .. } => fields.iter().all(|field| is_pure(&field.value)),
+        // Calls can have effects, indexing can be out of bounds, a field of a
+        // class can be read before the object exists, and an assignment writes.
+        _ => false,
+    }
+}
+
+/// Returns true if an assignment with the compound operator `op`, if any, and
+/// the value `value` cannot fail, so that it can be removed with its target.
+fn removable_assignment(op: Option<&BinOp>, value: &Expr) -> bool {
+    op != Some(&BinOp::Divide) || nonzero_divisor(value)
+}
+
+/// Returns true if dividing by `expr` cannot fail: only a divisor known not to
+/// be the integer zero.
+fn nonzero_divisor(expr: &Expr) -> bool {
+    matches!(expr, Expr::Literal(literal) if match &literal.ty {
+        Ty::Integer(int) => int.parse() != Ok(0),
+        Ty::Float(_) => true,
+        _ => false,
+    })
+}
+
+/// Returns the value of `expr` if it is a literal.
+fn constant(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Literal(literal) => Some(Value::from_literal(literal)),
+        _ => None,
+    }
+}
+
+/// Removes the statements that follow one that never completes, such as
+/// `return`, and the branches and loops whose condition is a constant that
+/// never lets them run.
+fn remove_unreachable(block: &mut Block) {
+    let mut i = 0;
+    while i < block.0.len() {
+        let remove = match &mut block.0[i] {
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                remove_unreachable(then_branch);
+                if let Some(else_branch) = else_branch {
+                    remove_unreachable(else_branch);
+                }
+                match constant(condition) {
+                    Some(Value::Bool(true)) => {
+                        *else_branch = None;
+                        false
+                    }
+                    Some(Value::Bool(false)) => else_branch.is_none(),
+                    _ => false,
+                }
+            }
+            Statement::While { condition, body } => {
+                remove_unreachable(body);
+                constant(condition) == Some(Value::Bool(false))
+            }
+            Statement::For { body, .. } => {
+                remove_unreachable(body);
+                false
+            }
+            _ => false,
+        };
+        if remove {
+            block.0.remove(i);
+            continue;
+        }
+        if diverges(&block.0[i]) {
+            block.0.truncate(i + 1);
+            break;
+        }
+        i += 1;
+    }
+}
+
+/// Returns true if the statement never lets execution continue to the next one.
+pub fn diverges(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Return { .. } | Statement::Break(_) | Statement::Continue(_) => true,
+        Statement::If {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => block_diverges(then_branch) && block_diverges(else_branch),
+        // The type checker only lets exhaustive `match`es through, so one of the arms always runs.
+        Statement::Expression(Expr::Match { arms, .. }) => {
+            !arms.is_empty()
+                && arms.iter().all(|arm| match &arm.body {
+                    ArmBody::Block(block) => block_diverges(block),
+                    ArmBody::Expr(_) => false,
+                })
+        }
+        _ => false,
+    }
+}
+
+fn block_diverges(block: &Block) -> bool {
+    block.0.iter().any(diverges)
+}
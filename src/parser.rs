@@ -1,7 +1,13 @@
-use crate::ast::{BinOp, Block, Expr, Function, Item, Parameter, Statement, UnaryOp};
+use std::path::PathBuf;
+
+use crate::ast::{
+    ArmBody, Attribute, BinOp, Block, Class, Constant, Enum, Expr, Field, FieldInit, Function,
+    Import, Item, MatchArm, Parameter, Pattern, Statement, Struct, TypeExpr, UnaryOp, Variant,
+};
 use crate::compiler::Compiler;
 use crate::diag;
 use crate::diagnostic::{Diagnostic, DiagnosticKind};
+use crate::error_codes::ErrorCode;
 use crate::utils::Span;
 use crate::{
     ast::Ast,
@@ -13,6 +19,9 @@ pub struct Parser<'a> {
     // source: &'a str,
     compiler: &'a Compiler,
     tokens: Vec<Token>,
+    /// Cleared while parsing the header of an `if`, `while` or `for`, where a `{`
+    /// after a name opens the body rather than a struct literal.
+    allow_struct_literal: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -22,6 +31,7 @@ impl<'a> Parser<'a> {
             // source: compiler.source,
             compiler,
             tokens,
+            allow_struct_literal: true,
         }
     }
 
@@ -35,47 +45,296 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_item(&mut self) -> Item {
-        match self.advance_ty() {
-            Ty::KFunction => Item::Func(self.parse_function()),
+        let public = self.is_curr_token(Ty::KPub);
+        if public {
+            self.advance();
+        }
+        let mut item = match self.advance_ty() {
+            Ty::KFunction => Item::Func(self.parse_function(false)),
+            Ty::KConstant => Item::Const(self.parse_const()),
+            Ty::KStruct => Item::Struct(self.parse_struct()),
+            Ty::KClass => Item::Class(self.parse_class()),
+            Ty::KEnum => Item::Enum(self.parse_enum()),
+            Ty::KImport => Item::Import(self.parse_import()),
+            Ty::KUse => Item::Import(self.parse_use()),
+            Ty::Hash => Item::Attribute(self.parse_attribute()),
             _ => {
-                self.error_on_prev_span("Unexpected token found.");
+                self.error_on_prev_span(ErrorCode::UnexpectedToken, "Unexpected token found.");
                 self.sync(false);
                 Item::Unknown
             }
+        };
+        if public {
+            match &mut item {
+                Item::Func(function) => function.public = true,
+                Item::Const(constant) => constant.public = true,
+                Item::Struct(decl) => decl.public = true,
+                Item::Class(class) => class.public = true,
+                Item::Enum(decl) => decl.public = true,
+                Item::Import(import) => self.error_with_diag(
+                    diag!("Imports cannot be `pub`.", "Remove the `pub`.", import.span)
+                        .with_code(ErrorCode::UnexpectedToken),
+                ),
+                Item::Attribute(attribute) => self.error_with_diag(
+                    diag!(
+                        "Attributes cannot be `pub`.",
+                        "Remove the `pub`.",
+                        attribute.name.span
+                    )
+                    .with_code(ErrorCode::UnexpectedToken),
+                ),
+                Item::Unknown => {}
+            }
         }
+
+        item
     }
 
-    fn parse_function(&mut self) -> Function {
+    fn parse_function(&mut self, is_method: bool) -> Function {
         let name = self.must_consume_ident();
+        let mut self_param: Option<Token> = None;
         let mut params: Vec<Parameter> = Vec::new();
-        let mut return_ty: Option<Token> = None;
-        let mut body: Block = Block::new();
+        let mut return_ty: Option<TypeExpr> = None;
 
         self.consume(Ty::LParen);
-        while !self.is_curr_token(Ty::RParen) {
+        let is_receiver = self.current().as_ident() == "self"
+            && self.peek(1).is_some_and(|next| *next != Ty::Colon);
+        if is_receiver {
+            let receiver = self.advance().clone();
+            if !is_method {
+                self.error_with_diag(
+                    diag!(
+                        "`self` parameter is only allowed in methods.",
+                        "Move this function into a class or remove `self`.",
+                        receiver.span
+                    )
+                    .with_code(ErrorCode::MisplacedSelf),
+                );
+            }
+            self_param = Some(receiver);
+            if self.is_curr_token(Ty::Comma) {
+                self.advance();
+            }
+        } else if is_method {
+            self.error_with_diag(
+                diag!(
+                    format!(
+                        "Method `{}` must take `self` as its first parameter.",
+                        name.ty
+                    ),
+                    "Add `self` to the parameter list.",
+                    name.span
+                )
+                .with_code(ErrorCode::MisplacedSelf),
+            );
+        }
+        if !self.is_curr_token(Ty::RParen) {
             params = self.parse_params();
         }
         self.consume(Ty::RParen);
 
         if self.is_curr_token(Ty::RightArrow) {
             self.consume(Ty::RightArrow);
-            return_ty = self.consume_ident();
+            return_ty = Some(self.parse_type());
         }
 
-        self.consume(Ty::LCurly);
-        while !self.is_curr_token(Ty::RCurly) {
-            body.push_stmt(self.parse_stmt());
-        }
-        self.consume(Ty::RCurly);
+        let body = self.parse_block();
 
         Function {
             name,
+            public: false,
+            self_param,
             params,
             body,
             return_ty,
         }
     }
 
+    /// Parses `import "path";`. A path without an extension names a `.glacier` file.
+    fn parse_import(&mut self) -> Import {
+        let span = self.current_span();
+        let mut path = match self.current_ty() {
+            Ty::String(path) => PathBuf::from(path),
+            _ => {
+                self.error_with_diag(
+                    diag!(
+                        "Expected the path of a file to import.",
+                        "Write the path as a string, such as \"math.glacier\".",
+                        span
+                    )
+                    .with_code(ErrorCode::UnexpectedToken),
+                );
+                self.sync(true);
+                return Import {
+                    path: PathBuf::new(),
+                    use_path: None,
+                    span,
+                    module: None,
+                };
+            }
+        };
+        self.advance();
+        if path.extension().is_none() {
+            path.set_extension("glacier");
+        }
+        self.terminate();
+
+        Import {
+            path,
+            use_path: None,
+            span,
+            module: None,
+        }
+    }
+
+    /// Parses `use foo::bar;`, which imports the file `foo/bar.glacier`.
+    fn parse_use(&mut self) -> Import {
+        let first = self.must_consume_ident();
+        let mut span = first.span;
+        let mut path = PathBuf::from(first.as_ident());
+        let mut use_path = first.as_ident().to_string();
+        while self.is_curr_token(Ty::DoubleColon) {
+            self.advance();
+            let segment = self.must_consume_ident();
+            span = span.to(segment.span);
+            path.push(segment.as_ident());
+            use_path.push_str("::");
+            use_path.push_str(segment.as_ident());
+        }
+        path.set_extension("glacier");
+        self.terminate();
+
+        Import {
+            path,
+            use_path: Some(use_path),
+            span,
+            module: None,
+        }
+    }
+
+    /// Parses `#allow(unused_variable, ...)`, after the `#`. Attributes apply to the whole file.
+    fn parse_attribute(&mut self) -> Attribute {
+        let name = self.must_consume_ident();
+        if name.as_ident() != "allow" && !name.is_eof() {
+            self.error_with_diag(
+                diag!(
+                    format!("Unknown attribute `{}`.", name.ty),
+                    "Only `#allow(...)` is supported.",
+                    name.span
+                )
+                .with_code(ErrorCode::UnexpectedToken),
+            );
+        }
+        let mut args = Vec::new();
+        self.consume(Ty::LParen);
+        while !self.is_curr_token(Ty::RParen) && !self.is_at_end() {
+            args.push(self.must_consume_ident());
+            if !self.is_curr_token(Ty::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        self.consume(Ty::RParen);
+
+        Attribute { name, args }
+    }
+
+    fn parse_struct(&mut self) -> Struct {
+        let name = self.must_consume_ident();
+        let mut fields: Vec<Field> = Vec::new();
+
+        self.consume(Ty::LCurly);
+        while !self.is_curr_token(Ty::RCurly) && !self.is_at_end() {
+            let name = self.must_consume_ident();
+            self.consume(Ty::Colon);
+            let ty = self.parse_type();
+            fields.push(Field { name, ty });
+
+            if !self.is_curr_token(Ty::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        self.consume(Ty::RCurly);
+
+        Struct {
+            name,
+            public: false,
+            fields,
+        }
+    }
+
+    fn parse_class(&mut self) -> Class {
+        let name = self.must_consume_ident();
+        let mut fields: Vec<Field> = Vec::new();
+        let mut methods: Vec<Function> = Vec::new();
+
+        self.consume(Ty::LCurly);
+        while !self.is_curr_token(Ty::RCurly) && !self.is_at_end() {
+            if self.is_curr_token(Ty::KFunction) {
+                self.advance();
+                methods.push(self.parse_function(true));
+            } else if self.is_curr_token_ident() {
+                let name = self.must_consume_ident();
+                self.consume(Ty::Colon);
+                let ty = self.parse_type();
+                fields.push(Field { name, ty });
+
+                if self.is_curr_token(Ty::Comma) {
+                    self.advance();
+                } else if !self.is_curr_token(Ty::RCurly) && !self.is_curr_token(Ty::KFunction) {
+                    self.consume(Ty::Comma);
+                }
+            } else {
+                self.error(ErrorCode::UnexpectedToken, "Expected a field or a method.");
+                self.advance();
+            }
+        }
+        self.consume(Ty::RCurly);
+
+        Class {
+            name,
+            public: false,
+            fields,
+            methods,
+        }
+    }
+
+    fn parse_enum(&mut self) -> Enum {
+        let name = self.must_consume_ident();
+        let mut variants: Vec<Variant> = Vec::new();
+
+        self.consume(Ty::LCurly);
+        while !self.is_curr_token(Ty::RCurly) && !self.is_at_end() {
+            let name = self.must_consume_ident();
+            let mut fields: Vec<TypeExpr> = Vec::new();
+            if self.is_curr_token(Ty::LParen) {
+                self.advance();
+                while !self.is_curr_token(Ty::RParen) && !self.is_at_end() {
+                    fields.push(self.parse_type());
+                    if !self.is_curr_token(Ty::Comma) {
+                        break;
+                    }
+                    self.advance();
+                }
+                self.consume(Ty::RParen);
+            }
+            variants.push(Variant { name, fields });
+
+            if !self.is_curr_token(Ty::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        self.consume(Ty::RCurly);
+
+        Enum {
+            name,
+            public: false,
+            variants,
+        }
+    }
+
     fn parse_params(&mut self) -> Vec<Parameter> {
         let mut params: Vec<Parameter> = vec![self.parse_param()];
         while self.is_curr_token(Ty::Comma) {
@@ -89,57 +348,300 @@ impl<'a> Parser<'a> {
     fn parse_param(&mut self) -> Parameter {
         let name = self.must_consume_ident();
         self.consume(Ty::Colon);
-        let ty = self.must_consume_ident();
+        let ty = self.parse_type();
         Parameter { name, ty }
     }
 
+    fn parse_type(&mut self) -> TypeExpr {
+        if self.is_curr_token(Ty::LBoxed) {
+            let open = self.advance().span;
+            let element = Box::new(self.parse_type());
+            let close = self.current_span();
+            self.consume(Ty::RBoxed);
+            TypeExpr::Array {
+                element,
+                span: open.to(close),
+            }
+        } else {
+            TypeExpr::Named(self.parse_qualified_name())
+        }
+    }
+
+    fn parse_block(&mut self) -> Block {
+        let mut block = Block::new();
+
+        self.consume(Ty::LCurly);
+        while !self.is_curr_token(Ty::RCurly) && !self.is_at_end() {
+            block.push_stmt(self.parse_stmt());
+        }
+        self.consume(Ty::RCurly);
+
+        block
+    }
+
     fn parse_stmt(&mut self) -> Statement {
         if self.current().is_eof() {
-            self.error("Expected a Statement or `}`. Found <EOF>.");
+            self.error(
+                ErrorCode::UnexpectedToken,
+                "Expected a Statement or `}`. Found <EOF>.",
+            );
             return Statement::Unknown;
         }
 
         match self.advance_ty() {
-            Ty::KVariable => {
-                let name = self.must_consume_ident();
-                let mut ty: Option<Token> = None;
-                if self.is_curr_token(Ty::Colon) {
-                    self.consume(Ty::Colon);
-                    ty = self.consume_ident();
-                }
-                let mut expr = Expr::None;
-                if self.is_curr_token(Ty::Eq) {
-                    self.advance();
-                    expr = self.parse_expr();
-                    if expr == Expr::None {
-                        self.error_with_diag(diag!(
-                            "Unexpected '=' without expression.",
-                            "Provide an expression.",
-                            self.previous_span()
-                        ));
-                    }
-                } else {
+            Ty::KVariable => self.parse_var_decl(),
+            Ty::KConstant => Statement::Const(self.parse_const()),
+            Ty::KReturn => {
+                let keyword = self.previous().clone();
+                let expr = self.parse_expr();
+                if expr != Expr::None {
                     self.terminate();
                 }
-                Statement::VarDecl { name, ty, expr }
+                Statement::Return { keyword, expr }
+            }
+            Ty::KIf => self.parse_if(),
+            Ty::KWhile => {
+                let condition = self.parse_header_expr();
+                let body = self.parse_block();
+                Statement::While { condition, body }
+            }
+            Ty::KFor => {
+                let var = self.must_consume_ident();
+                self.consume(Ty::KIn);
+                let range = self.parse_range();
+                let body = self.parse_block();
+                Statement::For { var, range, body }
+            }
+            Ty::KBreak => {
+                let keyword = self.previous().clone();
+                self.terminate();
+                Statement::Break(keyword)
+            }
+            Ty::KContinue => {
+                let keyword = self.previous().clone();
+                self.terminate();
+                Statement::Continue(keyword)
             }
-            Ty::KReturn => Statement::Return(self.parse_expr()),
             _ => {
                 self.current -= 1;
                 let expr = self.parse_expr();
-                self.sync(true);
                 if expr == Expr::Unknown {
-                    self.error_on_prev_span("Unexpected token found.");
+                    self.advance();
+                    self.sync(true);
                     Statement::Unknown
                 } else {
+                    // Like blocks, a `match` used as a statement needs no `;` after its `}`.
+                    let is_match = matches!(expr, Expr::Match { .. });
+                    if expr != Expr::None && (!is_match || self.is_curr_token(Ty::Semicolon)) {
+                        self.terminate();
+                    }
                     Statement::Expression(expr)
                 }
             }
         }
     }
 
+    fn parse_var_decl(&mut self) -> Statement {
+        let mutable = self.is_curr_token(Ty::KMutable);
+        if mutable {
+            self.advance();
+        }
+        let name = self.must_consume_ident();
+        let mut ty: Option<TypeExpr> = None;
+        if self.is_curr_token(Ty::Colon) {
+            self.consume(Ty::Colon);
+            ty = Some(self.parse_type());
+        }
+        let mut expr = Expr::None;
+        if self.is_curr_token(Ty::Eq) {
+            self.advance();
+            expr = self.parse_initializer();
+        } else {
+            self.terminate();
+        }
+
+        Statement::VarDecl {
+            name,
+            mutable,
+            ty,
+            expr,
+        }
+    }
+
+    fn parse_const(&mut self) -> Constant {
+        let name = self.must_consume_ident();
+        let mut ty: Option<TypeExpr> = None;
+        if self.is_curr_token(Ty::Colon) {
+            self.consume(Ty::Colon);
+            ty = Some(self.parse_type());
+        }
+        self.consume(Ty::Eq);
+        let expr = self.parse_initializer();
+
+        Constant {
+            name,
+            public: false,
+            ty,
+            expr,
+        }
+    }
+
+    /// Parses the expression after the `=` of a declaration, including the closing `;`.
+    fn parse_initializer(&mut self) -> Expr {
+        let expr = self.parse_expr();
+        if expr == Expr::None {
+            self.error_with_diag(
+                diag!(
+                    "Unexpected '=' without expression.",
+                    "Provide an expression.",
+                    self.previous_span()
+                )
+                .with_code(ErrorCode::ExpectedExpression),
+            );
+        } else {
+            self.terminate();
+        }
+
+        expr
+    }
+
+    fn parse_if(&mut self) -> Statement {
+        let condition = self.parse_header_expr();
+        let then_branch = self.parse_block();
+        let mut else_branch: Option<Block> = None;
+
+        if self.is_curr_token(Ty::KElse) {
+            self.advance();
+            if self.is_curr_token(Ty::KIf) {
+                // `else if` is sugar for an `else` block holding a single `if`.
+                self.advance();
+                let mut block = Block::new();
+                block.push_stmt(self.parse_if());
+                else_branch = Some(block);
+            } else {
+                else_branch = Some(self.parse_block());
+            }
+        }
+
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+        }
+    }
+
+    fn parse_range(&mut self) -> Expr {
+        let start = Box::new(self.parse_header_expr());
+        let inclusive = match self.current_ty() {
+            Ty::DoubleDot => false,
+            Ty::DoubleDotEq => true,
+            _ => {
+                self.error_with_diag(
+                    diag!(
+                        "Expected a range.",
+                        "Write a range such as `0..10` or `0..=9`.",
+                        self.current_span()
+                    )
+                    .with_code(ErrorCode::UnexpectedToken),
+                );
+                return Expr::Unknown;
+            }
+        };
+        self.advance();
+        let end = Box::new(self.parse_header_expr());
+
+        Expr::Range {
+            start,
+            end,
+            inclusive,
+        }
+    }
+
     fn parse_expr(&mut self) -> Expr {
-        self.equality()
+        self.assignment()
+    }
+
+    /// Parses an expression that is directly followed by a block, such as an `if` condition.
+    fn parse_header_expr(&mut self) -> Expr {
+        self.with_struct_literals(false, Self::parse_expr)
+    }
+
+    /// Parses an expression enclosed in delimiters, where struct literals are always allowed.
+    fn parse_nested_expr(&mut self) -> Expr {
+        self.with_struct_literals(true, Self::parse_expr)
+    }
+
+    fn with_struct_literals(&mut self, allow: bool, parse: fn(&mut Self) -> Expr) -> Expr {
+        let outer = std::mem::replace(&mut self.allow_struct_literal, allow);
+        let expr = parse(self);
+        self.allow_struct_literal = outer;
+        expr
+    }
+
+    fn assignment(&mut self) -> Expr {
+        let expr = self.logic_or();
+
+        let op = match self.current_ty() {
+            Ty::Eq => None,
+            Ty::PlusEq => Some(BinOp::Add),
+            Ty::MinusEq => Some(BinOp::Subtract),
+            Ty::AsteriskEq => Some(BinOp::Multiply),
+            Ty::SlashEq => Some(BinOp::Divide),
+            _ => return expr,
+        };
+        self.advance();
+        let value = Box::new(self.assignment());
+
+        if !matches!(expr, Expr::Var(_) | Expr::Field { .. } | Expr::Index { .. }) {
+            self.error_with_diag(
+                diag!(
+                    "Invalid assignment target.",
+                    "Only variables, fields and array elements can be assigned to.",
+                    expr.span()
+                )
+                .with_code(ErrorCode::InvalidAssignmentTarget),
+            );
+        }
+
+        Expr::Assign {
+            target: Box::new(expr),
+            op,
+            value,
+        }
+    }
+
+    fn logic_or(&mut self) -> Expr {
+        let mut expr = self.logic_and();
+
+        while self.is_curr_token(Ty::DoublePipe) {
+            self.advance();
+            let rhs = Box::new(self.logic_and());
+
+            expr = Expr::Binary {
+                lhs: Box::new(expr),
+                op: BinOp::Or,
+                rhs,
+            }
+        }
+
+        expr
+    }
+
+    fn logic_and(&mut self) -> Expr {
+        let mut expr = self.equality();
+
+        while self.is_curr_token(Ty::DoubleAmpersand) {
+            self.advance();
+            let rhs = Box::new(self.equality());
+
+            expr = Expr::Binary {
+                lhs: Box::new(expr),
+                op: BinOp::And,
+                rhs,
+            }
+        }
+
+        expr
     }
 
     fn equality(&mut self) -> Expr {
@@ -199,7 +701,7 @@ impl<'a> Parser<'a> {
                 _ => BinOp::Subtract,
             };
 
-            let rhs = Box::new(self.term());
+            let rhs = Box::new(self.factor());
 
             expr = Expr::Binary {
                 lhs: Box::new(expr),
@@ -244,7 +746,210 @@ impl<'a> Parser<'a> {
             return Expr::Unary { op, rhs };
         }
 
-        self.primary()
+        self.call()
+    }
+
+    fn call(&mut self) -> Expr {
+        let mut expr = self.primary();
+
+        loop {
+            if self.is_curr_token(Ty::LParen) {
+                self.advance();
+                let args = self.parse_args();
+                self.consume(Ty::RParen);
+
+                expr = Expr::Call {
+                    callee: Box::new(expr),
+                    args,
+                }
+            } else if self.is_curr_token(Ty::LBoxed) {
+                self.advance();
+                let index = Box::new(self.parse_nested_expr());
+                self.consume(Ty::RBoxed);
+
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    index,
+                }
+            } else if self.is_curr_token(Ty::Dot) {
+                self.advance();
+                let field = self.must_consume_ident();
+
+                expr = Expr::Field {
+                    object: Box::new(expr),
+                    field,
+                }
+            } else {
+                break;
+            }
+        }
+
+        expr
+    }
+
+    fn parse_args(&mut self) -> Vec<Expr> {
+        let mut args: Vec<Expr> = Vec::new();
+        if self.is_curr_token(Ty::RParen) {
+            return args;
+        }
+
+        args.push(self.parse_nested_expr());
+        while self.is_curr_token(Ty::Comma) {
+            self.consume(Ty::Comma);
+            args.push(self.parse_nested_expr());
+        }
+
+        args
+    }
+
+    fn parse_array(&mut self) -> Expr {
+        let open = self.advance().span;
+        let mut elements: Vec<Expr> = Vec::new();
+
+        while !self.is_curr_token(Ty::RBoxed) && !self.is_at_end() {
+            elements.push(self.parse_nested_expr());
+            if !self.is_curr_token(Ty::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        let close = self.current_span();
+        self.consume(Ty::RBoxed);
+
+        Expr::Array {
+            elements,
+            span: open.to(close),
+        }
+    }
+
+    fn parse_path(&mut self) -> Vec<Token> {
+        let mut segments = vec![self.must_consume_ident()];
+        while self.is_curr_token(Ty::DoubleColon) {
+            self.advance();
+            segments.push(self.must_consume_ident());
+        }
+
+        segments
+    }
+
+    /// Parses a name that may be qualified with a module, such as `math::Point`
+    /// or `geo::shapes::Point`. A qualified name is kept as a single identifier
+    /// covering the whole path.
+    fn parse_qualified_name(&mut self) -> Token {
+        let mut name = self.must_consume_ident();
+        while self.is_curr_token(Ty::DoubleColon) {
+            self.advance();
+            let item = self.must_consume_ident();
+            name = Token::new(
+                Ty::Identifier(format!("{}::{}", name.as_ident(), item.as_ident())),
+                name.span.to(item.span),
+            );
+        }
+
+        name
+    }
+
+    fn parse_match(&mut self) -> Expr {
+        let keyword = self.advance().clone();
+        let scrutinee = Box::new(self.parse_header_expr());
+        let mut arms: Vec<MatchArm> = Vec::new();
+
+        self.consume(Ty::LCurly);
+        while !self.is_curr_token(Ty::RCurly) && !self.is_at_end() {
+            let pattern = self.parse_pattern();
+            self.consume(Ty::RightFatArrow);
+            let body = if self.is_curr_token(Ty::LCurly) {
+                ArmBody::Block(self.parse_block())
+            } else {
+                ArmBody::Expr(self.parse_nested_expr())
+            };
+            let is_block = matches!(body, ArmBody::Block(_));
+            arms.push(MatchArm { pattern, body });
+
+            // Arms are separated by commas, which are optional after a block.
+            if self.is_curr_token(Ty::Comma) {
+                self.advance();
+            } else if !is_block {
+                break;
+            }
+        }
+        self.consume(Ty::RCurly);
+
+        Expr::Match {
+            keyword,
+            scrutinee,
+            arms,
+        }
+    }
+
+    fn parse_pattern(&mut self) -> Pattern {
+        let negative_number = self.is_curr_token(Ty::Minus)
+            && self
+                .peek(1)
+                .is_some_and(|next| matches!(next.ty, Ty::Integer(_) | Ty::Float(_)));
+
+        if negative_number {
+            self.advance();
+            let literal = Expr::Literal(self.advance().clone());
+            Pattern::Literal(Expr::Unary {
+                op: UnaryOp::Negative,
+                rhs: Box::new(literal),
+            })
+        } else if self.is_curr_token_int()
+            || self.is_curr_token_float()
+            || self.is_curr_token_char()
+            || self.is_curr_token_string()
+            || self.is_curr_token_bool()
+        {
+            Pattern::Literal(Expr::Literal(self.advance().clone()))
+        } else if self.current().as_ident() == "_" {
+            Pattern::Wildcard(self.advance().clone())
+        } else if self.is_curr_token_ident()
+            && self.peek(1).is_some_and(|next| *next == Ty::DoubleColon)
+        {
+            let path = self.parse_path();
+            let mut fields: Vec<Pattern> = Vec::new();
+            if self.is_curr_token(Ty::LParen) {
+                self.advance();
+                while !self.is_curr_token(Ty::RParen) && !self.is_at_end() {
+                    fields.push(self.parse_pattern());
+                    if !self.is_curr_token(Ty::Comma) {
+                        break;
+                    }
+                    self.advance();
+                }
+                self.consume(Ty::RParen);
+            }
+            Pattern::Variant { path, fields }
+        } else if self.is_curr_token_ident() {
+            Pattern::Binding(self.advance().clone())
+        } else {
+            self.error(ErrorCode::UnexpectedToken, "Expected a pattern.");
+            // Skip the token so that parsing makes progress, and treat it as `_`
+            // to avoid follow-up errors about the match.
+            Pattern::Wildcard(self.advance().clone())
+        }
+    }
+
+    fn parse_struct_literal(&mut self) -> Expr {
+        let name = self.parse_qualified_name();
+        let mut fields: Vec<FieldInit> = Vec::new();
+
+        self.consume(Ty::LCurly);
+        while !self.is_curr_token(Ty::RCurly) && !self.is_at_end() {
+            let name = self.must_consume_ident();
+            self.consume(Ty::Colon);
+            let value = self.parse_nested_expr();
+            fields.push(FieldInit { name, value });
+
+            if !self.is_curr_token(Ty::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        self.consume(Ty::RCurly);
+
+        Expr::StructLiteral { name, fields }
     }
 
     fn primary(&mut self) -> Expr {
@@ -252,21 +957,46 @@ impl<'a> Parser<'a> {
             || self.is_curr_token_float()
             || self.is_curr_token_char()
             || self.is_curr_token_string()
+            || self.is_curr_token_bool()
         {
-            return Expr::Literal(self.advance().clone());
+            Expr::Literal(self.advance().clone())
+        } else if self.is_curr_token_ident()
+            && self.peek(1).is_some_and(|next| *next == Ty::DoubleColon)
+        {
+            // `module::Struct { ... }` is the only path that can open a literal,
+            // and the module may itself be a path.
+            let mut end = 1;
+            while self.peek(end).is_some_and(|next| *next == Ty::DoubleColon) {
+                end += 2;
+            }
+            let opens_literal = self.peek(end).is_some_and(|next| *next == Ty::LCurly);
+            if opens_literal && self.allow_struct_literal {
+                self.parse_struct_literal()
+            } else {
+                Expr::Path(self.parse_path())
+            }
+        } else if self.is_curr_token(Ty::KMatch) {
+            self.parse_match()
         } else if self.is_curr_token_ident() {
-            return Expr::Var(self.advance().clone());
+            let opens_literal = self.peek(1).is_some_and(|next| *next == Ty::LCurly);
+            if opens_literal && self.allow_struct_literal {
+                self.parse_struct_literal()
+            } else {
+                Expr::Var(self.advance().clone())
+            }
+        } else if self.is_curr_token(Ty::LBoxed) {
+            self.parse_array()
         } else if self.is_curr_token(Ty::LParen) {
             self.advance();
-            let expr = Box::new(self.parse_expr());
+            let expr = Box::new(self.parse_nested_expr());
             self.consume(Ty::RParen);
-            return Expr::Grouping(expr);
+            Expr::Grouping(expr)
         } else if self.is_curr_token(Ty::Semicolon) {
             self.terminate();
-            return Expr::None;
+            Expr::None
         } else {
-            self.error("Expected an expression.");
-            return Expr::Unknown;
+            self.error(ErrorCode::ExpectedExpression, "Expected an expression.");
+            Expr::Unknown
         }
     }
 
@@ -275,7 +1005,8 @@ impl<'a> Parser<'a> {
     }
 
     fn current(&self) -> &Token {
-        self.peek(0).unwrap()
+        // Every token stream ends with `<EOF>`, which is returned once the parser runs past it.
+        self.peek(0).unwrap_or_else(|| self.tokens.last().unwrap())
     }
 
     fn current_ty(&self) -> &Ty {
@@ -302,15 +1033,15 @@ impl<'a> Parser<'a> {
         self.compiler.reporter.borrow_mut().add(diagnostic);
     }
 
-    fn error(&mut self, message: impl Into<String>) {
-        self.error_with_diag(diag!(message.into(), self.current_span()));
+    fn error(&mut self, code: ErrorCode, message: impl Into<String>) {
+        self.error_with_diag(diag!(message.into(), self.current_span()).with_code(code));
     }
 
-    fn error_on_prev_span(&mut self, message: impl Into<String>) {
+    fn error_on_prev_span(&mut self, code: ErrorCode, message: impl Into<String>) {
         self.compiler
             .reporter
             .borrow_mut()
-            .add(diag!(message.into(), self.previous_span()))
+            .add(diag!(message.into(), self.previous_span()).with_code(code))
     }
 
     fn sync(&mut self, sync_with_semicolon: bool) {
@@ -320,7 +1051,14 @@ impl<'a> Parser<'a> {
             }
 
             match self.current_ty() {
-                Ty::KClass | Ty::KStruct | Ty::KFunction => break,
+                Ty::KClass
+                | Ty::KStruct
+                | Ty::KEnum
+                | Ty::KFunction
+                | Ty::KImport
+                | Ty::KUse
+                | Ty::KPub
+                | Ty::Hash => break,
                 _ => {}
             }
             self.advance();
@@ -332,7 +1070,9 @@ impl<'a> Parser<'a> {
     }
 
     fn advance(&mut self) -> &Token {
-        self.current += 1;
+        if !self.is_at_end() {
+            self.current += 1;
+        }
         self.previous()
     }
 
@@ -342,47 +1082,34 @@ impl<'a> Parser<'a> {
 
     fn consume(&mut self, token_type: Ty) {
         if self.current().is_eof() {
-            self.error("Unexpected <EOF>.");
+            self.error(ErrorCode::UnexpectedToken, "Unexpected <EOF>.");
             return;
         }
 
-        if *self.advance() == token_type {
+        if *self.current() == token_type {
+            self.advance();
         } else {
-            self.error_with_diag(diag!(
-                format!("Expected token: `{}`", token_type),
-                format!("Put {} here.", token_type),
-                self.current_span()
-            ));
+            self.error_with_diag(
+                diag!(
+                    format!("Expected token: `{}`", token_type),
+                    format!("Put {} here.", token_type),
+                    self.current_span()
+                )
+                .with_code(ErrorCode::UnexpectedToken),
+            );
         }
     }
 
-    fn consume_ident(&mut self) -> Option<Token> {
-        if self.current().is_eof() {
-            self.error("Unexpected <EOF>.");
-            return None;
-        }
-
-        let ident = match self.current_ty() {
-            Ty::Identifier(_) => Some(self.current().clone()),
-            _ => {
-                return None;
-            }
-        };
-        self.advance();
-
-        ident
-    }
-
     fn must_consume_ident(&mut self) -> Token {
         if self.current().is_eof() {
-            self.error("Unexpected <EOF>.");
+            self.error(ErrorCode::UnexpectedToken, "Unexpected <EOF>.");
             return Token::default();
         }
 
         let ident = match self.current_ty() {
             Ty::Identifier(_) => self.current().clone(),
             _ => {
-                self.error("Expected an identifier.");
+                self.error(ErrorCode::UnexpectedToken, "Expected an identifier.");
                 return Token::default();
             }
         };
@@ -411,11 +1138,15 @@ impl<'a> Parser<'a> {
         matches!(self.current_ty(), Ty::String(_))
     }
 
+    fn is_curr_token_bool(&self) -> bool {
+        matches!(self.current_ty(), Ty::Bool(_))
+    }
+
     fn is_curr_token_ident(&self) -> bool {
         matches!(self.current_ty(), Ty::Identifier(_))
     }
 
     fn is_at_end(&self) -> bool {
-        self.current == self.tokens.len()
+        self.current().is_eof()
     }
 }
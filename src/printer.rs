@@ -1,9 +1,10 @@
-use std::fmt::{write, Display};
-
 use colored::Colorize;
 
 use crate::{
-    ast::{BinOp, Block, Expr, Function, Item, Statement},
+    ast::{
+        ArmBody, BinOp, Block, Class, Constant, Enum, Expr, FieldInit, Function, Item, MatchArm,
+        Pattern, Statement, Struct, TypeExpr, UnaryOp,
+    },
     utils::{Token, TokenType as Ty},
 };
 
@@ -13,7 +14,11 @@ pub trait Visitor {
             Item::Func(function) => {
                 self.visit_func(function);
             }
-            Item::Unknown => unimplemented!(),
+            Item::Const(constant) => self.visit_const(constant),
+            Item::Struct(decl) => self.visit_struct(decl),
+            Item::Class(class) => self.visit_class(class),
+            Item::Enum(decl) => self.visit_enum(decl),
+            Item::Import(_) | Item::Attribute(_) | Item::Unknown => {}
         }
     }
     fn visit_item(&mut self, item: &Item) {
@@ -23,34 +28,89 @@ pub trait Visitor {
     //     self.visit_func(function);
     // }
     fn visit_func(&mut self, function: &Function);
+    fn visit_struct(&mut self, decl: &Struct);
+    fn visit_class(&mut self, class: &Class);
+    fn visit_enum(&mut self, decl: &Enum);
     fn do_visit_stmt(&mut self, stmt: &Statement) {
         match stmt {
-            Statement::VarDecl { name, ty, expr } => self.visit_var_decl(name, ty, expr),
+            Statement::VarDecl {
+                name,
+                mutable,
+                ty,
+                expr,
+            } => self.visit_var_decl(name, *mutable, ty, expr),
+            Statement::Const(constant) => self.visit_const(constant),
+            Statement::Return { expr, .. } => self.visit_return(expr),
             Statement::Expression(expr) => self.visit_expr(expr),
-            _ => unimplemented!(),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => self.visit_if(condition, then_branch, else_branch),
+            Statement::While { condition, body } => self.visit_while(condition, body),
+            Statement::For { var, range, body } => self.visit_for(var, range, body),
+            Statement::Break(keyword) => self.visit_break(keyword),
+            Statement::Continue(keyword) => self.visit_continue(keyword),
+            Statement::Unknown => {}
         }
     }
     fn visit_block(&mut self, block: &Block);
     fn visit_stmt(&mut self, stmt: &Statement) {
         self.do_visit_stmt(stmt);
     }
-    fn visit_var_decl(&mut self, name: &Token, ty: &Option<Token>, expr: &Expr);
+    fn visit_var_decl(&mut self, name: &Token, mutable: bool, ty: &Option<TypeExpr>, expr: &Expr);
+    fn visit_const(&mut self, constant: &Constant);
+    fn visit_return(&mut self, expr: &Expr);
+    fn visit_if(&mut self, condition: &Expr, then_branch: &Block, else_branch: &Option<Block>);
+    fn visit_while(&mut self, condition: &Expr, body: &Block);
+    fn visit_for(&mut self, var: &Token, range: &Expr, body: &Block);
+    fn visit_break(&mut self, keyword: &Token);
+    fn visit_continue(&mut self, keyword: &Token);
     fn do_visit_expr(&mut self, expr: &Expr) {
         match expr {
             Expr::Binary { lhs, op, rhs } => self.visit_binary_expr(lhs, op, rhs),
             Expr::Var(v) => self.visit_ident(v),
             Expr::Literal(literal) => self.visit_literal(literal),
-            _ => unimplemented!(),
+            Expr::Unary { op, rhs } => self.visit_unary_expr(op, rhs),
+            Expr::Grouping(expr) => self.visit_grouping(expr),
+            Expr::Call { callee, args } => self.visit_call(callee, args),
+            Expr::Range {
+                start,
+                end,
+                inclusive,
+            } => self.visit_range(start, end, *inclusive),
+            Expr::Array { elements, .. } => self.visit_array(elements),
+            Expr::Index { object, index } => self.visit_index(object, index),
+            Expr::Path(segments) => self.visit_path(segments),
+            Expr::Match {
+                scrutinee, arms, ..
+            } => self.visit_match(scrutinee, arms),
+            Expr::StructLiteral { name, fields } => self.visit_struct_literal(name, fields),
+            Expr::Field { object, field } => self.visit_field(object, field),
+            Expr::Assign { target, op, value } => self.visit_assign(target, op, value),
+            Expr::None | Expr::Unknown => {}
         }
     }
     fn visit_expr(&mut self, expr: &Expr) {
         self.do_visit_expr(expr);
     }
-    fn visit_binary_expr(&mut self, lhs: &Box<Expr>, op: &BinOp, rhs: &Box<Expr>);
+    fn visit_binary_expr(&mut self, lhs: &Expr, op: &BinOp, rhs: &Expr);
+    fn visit_unary_expr(&mut self, op: &UnaryOp, rhs: &Expr);
+    fn visit_grouping(&mut self, expr: &Expr);
     fn visit_ident(&mut self, ident: &Token);
     fn visit_literal(&mut self, literal: &Token);
+    fn visit_call(&mut self, callee: &Expr, args: &[Expr]);
+    fn visit_range(&mut self, start: &Expr, end: &Expr, inclusive: bool);
+    fn visit_array(&mut self, elements: &[Expr]);
+    fn visit_index(&mut self, object: &Expr, index: &Expr);
+    fn visit_path(&mut self, segments: &[Token]);
+    fn visit_match(&mut self, scrutinee: &Expr, arms: &[MatchArm]);
+    fn visit_struct_literal(&mut self, name: &Token, fields: &[FieldInit]);
+    fn visit_field(&mut self, object: &Expr, field: &Token);
+    fn visit_assign(&mut self, target: &Expr, op: &Option<BinOp>, value: &Expr);
 }
 
+#[derive(Default)]
 pub struct AstPrinter {
     indent: usize,
 }
@@ -73,6 +133,36 @@ impl AstPrinter {
     fn decr_indent(&mut self) {
         self.indent -= LEVEL_INDENT
     }
+
+    fn print_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard(token) => self.print_with_indent(&format!("Wildcard: {}", token)),
+            Pattern::Binding(name) => self.print_with_indent(&format!("Binding: {}", name)),
+            Pattern::Literal(expr) => {
+                self.print_with_indent("Literal: {");
+                self.incr_indent();
+                self.visit_expr(expr);
+                self.decr_indent();
+                self.print_with_indent("}");
+            }
+            Pattern::Variant { path, fields } => {
+                self.print_with_indent("Variant: {");
+                self.incr_indent();
+                self.visit_path(path);
+                if !fields.is_empty() {
+                    self.print_with_indent("fields: [");
+                    self.incr_indent();
+                    for field in fields {
+                        self.print_pattern(field);
+                    }
+                    self.decr_indent();
+                    self.print_with_indent("]");
+                }
+                self.decr_indent();
+                self.print_with_indent("}");
+            }
+        }
+    }
 }
 
 impl Visitor for AstPrinter {
@@ -90,9 +180,12 @@ impl Visitor for AstPrinter {
     fn visit_func(&mut self, function: &Function) {
         self.print_with_indent(&format!("{}: Function {{", function.name));
         self.incr_indent();
-        if function.params.len() != 0 {
+        if function.self_param.is_some() || !function.params.is_empty() {
             self.print_with_indent("params: [");
             self.incr_indent();
+            if let Some(receiver) = &function.self_param {
+                self.print_with_indent(&format!("{}", receiver));
+            }
             for param in &function.params {
                 self.print_with_indent(&format!("{}", param));
             }
@@ -108,22 +201,94 @@ impl Visitor for AstPrinter {
         }
         self.visit_block(&function.body);
         self.decr_indent();
-        self.print_with_indent(&format!("}}"));
+        self.print_with_indent("}");
+    }
+
+    fn visit_struct(&mut self, decl: &Struct) {
+        self.print_with_indent(&format!("{}: Struct {{", decl.name));
+        self.incr_indent();
+        if !decl.fields.is_empty() {
+            self.print_with_indent("fields: [");
+            self.incr_indent();
+            for field in &decl.fields {
+                self.print_with_indent(&format!("{}", field));
+            }
+            self.decr_indent();
+            self.print_with_indent("]");
+        } else {
+            self.print_with_indent("fields: []");
+        }
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        self.print_with_indent(&format!("{}: Class {{", class.name));
+        self.incr_indent();
+        if !class.fields.is_empty() {
+            self.print_with_indent("fields: [");
+            self.incr_indent();
+            for field in &class.fields {
+                self.print_with_indent(&format!("{}", field));
+            }
+            self.decr_indent();
+            self.print_with_indent("]");
+        } else {
+            self.print_with_indent("fields: []");
+        }
+        if !class.methods.is_empty() {
+            self.print_with_indent("methods: [");
+            self.incr_indent();
+            for method in &class.methods {
+                self.visit_func(method);
+            }
+            self.decr_indent();
+            self.print_with_indent("]");
+        } else {
+            self.print_with_indent("methods: []");
+        }
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_enum(&mut self, decl: &Enum) {
+        self.print_with_indent(&format!("{}: Enum {{", decl.name));
+        self.incr_indent();
+        if !decl.variants.is_empty() {
+            self.print_with_indent("variants: [");
+            self.incr_indent();
+            for variant in &decl.variants {
+                let fields: Vec<String> = variant.fields.iter().map(|ty| ty.to_string()).collect();
+                if fields.is_empty() {
+                    self.print_with_indent(&format!("{}", variant.name));
+                } else {
+                    self.print_with_indent(&format!("{}({})", variant.name, fields.join(", ")));
+                }
+            }
+            self.decr_indent();
+            self.print_with_indent("]");
+        } else {
+            self.print_with_indent("variants: []");
+        }
+        self.decr_indent();
+        self.print_with_indent("}");
     }
 
     fn visit_block(&mut self, block: &Block) {
         self.print_with_indent("body: {");
         self.incr_indent();
         for stmt in &block.0 {
-            self.do_visit_stmt(stmt);
+            self.visit_stmt(stmt);
         }
+        self.decr_indent();
         self.print_with_indent("}");
     }
 
-    fn visit_var_decl(&mut self, name: &Token, ty: &Option<Token>, expr: &Expr) {
+    fn visit_var_decl(&mut self, name: &Token, mutable: bool, ty: &Option<TypeExpr>, expr: &Expr) {
         self.print_with_indent("VarDecl: {");
         self.incr_indent();
         self.print_with_indent(&format!("name: {}", name));
+        self.print_with_indent(&format!("mutable: {}", mutable));
         if let Some(t) = ty {
             self.print_with_indent(&format!("ty: {}", t));
         } else {
@@ -131,15 +296,103 @@ impl Visitor for AstPrinter {
         }
         if *expr != Expr::None {
             self.print_with_indent("expr: {");
+            self.incr_indent();
             self.visit_expr(expr);
+            self.decr_indent();
             self.print_with_indent("}");
         } else {
             self.print_with_indent("expr: ()");
         }
         self.decr_indent();
+        self.print_with_indent("}");
     }
 
-    fn visit_binary_expr(&mut self, lhs: &Box<Expr>, op: &BinOp, rhs: &Box<Expr>) {
+    fn visit_const(&mut self, constant: &Constant) {
+        self.print_with_indent("Const: {");
+        self.incr_indent();
+        self.print_with_indent(&format!("name: {}", constant.name));
+        if let Some(t) = &constant.ty {
+            self.print_with_indent(&format!("ty: {}", t));
+        } else {
+            self.print_with_indent("ty: ()");
+        }
+        self.print_with_indent("expr: {");
+        self.incr_indent();
+        self.visit_expr(&constant.expr);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_return(&mut self, expr: &Expr) {
+        if *expr != Expr::None {
+            self.print_with_indent("Return: {");
+            self.incr_indent();
+            self.visit_expr(expr);
+            self.decr_indent();
+            self.print_with_indent("}");
+        } else {
+            self.print_with_indent("Return: ()");
+        }
+    }
+
+    fn visit_if(&mut self, condition: &Expr, then_branch: &Block, else_branch: &Option<Block>) {
+        self.print_with_indent("If: {");
+        self.incr_indent();
+        self.print_with_indent("condition: {");
+        self.incr_indent();
+        self.visit_expr(condition);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.visit_block(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.print_with_indent("else: {");
+            self.incr_indent();
+            self.visit_block(else_branch);
+            self.decr_indent();
+            self.print_with_indent("}");
+        }
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_while(&mut self, condition: &Expr, body: &Block) {
+        self.print_with_indent("While: {");
+        self.incr_indent();
+        self.print_with_indent("condition: {");
+        self.incr_indent();
+        self.visit_expr(condition);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.visit_block(body);
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_for(&mut self, var: &Token, range: &Expr, body: &Block) {
+        self.print_with_indent("For: {");
+        self.incr_indent();
+        self.print_with_indent(&format!("var: {}", var));
+        self.print_with_indent("range: {");
+        self.incr_indent();
+        self.visit_expr(range);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.visit_block(body);
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_break(&mut self, keyword: &Token) {
+        self.print_with_indent(&format!("Break: {}", keyword));
+    }
+
+    fn visit_continue(&mut self, keyword: &Token) {
+        self.print_with_indent(&format!("Continue: {}", keyword));
+    }
+
+    fn visit_binary_expr(&mut self, lhs: &Expr, op: &BinOp, rhs: &Expr) {
         self.print_with_indent("lhs: {");
         self.incr_indent();
         self.visit_expr(lhs);
@@ -153,6 +406,23 @@ impl Visitor for AstPrinter {
         self.print_with_indent("}");
     }
 
+    fn visit_unary_expr(&mut self, op: &UnaryOp, rhs: &Expr) {
+        self.print_with_indent(&format!("op: {}", op));
+        self.print_with_indent("rhs: {");
+        self.incr_indent();
+        self.visit_expr(rhs);
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_grouping(&mut self, expr: &Expr) {
+        self.print_with_indent("Grouping: (");
+        self.incr_indent();
+        self.visit_expr(expr);
+        self.decr_indent();
+        self.print_with_indent(")");
+    }
+
     fn visit_ident(&mut self, ident: &Token) {
         self.print_with_indent(&format!("{}", ident));
     }
@@ -163,7 +433,167 @@ impl Visitor for AstPrinter {
             Ty::Float(float) => self.print_with_indent(&format!("{}", float.cyan())),
             Ty::String(str) => self.print_with_indent(&format!("\"{}\"", str.green())),
             Ty::Char(ch) => self.print_with_indent(&format!("'{}'", ch).green()),
+            Ty::Bool(bool) => self.print_with_indent(&format!("{}", bool).cyan()),
             _ => unreachable!(),
         }
     }
+
+    fn visit_call(&mut self, callee: &Expr, args: &[Expr]) {
+        self.print_with_indent("Call: {");
+        self.incr_indent();
+        self.print_with_indent("callee: {");
+        self.incr_indent();
+        self.visit_expr(callee);
+        self.decr_indent();
+        self.print_with_indent("}");
+        if !args.is_empty() {
+            self.print_with_indent("args: [");
+            self.incr_indent();
+            for arg in args {
+                self.visit_expr(arg);
+            }
+            self.decr_indent();
+            self.print_with_indent("]");
+        } else {
+            self.print_with_indent("args: []");
+        }
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_range(&mut self, start: &Expr, end: &Expr, inclusive: bool) {
+        self.print_with_indent("Range: {");
+        self.incr_indent();
+        self.print_with_indent("start: {");
+        self.incr_indent();
+        self.visit_expr(start);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.print_with_indent(&format!("inclusive: {}", inclusive));
+        self.print_with_indent("end: {");
+        self.incr_indent();
+        self.visit_expr(end);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_array(&mut self, elements: &[Expr]) {
+        if elements.is_empty() {
+            self.print_with_indent("Array: []");
+            return;
+        }
+        self.print_with_indent("Array: [");
+        self.incr_indent();
+        for element in elements {
+            self.visit_expr(element);
+        }
+        self.decr_indent();
+        self.print_with_indent("]");
+    }
+
+    fn visit_index(&mut self, object: &Expr, index: &Expr) {
+        self.print_with_indent("Index: {");
+        self.incr_indent();
+        self.print_with_indent("object: {");
+        self.incr_indent();
+        self.visit_expr(object);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.print_with_indent("index: {");
+        self.incr_indent();
+        self.visit_expr(index);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_path(&mut self, segments: &[Token]) {
+        let segments: Vec<String> = segments.iter().map(|segment| segment.to_string()).collect();
+        self.print_with_indent(&format!("Path: {}", segments.join("::")));
+    }
+
+    fn visit_match(&mut self, scrutinee: &Expr, arms: &[MatchArm]) {
+        self.print_with_indent("Match: {");
+        self.incr_indent();
+        self.print_with_indent("scrutinee: {");
+        self.incr_indent();
+        self.visit_expr(scrutinee);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.print_with_indent("arms: [");
+        self.incr_indent();
+        for arm in arms {
+            self.print_with_indent("Arm: {");
+            self.incr_indent();
+            self.print_pattern(&arm.pattern);
+            match &arm.body {
+                ArmBody::Expr(expr) => {
+                    self.print_with_indent("expr: {");
+                    self.incr_indent();
+                    self.visit_expr(expr);
+                    self.decr_indent();
+                    self.print_with_indent("}");
+                }
+                ArmBody::Block(block) => self.visit_block(block),
+            }
+            self.decr_indent();
+            self.print_with_indent("}");
+        }
+        self.decr_indent();
+        self.print_with_indent("]");
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_struct_literal(&mut self, name: &Token, fields: &[FieldInit]) {
+        self.print_with_indent(&format!("{}: StructLiteral {{", name));
+        self.incr_indent();
+        for field in fields {
+            self.print_with_indent(&format!("{}: {{", field.name));
+            self.incr_indent();
+            self.visit_expr(&field.value);
+            self.decr_indent();
+            self.print_with_indent("}");
+        }
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_field(&mut self, object: &Expr, field: &Token) {
+        self.print_with_indent("Field: {");
+        self.incr_indent();
+        self.print_with_indent("object: {");
+        self.incr_indent();
+        self.visit_expr(object);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.print_with_indent(&format!("field: {}", field));
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
+
+    fn visit_assign(&mut self, target: &Expr, op: &Option<BinOp>, value: &Expr) {
+        self.print_with_indent("Assign: {");
+        self.incr_indent();
+        self.print_with_indent("target: {");
+        self.incr_indent();
+        self.visit_expr(target);
+        self.decr_indent();
+        self.print_with_indent("}");
+        if let Some(op) = op {
+            self.print_with_indent(&format!("op: {}=", op));
+        } else {
+            self.print_with_indent("op: =");
+        }
+        self.print_with_indent("value: {");
+        self.incr_indent();
+        self.visit_expr(value);
+        self.decr_indent();
+        self.print_with_indent("}");
+        self.decr_indent();
+        self.print_with_indent("}");
+    }
 }
@@ -0,0 +1,210 @@
+use crate::{
+    ast::{ArmBody, Ast, Block, Expr, Function, Item, Pattern, Statement, TypeExpr},
+    resolver::Resolutions,
+    utils::{Token, TokenType as Ty},
+};
+
+/// Gives every item of an imported module its qualified name, such as
+/// `math::sqrt`, at its declaration and at every use, and turns paths into
+/// other modules into plain names. Later passes look items up by name, so
+/// this keeps items with the same name in different modules apart.
+pub struct Qualifier<'a> {
+    resolutions: &'a Resolutions,
+}
+
+impl<'a> Qualifier<'a> {
+    pub fn new(resolutions: &'a Resolutions) -> Self {
+        Self { resolutions }
+    }
+
+    pub fn qualify(&self, ast: &mut Ast) {
+        for item in &mut ast.items {
+            match item {
+                Item::Func(function) => self.function(function),
+                Item::Const(constant) => {
+                    self.name(&mut constant.name);
+                    self.ty(&mut constant.ty);
+                    self.expr(&mut constant.expr);
+                }
+                Item::Struct(decl) => {
+                    self.name(&mut decl.name);
+                    for field in &mut decl.fields {
+                        self.type_expr(&mut field.ty);
+                    }
+                }
+                Item::Class(class) => {
+                    self.name(&mut class.name);
+                    for field in &mut class.fields {
+                        self.type_expr(&mut field.ty);
+                    }
+                    for method in &mut class.methods {
+                        self.function(method);
+                    }
+                }
+                Item::Enum(decl) => {
+                    self.name(&mut decl.name);
+                    for variant in &mut decl.variants {
+                        for ty in &mut variant.fields {
+                            self.type_expr(ty);
+                        }
+                    }
+                }
+                Item::Import(_) | Item::Attribute(_) | Item::Unknown => {}
+            }
+        }
+    }
+
+    /// Renames `name` if it declares or refers to an item of an imported module.
+    fn name(&self, name: &mut Token) {
+        if let Some(qualified) = self.resolutions.qualified_name(name.span) {
+            name.ty = Ty::Identifier(qualified.to_string());
+        }
+    }
+
+    /// Replaces a leading `module::item`, whose module may itself be a path such
+    /// as `geo::shapes`, in `path` with the qualified name of the item.
+    fn path(&self, path: &mut Vec<Token>) {
+        for end in (1..path.len()).rev() {
+            let span = path[0].span.to(path[end].span);
+            if let Some(qualified) = self.resolutions.qualified_name(span) {
+                let name = Token::new(Ty::Identifier(qualified.to_string()), span);
+                path.splice(0..=end, [name]);
+                return;
+            }
+        }
+        self.name(&mut path[0]);
+    }
+
+    fn ty(&self, ty: &mut Option<TypeExpr>) {
+        if let Some(ty) = ty {
+            self.type_expr(ty);
+        }
+    }
+
+    fn type_expr(&self, ty: &mut TypeExpr) {
+        match ty {
+            TypeExpr::Named(name) => self.name(name),
+            TypeExpr::Array { element, .. } => self.type_expr(element),
+        }
+    }
+
+    fn function(&self, function: &mut Function) {
+        self.name(&mut function.name);
+        for param in &mut function.params {
+            self.type_expr(&mut param.ty);
+        }
+        self.ty(&mut function.return_ty);
+        self.block(&mut function.body);
+    }
+
+    fn block(&self, block: &mut Block) {
+        for stmt in &mut block.0 {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&self, stmt: &mut Statement) {
+        match stmt {
+            Statement::VarDecl { ty, expr, .. } => {
+                self.ty(ty);
+                self.expr(expr);
+            }
+            Statement::Const(constant) => {
+                self.ty(&mut constant.ty);
+                self.expr(&mut constant.expr);
+            }
+            Statement::Return { expr, .. } | Statement::Expression(expr) => self.expr(expr),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.block(else_branch);
+                }
+            }
+            Statement::While { condition, body } => {
+                self.expr(condition);
+                self.block(body);
+            }
+            Statement::For { range, body, .. } => {
+                self.expr(range);
+                self.block(body);
+            }
+            Statement::Break(_) | Statement::Continue(_) | Statement::Unknown => {}
+        }
+    }
+
+    fn expr(&self, expr: &mut Expr) {
+        match expr {
+            Expr::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::Unary { rhs, .. } => self.expr(rhs),
+            Expr::Var(name) => self.name(name),
+            Expr::Grouping(expr) => self.expr(expr),
+            Expr::Call { callee, args } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Range { start, end, .. } => {
+                self.expr(start);
+                self.expr(end);
+            }
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::Index { object, index } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Expr::Path(path) => {
+                self.path(path);
+                // `math::sqrt` names a function or a constant, not an enum variant.
+                if path.len() == 1 {
+                    *expr = Expr::Var(path.remove(0));
+                }
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.pattern(&mut arm.pattern);
+                    match &mut arm.body {
+                        ArmBody::Expr(expr) => self.expr(expr),
+                        ArmBody::Block(block) => self.block(block),
+                    }
+                }
+            }
+            Expr::StructLiteral { name, fields } => {
+                self.name(name);
+                for field in fields {
+                    self.expr(&mut field.value);
+                }
+            }
+            Expr::Field { object, .. } => self.expr(object),
+            Expr::Assign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Expr::Literal(_) | Expr::None | Expr::Unknown => {}
+        }
+    }
+
+    fn pattern(&self, pattern: &mut Pattern) {
+        if let Pattern::Variant { path, fields } = pattern {
+            self.path(path);
+            for field in fields {
+                self.pattern(field);
+            }
+        }
+    }
+}
@@ -0,0 +1,876 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Component, Path},
+};
+
+use crate::{
+    ast::{
+        ArmBody, Ast, BinOp, Block, Class, Constant, Enum, Expr, FieldInit, Function, Import, Item,
+        MatchArm, Pattern, Statement, Struct, TypeExpr, UnaryOp,
+    },
+    builtins::Builtin,
+    compiler::Compiler,
+    diag,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    printer::Visitor,
+    types::Type,
+    utils::{FileId, Span, Token},
+};
+
+pub type ScopeId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Function,
+    Parameter,
+    Variable,
+    Constant,
+    Struct,
+    Class,
+    Enum,
+    Builtin,
+}
+
+/// A declaration that a name can bind to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    /// The span of the declaring identifier. Builtins use the default span.
+    pub span: Span,
+    /// Whether the binding was declared with `var mut` and can be assigned to.
+    pub mutable: bool,
+}
+
+#[derive(Debug)]
+pub struct Scope {
+    pub parent: Option<ScopeId>,
+    symbols: HashMap<String, Symbol>,
+    /// Names that are declared somewhere in this scope's block, used to tell
+    /// a use before declaration apart from an undefined name.
+    pending: HashMap<String, Span>,
+}
+
+impl Scope {
+    fn new(parent: Option<ScopeId>) -> Self {
+        Self {
+            parent,
+            symbols: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+}
+
+/// The result of name resolution: the scope tree of the program and, for every
+/// identifier that refers to a declaration, the symbol it binds to.
+#[derive(Debug, Default)]
+pub struct Resolutions {
+    pub scopes: Vec<Scope>,
+    bindings: HashMap<Span, Symbol>,
+    /// The name of every item declared in an imported module, qualified with the
+    /// path of the module, keyed by the span of its declaration and of every use.
+    /// A path such as `math::sqrt` is keyed by the span of the whole path.
+    qualified: HashMap<Span, String>,
+}
+
+impl Resolutions {
+    /// Returns the declaration that the identifier `ident` refers to.
+    pub fn get(&self, ident: &Token) -> Option<&Symbol> {
+        self.bindings.get(&ident.span)
+    }
+
+    /// Returns the spans of the declarations that are referred to at least once.
+    pub fn used_declarations(&self) -> HashSet<Span> {
+        self.bindings.values().map(|symbol| symbol.span).collect()
+    }
+
+    /// Returns the qualified name of the item declared or used at `span`, if it
+    /// belongs to an imported module.
+    pub fn qualified_name(&self, span: Span) -> Option<&str> {
+        self.qualified.get(&span).map(String::as_str)
+    }
+}
+
+/// The namespace of a file: the scope holding its items and the modules it imports.
+#[derive(Debug)]
+struct Module {
+    scope: ScopeId,
+    /// The imported files, by the names they are used with: the path written
+    /// after `use`, such as `geo::shapes`, and the file name, `shapes`.
+    imports: HashMap<String, ModuleName>,
+    /// The name that tells the file apart from every other file of the program.
+    path: String,
+}
+
+/// What a name for an imported module refers to.
+#[derive(Debug, Clone)]
+enum ModuleName {
+    /// A file, with how it was imported, such as `geo::shapes`.
+    File(FileId, String),
+    /// The file name of several imported files, as they were imported. They
+    /// can only be used by the path written after `use`.
+    Ambiguous(Vec<String>),
+}
+
+/// Resolves every name in the program to its declaration, reporting undefined
+/// names, duplicate definitions and uses before declaration. Every file is a
+/// module with its own namespace, whose `pub` items other files can use
+/// through paths such as `math::sqrt`.
+pub struct Resolver<'a> {
+    compiler: &'a Compiler,
+    resolutions: Resolutions,
+    modules: HashMap<FileId, Module>,
+    /// The declaration spans of the items that are declared `pub`.
+    public: HashSet<Span>,
+    /// The file whose items are being resolved.
+    file: FileId,
+    current: ScopeId,
+    loop_depth: usize,
+    /// The top-level constant whose initializer is being resolved, if any.
+    global_const: Option<Span>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(compiler: &'a Compiler) -> Self {
+        Self {
+            compiler,
+            resolutions: Resolutions::default(),
+            modules: HashMap::new(),
+            public: HashSet::new(),
+            file: 0,
+            current: 0,
+            loop_depth: 0,
+            global_const: None,
+        }
+    }
+
+    pub fn resolve(mut self, ast: &Ast) -> Resolutions {
+        self.enter_module(0);
+        // Items are visible in the whole file, regardless of declaration order.
+        for item in &ast.items {
+            match item {
+                Item::Func(function) => {
+                    self.declare_item(&function.name, SymbolKind::Function, function.public)
+                }
+                Item::Const(constant) => {
+                    self.declare_item(&constant.name, SymbolKind::Constant, constant.public)
+                }
+                Item::Struct(decl) => {
+                    self.declare_item(&decl.name, SymbolKind::Struct, decl.public)
+                }
+                Item::Class(class) => {
+                    self.declare_item(&class.name, SymbolKind::Class, class.public)
+                }
+                Item::Enum(decl) => self.declare_item(&decl.name, SymbolKind::Enum, decl.public),
+                Item::Import(import) => self.import(import),
+                Item::Attribute(_) | Item::Unknown => {}
+            }
+        }
+        ast.visit(&mut self);
+
+        self.resolutions
+    }
+
+    /// Makes the namespace of `file` the current scope, creating it on first use.
+    fn enter_module(&mut self, file: FileId) {
+        if !self.modules.contains_key(&file) {
+            self.resolutions.scopes.push(Scope::new(None));
+            let module = Module {
+                scope: self.resolutions.scopes.len() - 1,
+                imports: HashMap::new(),
+                path: self.module_path(file),
+            };
+            self.modules.insert(file, module);
+        }
+        self.file = file;
+        self.current = self.modules[&file].scope;
+    }
+
+    /// Returns the path of `file` from the directory of the main file, such as
+    /// `shapes::rectangle` for `shapes/rectangle.glacier`. Files outside of that
+    /// directory are named by their whole path. Unlike the file name, the path
+    /// is different for every file of the program.
+    fn module_path(&self, file: FileId) -> String {
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let path = canonical(self.compiler.get_module_filepath(file));
+        let root = match self.compiler.get_module_filepath(0).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => canonical(dir),
+            _ => canonical(Path::new(".")),
+        };
+        let path = path.strip_prefix(&root).unwrap_or(&path).with_extension("");
+        let parts: Vec<_> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => Some(part.to_string_lossy()),
+                _ => None,
+            })
+            .collect();
+        parts.join("::")
+    }
+
+    /// Returns the name that files importing `file` use for it: its file name
+    /// without the extension.
+    fn module_name(&self, file: FileId) -> String {
+        let path = self.compiler.get_module_filepath(file);
+        path.file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Declares a top-level item in the namespace of its file. Items of imported
+    /// modules are renamed to their qualified name, made from the path of their
+    /// file, so that items with the same name in different modules do not clash.
+    fn declare_item(&mut self, name: &Token, kind: SymbolKind, public: bool) {
+        let file = name.span.file_id;
+        self.enter_module(file);
+        self.declare(name, kind, false);
+        if public {
+            self.public.insert(name.span);
+        }
+        if file == 0 {
+            return;
+        }
+
+        let qualified = format!("{}::{}", self.modules[&file].path, name.ty);
+        self.resolutions.qualified.insert(name.span, qualified);
+    }
+
+    /// Makes the module loaded for `import` available to the importing file by
+    /// the path written after `use`, if any, and by the name of the imported
+    /// file. Files with the same name are only available by their path.
+    fn import(&mut self, import: &Import) {
+        let Some(module) = import.module else {
+            return;
+        };
+        let written = match &import.use_path {
+            Some(path) => path.clone(),
+            None => format!("\"{}\"", import.path.display()),
+        };
+        let name = self.module_name(module);
+        self.enter_module(import.span.file_id);
+        let imports = &mut self.modules.get_mut(&import.span.file_id).unwrap().imports;
+        if let Some(path) = &import.use_path {
+            imports.insert(path.clone(), ModuleName::File(module, written.clone()));
+        }
+        let entry = match imports.remove(&name) {
+            None => ModuleName::File(module, written),
+            Some(ModuleName::File(file, other)) if file == module => ModuleName::File(file, other),
+            Some(ModuleName::File(_, other)) => ModuleName::Ambiguous(vec![other, written]),
+            Some(ModuleName::Ambiguous(mut paths)) => {
+                paths.push(written);
+                ModuleName::Ambiguous(paths)
+            }
+        };
+        imports.insert(name, entry);
+    }
+
+    /// Returns the module that `path`, such as `geo::shapes`, names in the
+    /// current file, if any. A file name shared by several imported files is
+    /// reported at `span`.
+    fn module_named(&self, path: &str, span: Span) -> Option<FileId> {
+        match self.modules[&self.file].imports.get(path)? {
+            ModuleName::File(file, _) => Some(*file),
+            ModuleName::Ambiguous(paths) => {
+                let paths: Vec<String> = paths.iter().map(|path| format!("`{}`", path)).collect();
+                self.compiler.reporter.borrow_mut().add(
+                    diag!(
+                        format!("`{}` is the name of more than one imported module.", path),
+                        "It could be any of the modules with this file name.",
+                        span
+                    )
+                    .with_code(ErrorCode::UndefinedName)
+                    .with_note(format!(
+                        "The modules were imported as {}.",
+                        paths.join(", ")
+                    ))
+                    .with_help(
+                        "Use the path written after `use` instead, such as `geo::shapes::area`.",
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns how many of the leading `segments` name a module imported by the
+    /// current file, and the module, preferring the longest such path. A first
+    /// segment that is a declaration in scope is never a module.
+    fn imported_module(&self, segments: &[Token]) -> Option<(usize, Option<FileId>)> {
+        if self.is_declared(segments[0].as_ident()) {
+            return None;
+        }
+        let imports = &self.modules[&self.file].imports;
+        let mut path = String::new();
+        let mut found = None;
+        for (i, segment) in segments.iter().enumerate() {
+            if i > 0 {
+                path.push_str("::");
+            }
+            path.push_str(segment.as_ident());
+            if imports.contains_key(&path) {
+                found = Some((i + 1, path.clone()));
+            }
+        }
+        let (len, path) = found?;
+        let span = segments[0].span.to(segments[len - 1].span);
+        Some((len, self.module_named(&path, span)))
+    }
+
+    /// Returns whether `name` is declared in the current scope or one enclosing it.
+    fn is_declared(&self, name: &str) -> bool {
+        let mut scope_id = Some(self.current);
+        while let Some(id) = scope_id {
+            let scope = &self.resolutions.scopes[id];
+            if scope.symbols.contains_key(name) {
+                return true;
+            }
+            scope_id = scope.parent;
+        }
+        false
+    }
+
+    /// Resolves `item` among the items of the imported `module`, which the
+    /// current file calls `module_name`, binding the whole path at `span`. Only
+    /// `pub` items can be used from other modules.
+    fn lookup_in_module(&mut self, module: FileId, module_name: &str, item: &str, span: Span) {
+        let scope = self.modules[&module].scope;
+        let Some(symbol) = self.resolutions.scopes[scope].symbols.get(item).copied() else {
+            self.compiler.reporter.borrow_mut().add(
+                diag!(
+                    format!("`{}` is not defined in module `{}`.", item, module_name),
+                    "Not found in this module.",
+                    span
+                )
+                .with_code(ErrorCode::UndefinedName),
+            );
+            return;
+        };
+
+        if !self.public.contains(&symbol.span) {
+            self.compiler.reporter.borrow_mut().add(
+                diag!(
+                    format!("`{}` is private to module `{}`.", item, module_name),
+                    "Used here, outside of its module.",
+                    span
+                )
+                .with_code(ErrorCode::PrivateItem)
+                .with_label(
+                    symbol.span,
+                    format!("`{}` is declared here without `pub`.", item),
+                )
+                .with_help(format!(
+                    "Declare `{}` with `pub` to use it from other modules.",
+                    item
+                )),
+            );
+        }
+        self.bind(span, symbol);
+    }
+
+    /// Records that the name at `span` refers to `symbol`, along with the
+    /// qualified name of the item it refers to, if any.
+    fn bind(&mut self, span: Span, symbol: Symbol) {
+        self.resolutions.bindings.insert(span, symbol);
+        if let Some(qualified) = self.resolutions.qualified.get(&symbol.span).cloned() {
+            self.resolutions.qualified.insert(span, qualified);
+        }
+    }
+
+    /// Resolves the path of an enum variant, or of an item in another module
+    /// such as `geo::shapes::area`, whose module is named by all but one of its
+    /// segments or, for a variant, all but two.
+    fn resolve_path(&mut self, segments: &[Token]) {
+        if segments.len() == 1 {
+            return self.lookup(&segments[0]);
+        }
+        match self.imported_module(&segments[..segments.len() - 1]) {
+            Some((len, Some(module))) => {
+                let item = &segments[len];
+                let module_name: Vec<&str> = segments[..len].iter().map(Token::as_ident).collect();
+                let span = segments[0].span.to(item.span);
+                self.lookup_in_module(module, &module_name.join("::"), item.as_ident(), span)
+            }
+            Some((_, None)) => {}
+            // Only an item of another module has a path of more than two segments.
+            None if segments.len() > 2 && !self.is_declared(segments[0].as_ident()) => {
+                let module = &segments[..segments.len() - 1];
+                let name: Vec<&str> = module.iter().map(Token::as_ident).collect();
+                self.compiler.reporter.borrow_mut().add(
+                    diag!(
+                        format!("Undefined module `{}`.", name.join("::")),
+                        "No module with this name is imported in this file.",
+                        module[0].span.to(module[module.len() - 1].span)
+                    )
+                    .with_code(ErrorCode::UndefinedName)
+                    .with_help("Import it with `use`, and use it by the path written after `use`."),
+                );
+            }
+            None => self.lookup(&segments[0]),
+        }
+    }
+
+    /// Binds the names of structs, classes and enums in a type. Names that are
+    /// not types are reported by the type checker.
+    fn resolve_type(&mut self, ty: &TypeExpr) {
+        match ty {
+            TypeExpr::Named(name) if name.as_ident().contains("::") => self.lookup(name),
+            TypeExpr::Named(name) if Type::from_name(name.as_ident()).is_none() => {
+                let scope = self.modules[&self.file].scope;
+                let symbol = self.resolutions.scopes[scope].symbols.get(name.as_ident());
+                if let Some(symbol) = symbol.copied() {
+                    self.bind(name.span, symbol);
+                }
+            }
+            TypeExpr::Named(_) => {}
+            TypeExpr::Array { element, .. } => self.resolve_type(element),
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.resolutions.scopes.push(Scope::new(Some(self.current)));
+        self.current = self.resolutions.scopes.len() - 1;
+    }
+
+    fn end_scope(&mut self) {
+        self.current = self.resolutions.scopes[self.current].parent.unwrap();
+    }
+
+    fn declare(&mut self, name: &Token, kind: SymbolKind, mutable: bool) {
+        let scope = &mut self.resolutions.scopes[self.current];
+        if let Some(previous) = scope.symbols.get(name.as_ident()) {
+            self.compiler.reporter.borrow_mut().add(
+                diag!(
+                    format!("Duplicate definition of `{}`.", name.ty),
+                    format!("`{}` is already defined in this scope.", name.ty),
+                    name.span
+                )
+                .with_code(ErrorCode::DuplicateDefinition)
+                .with_label(previous.span, "The first definition is here."),
+            );
+            return;
+        }
+
+        scope.symbols.insert(
+            name.as_ident().to_string(),
+            Symbol {
+                kind,
+                span: name.span,
+                mutable,
+            },
+        );
+    }
+
+    /// Reports the first part of a constant's initializer that cannot be evaluated at compile time.
+    fn check_const_expr(&mut self, expr: &Expr) -> bool {
+        let reason = match expr {
+            Expr::Literal(_) => return true,
+            Expr::Var(name) => match self.resolutions.get(name) {
+                Some(symbol) if symbol.kind == SymbolKind::Constant => return true,
+                Some(_) => format!("`{}` is not a constant.", name.ty),
+                None => return false,
+            },
+            Expr::Unary { rhs, .. } => return self.check_const_expr(rhs),
+            Expr::Binary { lhs, rhs, .. } => {
+                return self.check_const_expr(lhs) && self.check_const_expr(rhs)
+            }
+            Expr::Grouping(expr) => return self.check_const_expr(expr),
+            Expr::StructLiteral { fields, .. } => {
+                return fields
+                    .iter()
+                    .all(|field| self.check_const_expr(&field.value))
+            }
+            Expr::Field { object, .. } => return self.check_const_expr(object),
+            Expr::Path(_) => return true,
+            Expr::Array { elements, .. } => {
+                return elements
+                    .iter()
+                    .all(|element| self.check_const_expr(element))
+            }
+            Expr::Index { object, index } => {
+                return self.check_const_expr(object) && self.check_const_expr(index)
+            }
+            Expr::Call { .. } => {
+                String::from("Function calls cannot be evaluated at compile time.")
+            }
+            _ => String::from("Only literals, operators and other constants are allowed."),
+        };
+
+        self.compiler.reporter.borrow_mut().add(
+            diag!(
+                "Constant initializer is not a constant expression.",
+                reason,
+                expr.span()
+            )
+            .with_code(ErrorCode::NonConstantInitializer),
+        );
+        false
+    }
+
+    /// Checks assignments to plain names. Field assignments depend on the type of
+    /// the object and are checked by the type checker.
+    fn check_assign_target(&mut self, target: &Expr) {
+        let Expr::Var(name) = target else {
+            return;
+        };
+        let Some(symbol) = self.resolutions.get(name).copied() else {
+            return;
+        };
+
+        let (message, secondary_msg) = match symbol.kind {
+            SymbolKind::Variable if symbol.mutable => return,
+            SymbolKind::Variable => (
+                format!("Cannot assign twice to immutable variable `{}`.", name.ty),
+                format!("Declare it with `var mut {}` to make it mutable.", name.ty),
+            ),
+            SymbolKind::Parameter => (
+                format!("Cannot assign to parameter `{}`.", name.ty),
+                String::from("Parameters are immutable."),
+            ),
+            SymbolKind::Constant => (
+                format!("Cannot assign to constant `{}`.", name.ty),
+                String::from("Constants cannot change after they are declared."),
+            ),
+            SymbolKind::Function | SymbolKind::Builtin => (
+                format!("Cannot assign to function `{}`.", name.ty),
+                String::from("Only variables can be assigned to."),
+            ),
+            SymbolKind::Struct | SymbolKind::Class | SymbolKind::Enum => (
+                format!("Cannot assign to type `{}`.", name.ty),
+                String::from("Only variables can be assigned to."),
+            ),
+        };
+        self.compiler
+            .reporter
+            .borrow_mut()
+            .add(diag!(message, secondary_msg, name.span).with_code(ErrorCode::AssignToImmutable));
+    }
+
+    /// Declares the names bound by `pattern` in the current scope.
+    fn resolve_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding(name) => self.declare(name, SymbolKind::Variable, false),
+            Pattern::Variant { path, fields } => {
+                self.resolve_path(path);
+                for field in fields {
+                    self.resolve_pattern(field);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+        }
+    }
+
+    fn check_in_loop(&mut self, keyword: &Token) {
+        if self.loop_depth == 0 {
+            self.compiler.reporter.borrow_mut().add(
+                diag!(
+                    format!("`{}` outside of a loop.", keyword.ty),
+                    format!(
+                        "`{}` can only be used inside `while` or `for` loops.",
+                        keyword.ty
+                    ),
+                    keyword.span
+                )
+                .with_code(ErrorCode::OutsideLoop),
+            );
+        }
+    }
+
+    fn lookup(&mut self, name: &Token) {
+        // Qualified names in types and struct literals are a single identifier.
+        if let Some((module, item)) = name.as_ident().rsplit_once("::") {
+            match self.modules[&self.file].imports.contains_key(module) {
+                true => {
+                    if let Some(file) = self.module_named(module, name.span) {
+                        self.lookup_in_module(file, module, item, name.span);
+                    }
+                }
+                false => self.compiler.reporter.borrow_mut().add(
+                    diag!(
+                        format!("Undefined module `{}`.", module),
+                        "No module with this name is imported in this file.",
+                        name.span
+                    )
+                    .with_code(ErrorCode::UndefinedName),
+                ),
+            }
+            return;
+        }
+
+        let mut scope_id = Some(self.current);
+        while let Some(id) = scope_id {
+            let scope = &self.resolutions.scopes[id];
+            if let Some(symbol) = scope.symbols.get(name.as_ident()) {
+                let symbol = *symbol;
+                self.bind(name.span, symbol);
+                // Top-level constants are evaluated in order, so they may only refer to earlier ones.
+                if let Some(constant) = self.global_const {
+                    if symbol.kind == SymbolKind::Constant && symbol.span.start >= constant.start {
+                        self.compiler.reporter.borrow_mut().add(
+                            diag!(
+                                format!("Use of `{}` before its declaration.", name.ty),
+                                format!("`{}` is declared later in this file.", name.ty),
+                                name.span
+                            )
+                            .with_code(ErrorCode::UseBeforeDeclaration),
+                        );
+                    }
+                }
+                return;
+            }
+            scope_id = scope.parent;
+        }
+
+        if Builtin::from_name(name.as_ident()).is_some() {
+            let symbol = Symbol {
+                kind: SymbolKind::Builtin,
+                span: Span::default(),
+                mutable: false,
+            };
+            self.resolutions.bindings.insert(name.span, symbol);
+            return;
+        }
+
+        let mut scope_id = Some(self.current);
+        while let Some(id) = scope_id {
+            let scope = &self.resolutions.scopes[id];
+            if let Some(decl) = scope.pending.get(name.as_ident()) {
+                if decl.start > name.span.start {
+                    self.compiler.reporter.borrow_mut().add(
+                        diag!(
+                            format!("Use of `{}` before its declaration.", name.ty),
+                            format!("`{}` is declared later in this block.", name.ty),
+                            name.span
+                        )
+                        .with_code(ErrorCode::UseBeforeDeclaration),
+                    );
+                    return;
+                }
+            }
+            scope_id = scope.parent;
+        }
+
+        self.compiler.reporter.borrow_mut().add(
+            diag!(
+                format!("Undefined variable `{}`.", name.ty),
+                "Not found in this scope.",
+                name.span
+            )
+            .with_code(ErrorCode::UndefinedName),
+        );
+    }
+}
+
+impl Visitor for Resolver<'_> {
+    fn visit_item(&mut self, item: &Item) {
+        let name = match item {
+            Item::Func(function) => &function.name,
+            Item::Const(constant) => &constant.name,
+            Item::Struct(decl) => &decl.name,
+            Item::Class(class) => &class.name,
+            Item::Enum(decl) => &decl.name,
+            Item::Import(_) | Item::Attribute(_) | Item::Unknown => return,
+        };
+        self.enter_module(name.span.file_id);
+        self.do_visit_item(item);
+    }
+
+    fn visit_func(&mut self, function: &Function) {
+        for param in &function.params {
+            self.resolve_type(&param.ty);
+        }
+        if let Some(return_ty) = &function.return_ty {
+            self.resolve_type(return_ty);
+        }
+        self.begin_scope();
+        if let Some(receiver) = &function.self_param {
+            self.declare(receiver, SymbolKind::Parameter, false);
+        }
+        for param in &function.params {
+            self.declare(&param.name, SymbolKind::Parameter, false);
+        }
+        self.visit_block(&function.body);
+        self.end_scope();
+    }
+
+    fn visit_struct(&mut self, decl: &Struct) {
+        for field in &decl.fields {
+            self.resolve_type(&field.ty);
+        }
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        for field in &class.fields {
+            self.resolve_type(&field.ty);
+        }
+        for method in &class.methods {
+            self.visit_func(method);
+        }
+    }
+
+    fn visit_enum(&mut self, decl: &Enum) {
+        for variant in &decl.variants {
+            for ty in &variant.fields {
+                self.resolve_type(ty);
+            }
+        }
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        self.begin_scope();
+        for stmt in &block.0 {
+            let name = match stmt {
+                Statement::VarDecl { name, .. } => name,
+                Statement::Const(constant) => &constant.name,
+                _ => continue,
+            };
+            self.resolutions.scopes[self.current]
+                .pending
+                .entry(name.as_ident().to_string())
+                .or_insert(name.span);
+        }
+        for stmt in &block.0 {
+            self.visit_stmt(stmt);
+        }
+        self.end_scope();
+    }
+
+    fn visit_var_decl(&mut self, name: &Token, mutable: bool, ty: &Option<TypeExpr>, expr: &Expr) {
+        if let Some(ty) = ty {
+            self.resolve_type(ty);
+        }
+        // The initializer is resolved first, so `var x = x;` refers to an outer `x`.
+        self.visit_expr(expr);
+        self.declare(name, SymbolKind::Variable, mutable);
+    }
+
+    fn visit_const(&mut self, constant: &Constant) {
+        if let Some(ty) = &constant.ty {
+            self.resolve_type(ty);
+        }
+        if self.resolutions.scopes[self.current].parent.is_none() {
+            // Top-level constants were already declared along with the other items.
+            self.global_const = Some(constant.name.span);
+            self.visit_expr(&constant.expr);
+            self.global_const = None;
+            self.check_const_expr(&constant.expr);
+        } else {
+            self.visit_expr(&constant.expr);
+            self.check_const_expr(&constant.expr);
+            self.declare(&constant.name, SymbolKind::Constant, false);
+        }
+    }
+
+    fn visit_return(&mut self, expr: &Expr) {
+        self.visit_expr(expr);
+    }
+
+    fn visit_if(&mut self, condition: &Expr, then_branch: &Block, else_branch: &Option<Block>) {
+        self.visit_expr(condition);
+        self.visit_block(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.visit_block(else_branch);
+        }
+    }
+
+    fn visit_while(&mut self, condition: &Expr, body: &Block) {
+        self.visit_expr(condition);
+        self.loop_depth += 1;
+        self.visit_block(body);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_for(&mut self, var: &Token, range: &Expr, body: &Block) {
+        self.visit_expr(range);
+        self.begin_scope();
+        self.declare(var, SymbolKind::Variable, false);
+        self.loop_depth += 1;
+        self.visit_block(body);
+        self.loop_depth -= 1;
+        self.end_scope();
+    }
+
+    fn visit_break(&mut self, keyword: &Token) {
+        self.check_in_loop(keyword);
+    }
+
+    fn visit_continue(&mut self, keyword: &Token) {
+        self.check_in_loop(keyword);
+    }
+
+    fn visit_binary_expr(&mut self, lhs: &Expr, _op: &BinOp, rhs: &Expr) {
+        self.visit_expr(lhs);
+        self.visit_expr(rhs);
+    }
+
+    fn visit_unary_expr(&mut self, _op: &UnaryOp, rhs: &Expr) {
+        self.visit_expr(rhs);
+    }
+
+    fn visit_grouping(&mut self, expr: &Expr) {
+        self.visit_expr(expr);
+    }
+
+    fn visit_ident(&mut self, ident: &Token) {
+        self.lookup(ident);
+    }
+
+    fn visit_literal(&mut self, _literal: &Token) {}
+
+    fn visit_call(&mut self, callee: &Expr, args: &[Expr]) {
+        self.visit_expr(callee);
+        for arg in args {
+            self.visit_expr(arg);
+        }
+    }
+
+    fn visit_range(&mut self, start: &Expr, end: &Expr, _inclusive: bool) {
+        self.visit_expr(start);
+        self.visit_expr(end);
+    }
+
+    fn visit_array(&mut self, elements: &[Expr]) {
+        for element in elements {
+            self.visit_expr(element);
+        }
+    }
+
+    fn visit_index(&mut self, object: &Expr, index: &Expr) {
+        self.visit_expr(object);
+        self.visit_expr(index);
+    }
+
+    fn visit_path(&mut self, segments: &[Token]) {
+        self.resolve_path(segments);
+    }
+
+    fn visit_match(&mut self, scrutinee: &Expr, arms: &[MatchArm]) {
+        self.visit_expr(scrutinee);
+        for arm in arms {
+            self.begin_scope();
+            self.resolve_pattern(&arm.pattern);
+            match &arm.body {
+                ArmBody::Expr(expr) => self.visit_expr(expr),
+                ArmBody::Block(block) => self.visit_block(block),
+            }
+            self.end_scope();
+        }
+    }
+
+    fn visit_struct_literal(&mut self, name: &Token, fields: &[FieldInit]) {
+        self.lookup(name);
+        for field in fields {
+            self.visit_expr(&field.value);
+        }
+    }
+
+    fn visit_field(&mut self, object: &Expr, _field: &Token) {
+        self.visit_expr(object);
+    }
+
+    fn visit_assign(&mut self, target: &Expr, _op: &Option<BinOp>, value: &Expr) {
+        self.visit_expr(value);
+        self.visit_expr(target);
+        self.check_assign_target(target);
+    }
+}
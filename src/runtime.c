@@ -0,0 +1,649 @@
+/* The runtime linked into every program compiled to C. Values are tagged and
+ * the heap allocated ones are reference counted. Arrays and structs are
+ * values: they are shared between copies and cloned before one is written to.
+ * Class instances are objects shared by reference.
+ *
+ * Unless stated otherwise, functions take ownership of the `Value`s passed to
+ * them and return a value owned by the caller. */
+
+#include <inttypes.h>
+#include <math.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef enum {
+    GL_UNIT,
+    GL_INT,
+    GL_FLOAT,
+    GL_BOOL,
+    GL_CHAR,
+    GL_STRING,
+    GL_ARRAY,
+    GL_STRUCT,
+    GL_ENUM,
+    GL_OBJECT
+} GlTag;
+
+typedef struct { size_t rc; } GlHeap;
+
+typedef struct {
+    GlTag tag;
+    union {
+        int64_t i;
+        double f;
+        bool b;
+        uint32_t c;
+        GlHeap *h;
+    } as;
+} Value;
+
+/* A struct or class: its name and field names. Field names are compared by address. */
+typedef struct {
+    const char *name;
+    size_t count;
+    const char *const *fields;
+} GlType;
+
+typedef struct {
+    const char *enum_name;
+    const char *name;
+} GlVariant;
+
+typedef struct { GlHeap head; size_t len; char data[1]; } GlString;
+typedef struct { GlHeap head; size_t len; Value *items; } GlArray;
+/* The fields of a struct value or a class instance. */
+typedef struct { GlHeap head; const GlType *type; Value fields[1]; } GlRecord;
+typedef struct { GlHeap head; const GlVariant *variant; size_t len; Value fields[1]; } GlEnum;
+
+enum { GL_ADD, GL_SUB, GL_MUL, GL_DIV, GL_EQ, GL_NE, GL_LT, GL_LE, GL_GT, GL_GE };
+
+static const char *const gl_op_names[] = {"+", "-", "*", "/", "==", "!=", "<", "<=", ">", ">="};
+
+/* Reports a runtime error the way the compiler reports diagnostics and stops the
+ * program. `code` is the error code, or NULL, and `location` is the file, line
+ * and underlined source of the error, which `detail` follows. */
+static void gl_fail(const char *code, const char *message, const char *detail,
+                    const char *location) {
+    fflush(stdout);
+    if (code != NULL) {
+        fprintf(stderr, "Error[%s]: %s\n", code, message);
+    } else {
+        fprintf(stderr, "Error: %s\n", message);
+    }
+    fprintf(stderr, "%s %s\n", location, detail != NULL ? detail : "");
+    fputs("1 error has been emitted.\n", stderr);
+    if (code != NULL) {
+        fprintf(stderr, "For more information about an error, try `olive --explain %s`.\n", code);
+    }
+    exit(1);
+}
+
+/* The number of calls in progress, which the prologue of every function counts,
+ * and the location of the latest call, where a stack overflow is reported. */
+static int64_t gl_depth = 0;
+static const char *gl_call_site = NULL;
+
+static void *gl_alloc(size_t size) {
+    void *memory = malloc(size);
+    if (memory == NULL) {
+        fputs("Error: Out of memory.\n", stderr);
+        exit(1);
+    }
+    return memory;
+}
+
+static Value gl_unit(void) { Value v; v.tag = GL_UNIT; v.as.i = 0; return v; }
+static Value gl_int(int64_t i) { Value v; v.tag = GL_INT; v.as.i = i; return v; }
+static Value gl_bool(bool b) { Value v; v.tag = GL_BOOL; v.as.b = b; return v; }
+static Value gl_char(uint32_t c) { Value v; v.tag = GL_CHAR; v.as.c = c; return v; }
+static Value gl_float(double f) { Value v; v.tag = GL_FLOAT; v.as.f = f; return v; }
+static Value gl_heap(GlTag tag, void *h) { Value v; v.tag = tag; v.as.h = (GlHeap *)h; return v; }
+
+/* Builds a float from its bits, so that constants are exact. */
+static Value gl_float_bits(uint64_t bits) {
+    double f;
+    memcpy(&f, &bits, sizeof f);
+    return gl_float(f);
+}
+
+static Value gl_retain(Value v) {
+    if (v.tag >= GL_STRING) {
+        v.as.h->rc++;
+    }
+    return v;
+}
+
+static void gl_release(Value v) {
+    size_t i;
+    if (v.tag < GL_STRING || --v.as.h->rc > 0) {
+        return;
+    }
+    switch (v.tag) {
+    case GL_ARRAY: {
+        GlArray *array = (GlArray *)v.as.h;
+        for (i = 0; i < array->len; i++) gl_release(array->items[i]);
+        free(array->items);
+        break;
+    }
+    case GL_STRUCT:
+    case GL_OBJECT: {
+        GlRecord *record = (GlRecord *)v.as.h;
+        for (i = 0; i < record->type->count; i++) gl_release(record->fields[i]);
+        break;
+    }
+    case GL_ENUM: {
+        GlEnum *e = (GlEnum *)v.as.h;
+        for (i = 0; i < e->len; i++) gl_release(e->fields[i]);
+        break;
+    }
+    default:
+        break;
+    }
+    free(v.as.h);
+}
+
+/* Stores `value` in `slot`, releasing what it held before. */
+static void gl_set(Value *slot, Value value) {
+    gl_release(*slot);
+    *slot = value;
+}
+
+static Value gl_string(const char *data, size_t len) {
+    GlString *s = (GlString *)gl_alloc(sizeof(GlString) + len);
+    s->head.rc = 1;
+    s->len = len;
+    memcpy(s->data, data, len);
+    s->data[len] = '\0';
+    return gl_heap(GL_STRING, s);
+}
+
+static Value gl_array(size_t len, const Value *items) {
+    GlArray *array = (GlArray *)gl_alloc(sizeof(GlArray));
+    array->head.rc = 1;
+    array->len = len;
+    array->items = (Value *)gl_alloc(sizeof(Value) * (len > 0 ? len : 1));
+    if (len > 0) memcpy(array->items, items, sizeof(Value) * len);
+    return gl_heap(GL_ARRAY, array);
+}
+
+static GlRecord *gl_record(const GlType *type, const Value *fields) {
+    size_t count = type->count;
+    GlRecord *record = (GlRecord *)gl_alloc(sizeof(GlRecord) + sizeof(Value) * count);
+    record->head.rc = 1;
+    record->type = type;
+    if (count > 0) memcpy(record->fields, fields, sizeof(Value) * count);
+    return record;
+}
+
+static Value gl_struct(const GlType *type, const Value *fields) {
+    return gl_heap(GL_STRUCT, gl_record(type, fields));
+}
+
+/* Creates a class instance whose fields are copied from the struct `fields`. */
+static Value gl_new(Value fields) {
+    GlRecord *from = (GlRecord *)fields.as.h;
+    GlRecord *record = gl_record(from->type, from->fields);
+    size_t i;
+    for (i = 0; i < from->type->count; i++) gl_retain(record->fields[i]);
+    gl_release(fields);
+    return gl_heap(GL_OBJECT, record);
+}
+
+static Value gl_enum(const GlVariant *variant, size_t len, const Value *fields) {
+    GlEnum *e = (GlEnum *)gl_alloc(sizeof(GlEnum) + sizeof(Value) * len);
+    e->head.rc = 1;
+    e->variant = variant;
+    e->len = len;
+    if (len > 0) memcpy(e->fields, fields, sizeof(Value) * len);
+    return gl_heap(GL_ENUM, e);
+}
+
+static const char *gl_type_name(const Value *v) {
+    switch (v->tag) {
+    case GL_UNIT: return "()";
+    case GL_INT: return "int";
+    case GL_FLOAT: return "float";
+    case GL_BOOL: return "bool";
+    case GL_CHAR: return "char";
+    case GL_STRING: return "string";
+    case GL_ARRAY: return "array";
+    case GL_STRUCT:
+    case GL_OBJECT: return ((GlRecord *)v->as.h)->type->name;
+    case GL_ENUM: return ((GlEnum *)v->as.h)->variant->enum_name;
+    }
+    return "()";
+}
+
+/* A growable buffer used to format values. */
+typedef struct { char *data; size_t len, cap; } GlBuf;
+
+static void gl_buf_push(GlBuf *buf, const char *data, size_t len) {
+    if (buf->len + len > buf->cap) {
+        char *grown;
+        buf->cap = (buf->len + len) * 2 + 16;
+        grown = (char *)realloc(buf->data, buf->cap);
+        if (grown == NULL) {
+            fputs("Error: Out of memory.\n", stderr);
+            exit(1);
+        }
+        buf->data = grown;
+    }
+    memcpy(buf->data + buf->len, data, len);
+    buf->len += len;
+}
+
+static void gl_buf_str(GlBuf *buf, const char *s) { gl_buf_push(buf, s, strlen(s)); }
+
+static void gl_buf_char(GlBuf *buf, uint32_t c) {
+    char utf8[4];
+    size_t len;
+    if (c < 0x80) {
+        utf8[0] = (char)c;
+        len = 1;
+    } else if (c < 0x800) {
+        utf8[0] = (char)(0xC0 | (c >> 6));
+        utf8[1] = (char)(0x80 | (c & 0x3F));
+        len = 2;
+    } else if (c < 0x10000) {
+        utf8[0] = (char)(0xE0 | (c >> 12));
+        utf8[1] = (char)(0x80 | ((c >> 6) & 0x3F));
+        utf8[2] = (char)(0x80 | (c & 0x3F));
+        len = 3;
+    } else {
+        utf8[0] = (char)(0xF0 | (c >> 18));
+        utf8[1] = (char)(0x80 | ((c >> 12) & 0x3F));
+        utf8[2] = (char)(0x80 | ((c >> 6) & 0x3F));
+        utf8[3] = (char)(0x80 | (c & 0x3F));
+        len = 4;
+    }
+    gl_buf_push(buf, utf8, len);
+}
+
+static void gl_buf_zeros(GlBuf *buf, int count) {
+    for (; count > 0; count--) gl_buf_push(buf, "0", 1);
+}
+
+/* Formats a float like the interpreter: the shortest digits that round-trip,
+ * in plain notation for exponents in -5..17 and in scientific notation otherwise. */
+static void gl_buf_float(GlBuf *buf, double f) {
+    char sci[40], digits[24];
+    int precision, exp, len = 0;
+    const char *p;
+    if (isnan(f)) {
+        gl_buf_str(buf, "NaN");
+        return;
+    } else if (isinf(f)) {
+        gl_buf_str(buf, f > 0 ? "inf" : "-inf");
+        return;
+    }
+
+    for (precision = 0; precision < 17; precision++) {
+        snprintf(sci, sizeof sci, "%.*e", precision, f);
+        if (strtod(sci, NULL) == f) break;
+    }
+    for (p = sci; *p != 'e'; p++) {
+        if (*p >= '0' && *p <= '9') digits[len++] = *p;
+    }
+    digits[len] = '\0';
+    exp = atoi(p + 1);
+    if (sci[0] == '-') gl_buf_push(buf, "-", 1);
+
+    if (exp >= -5 && exp < 17) {
+        if (exp < 0) {
+            gl_buf_str(buf, "0.");
+            gl_buf_zeros(buf, -exp - 1);
+            gl_buf_push(buf, digits, (size_t)len);
+        } else if (len > exp + 1) {
+            gl_buf_push(buf, digits, (size_t)exp + 1);
+            gl_buf_push(buf, ".", 1);
+            gl_buf_push(buf, digits + exp + 1, (size_t)(len - exp - 1));
+        } else {
+            gl_buf_push(buf, digits, (size_t)len);
+            gl_buf_zeros(buf, exp + 1 - len);
+            gl_buf_str(buf, ".0");
+        }
+    } else {
+        char exponent[16];
+        gl_buf_push(buf, digits, 1);
+        if (len > 1) {
+            gl_buf_push(buf, ".", 1);
+            gl_buf_push(buf, digits + 1, (size_t)len - 1);
+        }
+        snprintf(exponent, sizeof exponent, "e%d", exp);
+        gl_buf_str(buf, exponent);
+    }
+}
+
+static void gl_buf_value(GlBuf *buf, const Value *v) {
+    char number[32];
+    size_t i;
+    switch (v->tag) {
+    case GL_UNIT:
+        gl_buf_str(buf, "()");
+        break;
+    case GL_INT:
+        snprintf(number, sizeof number, "%" PRId64, v->as.i);
+        gl_buf_str(buf, number);
+        break;
+    case GL_FLOAT:
+        gl_buf_float(buf, v->as.f);
+        break;
+    case GL_BOOL:
+        gl_buf_str(buf, v->as.b ? "true" : "false");
+        break;
+    case GL_CHAR:
+        gl_buf_char(buf, v->as.c);
+        break;
+    case GL_STRING: {
+        GlString *s = (GlString *)v->as.h;
+        gl_buf_push(buf, s->data, s->len);
+        break;
+    }
+    case GL_ARRAY: {
+        GlArray *array = (GlArray *)v->as.h;
+        gl_buf_str(buf, "[");
+        for (i = 0; i < array->len; i++) {
+            if (i > 0) gl_buf_str(buf, ", ");
+            gl_buf_value(buf, &array->items[i]);
+        }
+        gl_buf_str(buf, "]");
+        break;
+    }
+    case GL_STRUCT:
+    case GL_OBJECT: {
+        GlRecord *record = (GlRecord *)v->as.h;
+        gl_buf_str(buf, record->type->name);
+        gl_buf_str(buf, " {");
+        for (i = 0; i < record->type->count; i++) {
+            gl_buf_str(buf, i == 0 ? " " : ", ");
+            gl_buf_str(buf, record->type->fields[i]);
+            gl_buf_str(buf, ": ");
+            gl_buf_value(buf, &record->fields[i]);
+        }
+        gl_buf_str(buf, record->type->count == 0 ? "}" : " }");
+        break;
+    }
+    case GL_ENUM: {
+        GlEnum *e = (GlEnum *)v->as.h;
+        gl_buf_str(buf, e->variant->enum_name);
+        gl_buf_str(buf, "::");
+        gl_buf_str(buf, e->variant->name);
+        if (e->len > 0) {
+            gl_buf_str(buf, "(");
+            for (i = 0; i < e->len; i++) {
+                if (i > 0) gl_buf_str(buf, ", ");
+                gl_buf_value(buf, &e->fields[i]);
+            }
+            gl_buf_str(buf, ")");
+        }
+        break;
+    }
+    }
+}
+
+static Value gl_println(size_t count, Value *args) {
+    GlBuf buf = {NULL, 0, 0};
+    size_t i;
+    for (i = 0; i < count; i++) {
+        if (i > 0) gl_buf_push(&buf, " ", 1);
+        gl_buf_value(&buf, &args[i]);
+        gl_release(args[i]);
+    }
+    gl_buf_push(&buf, "\n", 1);
+    fwrite(buf.data, 1, buf.len, stdout);
+    free(buf.data);
+    return gl_unit();
+}
+
+static Value gl_len(Value v) {
+    int64_t len = 0;
+    if (v.tag == GL_ARRAY) {
+        len = (int64_t)((GlArray *)v.as.h)->len;
+    } else if (v.tag == GL_STRING) {
+        GlString *s = (GlString *)v.as.h;
+        size_t i;
+        for (i = 0; i < s->len; i++) {
+            if ((s->data[i] & 0xC0) != 0x80) len++;
+        }
+    }
+    gl_release(v);
+    return gl_int(len);
+}
+
+static bool gl_compare(int op, int order) {
+    switch (op) {
+    case GL_EQ: return order == 0;
+    case GL_NE: return order != 0;
+    case GL_LT: return order < 0;
+    case GL_LE: return order <= 0;
+    case GL_GT: return order > 0;
+    default: return order >= 0;
+    }
+}
+
+static bool gl_compare_floats(int op, double a, double b) {
+    switch (op) {
+    case GL_EQ: return a == b;
+    case GL_NE: return a != b;
+    case GL_LT: return a < b;
+    case GL_LE: return a <= b;
+    case GL_GT: return a > b;
+    default: return a >= b;
+    }
+}
+
+static void gl_binary_error(int op, const Value *a, const Value *b, const char *location) {
+    char message[256];
+    snprintf(message, sizeof message,
+             "Cannot apply binary operator `%s` to types `%s` and `%s`.",
+             gl_op_names[op], gl_type_name(a), gl_type_name(b));
+    gl_fail("E0019", message, NULL, location);
+}
+
+/* Applies a binary operator: integers wrap on overflow and dividing an integer by zero is an error. */
+static Value gl_binary(int op, Value a, Value b, const char *location) {
+    Value result = gl_unit();
+    if (a.tag == GL_INT && b.tag == GL_INT) {
+        uint64_t x = (uint64_t)a.as.i, y = (uint64_t)b.as.i;
+        switch (op) {
+        case GL_ADD: return gl_int((int64_t)(x + y));
+        case GL_SUB: return gl_int((int64_t)(x - y));
+        case GL_MUL: return gl_int((int64_t)(x * y));
+        case GL_DIV:
+            if (b.as.i == 0) {
+                gl_fail("E0035", "Attempt to divide by zero.",
+                        "The divisor of this expression is zero.", location);
+            }
+            if (a.as.i == INT64_MIN && b.as.i == -1) return a;
+            return gl_int(a.as.i / b.as.i);
+        default:
+            return gl_bool(gl_compare(op, (a.as.i > b.as.i) - (a.as.i < b.as.i)));
+        }
+    } else if (a.tag == GL_FLOAT && b.tag == GL_FLOAT) {
+        switch (op) {
+        case GL_ADD: return gl_float(a.as.f + b.as.f);
+        case GL_SUB: return gl_float(a.as.f - b.as.f);
+        case GL_MUL: return gl_float(a.as.f * b.as.f);
+        case GL_DIV: return gl_float(a.as.f / b.as.f);
+        default: return gl_bool(gl_compare_floats(op, a.as.f, b.as.f));
+        }
+    } else if (a.tag == GL_STRING && b.tag == GL_STRING && op != GL_SUB && op != GL_MUL &&
+               op != GL_DIV) {
+        GlString *x = (GlString *)a.as.h, *y = (GlString *)b.as.h;
+        if (op == GL_ADD) {
+            GlString *s = (GlString *)gl_alloc(sizeof(GlString) + x->len + y->len);
+            s->head.rc = 1;
+            s->len = x->len + y->len;
+            memcpy(s->data, x->data, x->len);
+            memcpy(s->data + x->len, y->data, y->len);
+            s->data[s->len] = '\0';
+            result = gl_heap(GL_STRING, s);
+        } else {
+            size_t shorter = x->len < y->len ? x->len : y->len;
+            int order = memcmp(x->data, y->data, shorter);
+            if (order == 0) order = (x->len > y->len) - (x->len < y->len);
+            result = gl_bool(gl_compare(op, order));
+        }
+    } else if (a.tag == GL_CHAR && b.tag == GL_CHAR && op >= GL_EQ) {
+        return gl_bool(gl_compare(op, (a.as.c > b.as.c) - (a.as.c < b.as.c)));
+    } else if (a.tag == GL_BOOL && b.tag == GL_BOOL && (op == GL_EQ || op == GL_NE)) {
+        return gl_bool(gl_compare(op, a.as.b != b.as.b));
+    } else {
+        gl_binary_error(op, &a, &b, location);
+    }
+    gl_release(a);
+    gl_release(b);
+    return result;
+}
+
+static void gl_unary_error(const char *op, const Value *v, const char *location) {
+    char message[256];
+    snprintf(message, sizeof message, "Cannot apply unary operator `%s` to type `%s`.", op,
+             gl_type_name(v));
+    gl_fail("E0019", message, NULL, location);
+}
+
+static Value gl_negative(Value v, const char *location) {
+    if (v.tag == GL_INT) return gl_int((int64_t)(0 - (uint64_t)v.as.i));
+    if (v.tag == GL_FLOAT) return gl_float(-v.as.f);
+    gl_unary_error("-", &v, location);
+    return v;
+}
+
+static Value gl_not(Value v, const char *location) {
+    if (v.tag == GL_BOOL) return gl_bool(!v.as.b);
+    gl_unary_error("!", &v, location);
+    return v;
+}
+
+/* Returns the value of a condition, which must be a `bool`. */
+static bool gl_truth(Value v, const char *location) {
+    if (v.tag != GL_BOOL) {
+        char message[256];
+        snprintf(message, sizeof message, "Expected a `bool` condition, found `%s`.",
+                 gl_type_name(&v));
+        gl_fail("E0018", message, NULL, location);
+    }
+    return v.as.b;
+}
+
+static int64_t gl_as_int(Value v, const char *location) {
+    if (v.tag != GL_INT) {
+        char message[256];
+        snprintf(message, sizeof message, "Expected an `int`, found `%s`.", gl_type_name(&v));
+        gl_fail("E0018", message, NULL, location);
+    }
+    return v.as.i;
+}
+
+/* Returns the position of `index` in `array`, failing if it is out of bounds. */
+static size_t gl_element(const Value *array, int64_t index, const char *location) {
+    size_t len;
+    if (array->tag != GL_ARRAY) {
+        char message[256];
+        snprintf(message, sizeof message, "Cannot index into a value of type `%s`.",
+                 gl_type_name(array));
+        gl_fail("E0033", message, NULL, location);
+    }
+    len = ((GlArray *)array->as.h)->len;
+    if (index < 0 || (uint64_t)index >= len) {
+        char detail[128];
+        snprintf(detail, sizeof detail, "The length is %lu but the index is %" PRId64 ".",
+                 (unsigned long)len, index);
+        gl_fail("E0036", "Index out of bounds.", detail, location);
+    }
+    return (size_t)index;
+}
+
+static Value gl_index(Value array, Value index, const char *location) {
+    size_t position = gl_element(&array, gl_as_int(index, location), location);
+    Value element = gl_retain(((GlArray *)array.as.h)->items[position]);
+    gl_release(array);
+    return element;
+}
+
+/* Returns the storage of an element of the array in `slot`, first copying the
+ * elements if they are shared with another array. */
+static Value *gl_index_ptr(Value *slot, Value index, const char *location) {
+    int64_t i = gl_as_int(index, location);
+    size_t position = gl_element(slot, i, location), k;
+    GlArray *array = (GlArray *)slot->as.h;
+    if (array->head.rc > 1) {
+        Value copy = gl_array(array->len, array->items);
+        for (k = 0; k < array->len; k++) gl_retain(array->items[k]);
+        gl_set(slot, copy);
+        array = (GlArray *)copy.as.h;
+    }
+    return &array->items[position];
+}
+
+static void gl_no_field(const char *field, const Value *object, const char *location) {
+    char message[256];
+    snprintf(message, sizeof message, "No field `%s` on type `%s`.", field, gl_type_name(object));
+    gl_fail("E0021", message, NULL, location);
+}
+
+static size_t gl_field_position(const Value *object, const char *field, const char *location) {
+    if (object->tag == GL_STRUCT || object->tag == GL_OBJECT) {
+        const GlType *type = ((GlRecord *)object->as.h)->type;
+        size_t i;
+        for (i = 0; i < type->count; i++) {
+            if (type->fields[i] == field) return i;
+        }
+    }
+    gl_no_field(field, object, location);
+    return 0;
+}
+
+static Value gl_field(Value object, const char *field, const char *location) {
+    size_t position = gl_field_position(&object, field, location);
+    Value value = gl_retain(((GlRecord *)object.as.h)->fields[position]);
+    gl_release(object);
+    return value;
+}
+
+/* Returns the storage of a field of the struct or object in `slot`. A struct is
+ * copied first if it is shared; an object is written through the reference. */
+static Value *gl_field_ptr(Value *slot, const char *field, const char *location) {
+    size_t position = gl_field_position(slot, field, location), k;
+    GlRecord *record = (GlRecord *)slot->as.h;
+    if (slot->tag == GL_STRUCT && record->head.rc > 1) {
+        Value copy = gl_struct(record->type, record->fields);
+        for (k = 0; k < record->type->count; k++) gl_retain(record->fields[k]);
+        gl_set(slot, copy);
+        record = (GlRecord *)copy.as.h;
+    }
+    return &record->fields[position];
+}
+
+static bool gl_is_variant(const Value *v, const GlVariant *variant) {
+    return v->tag == GL_ENUM && ((GlEnum *)v->as.h)->variant == variant;
+}
+
+/* Returns a field of the payload of an enum value, without taking ownership of it. */
+static const Value *gl_variant_field(const Value *v, size_t i) {
+    return &((GlEnum *)v->as.h)->fields[i];
+}
+
+static void gl_no_match(Value v, const char *location) {
+    GlBuf buf = {NULL, 0, 0};
+    gl_buf_str(&buf, "No arm of this `match` matched the value `");
+    gl_buf_value(&buf, &v);
+    gl_buf_push(&buf, "`.", 3); /* Includes the terminating NUL. */
+    gl_fail("E0027", buf.data, NULL, location);
+}
+
+static void gl_no_method(const char *method, const Value *object, const char *location) {
+    char message[256];
+    snprintf(message, sizeof message, "No method `%s` on type `%s`.", method,
+             gl_type_name(object));
+    gl_fail("E0023", message, NULL, location);
+}
+
+static const GlType *gl_class_of(const Value *v) {
+    return v->tag == GL_OBJECT ? ((GlRecord *)v->as.h)->type : NULL;
+}
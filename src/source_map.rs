@@ -0,0 +1,148 @@
+use std::path::{Path, PathBuf};
+
+use crate::utils::FileId;
+
+/// The number of columns between tab stops when a line is displayed.
+const TAB_WIDTH: usize = 4;
+
+/// The text of every file of the program, read once and indexed by its file id.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+/// A file of the program, with the offsets at which its lines start.
+#[derive(Debug)]
+pub struct SourceFile {
+    path: PathBuf,
+    source: String,
+    /// The byte offset of the start of every line, beginning with 0.
+    line_starts: Vec<usize>,
+}
+
+/// A position in a source file, as shown to people. Lines and columns start
+/// at 1. The column counts characters, and the display column also accounts
+/// for tabs, which advance to the next tab stop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub display_column: usize,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    /// Adds the file at `path` with its text and returns its id.
+    pub fn add(&mut self, path: PathBuf, source: String) -> FileId {
+        self.files.push(SourceFile::new(path, source));
+        self.files.len() - 1
+    }
+
+    pub fn file(&self, file_id: FileId) -> &SourceFile {
+        &self.files[file_id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl SourceFile {
+    fn new(path: PathBuf, source: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            path,
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns `offset` moved back to the nearest character boundary within the source.
+    pub fn clamp(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+
+    /// Returns the index, starting at 0, of the line containing the byte `offset`.
+    pub fn line_index(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+
+    /// Returns the byte range of the line with index `line`, without its line break.
+    pub fn line_range(&self, line: usize) -> (usize, usize) {
+        let start = self.line_starts[line];
+        let end = match self.line_starts.get(line + 1) {
+            Some(next) => next - 1,
+            None => self.source.len(),
+        };
+        let text = &self.source[start..end];
+        (start, start + text.strip_suffix('\r').unwrap_or(text).len())
+    }
+
+    /// Returns the text of the line with index `line`, without its line break.
+    pub fn line_text(&self, line: usize) -> &str {
+        let (start, end) = self.line_range(line);
+        &self.source[start..end]
+    }
+
+    /// Returns the line and columns of the byte `offset`.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = self.clamp(offset);
+        let line = self.line_index(offset);
+        let before = &self.source[self.line_starts[line]..offset];
+        Position {
+            line: line + 1,
+            column: before.chars().count() + 1,
+            display_column: display_width(before) + 1,
+        }
+    }
+}
+
+/// Returns the number of columns that `text` takes up at the start of a line.
+pub fn display_width(text: &str) -> usize {
+    text.chars().fold(0, |width, ch| match ch {
+        '\t' => (width / TAB_WIDTH + 1) * TAB_WIDTH,
+        _ => width + 1,
+    })
+}
+
+/// Returns `line` with its tabs expanded to spaces up to the next tab stop,
+/// so that it lines up with columns computed by `display_width`.
+pub fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut width = 0;
+    for ch in line.chars() {
+        match ch {
+            '\t' => {
+                let next = (width / TAB_WIDTH + 1) * TAB_WIDTH;
+                expanded.push_str(&" ".repeat(next - width));
+                width = next;
+            }
+            ch => {
+                expanded.push(ch);
+                width += 1;
+            }
+        }
+    }
+    expanded
+}
@@ -0,0 +1,1157 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    fs,
+    io::{ErrorKind, Write as _},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    ast::{
+        ArmBody, Ast, BinOp, Block, Expr, Function, Item, MatchArm, Pattern, Statement, UnaryOp,
+    },
+    compiler::Compiler,
+    diag,
+    diagnostic::{plain_location, Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    resolver::{Resolutions, SymbolKind},
+    types::TypeInfo,
+    utils::{Span, Token},
+    value::{stack_overflow, Value, MAX_CALL_DEPTH},
+};
+
+/// The C runtime that every generated program starts with.
+pub const RUNTIME: &str = include_str!("runtime.c");
+
+/// The code of the C function being generated.
+#[derive(Default)]
+struct CFunction {
+    body: String,
+    indent: usize,
+    /// The number of `l` slots for named locals, including the parameters.
+    locals: usize,
+    temps: usize,
+    labels: usize,
+    /// Whether the function counts itself in `gl_depth`, which every function of
+    /// the program does but the initialization of the globals does not.
+    counted: bool,
+}
+
+/// Translates a type checked program into a single C99 source file that uses
+/// the runtime in `runtime.c`. Evaluation order, integer and float semantics
+/// and the output of `println` match the interpreter.
+pub struct Transpiler<'a> {
+    compiler: &'a Compiler,
+    resolutions: &'a Resolutions,
+    types: &'a TypeInfo,
+    /// Values built once at startup, such as strings and default values.
+    constants: Vec<Value>,
+    /// Every field name, which the runtime compares by address.
+    fields: BTreeMap<String, String>,
+    /// C names of functions, by function name or `Class.method`.
+    functions: HashMap<String, String>,
+    /// The classes that declare each method, by method name and arity.
+    methods: BTreeMap<(String, usize), Vec<String>>,
+    /// C names of the global constants, by the span of their name.
+    globals: HashMap<Span, String>,
+    /// C names of the locals of the function being generated, by the span of the declaring name.
+    locals: HashMap<Span, String>,
+    function: CFunction,
+}
+
+impl<'a> Transpiler<'a> {
+    pub fn new(compiler: &'a Compiler, resolutions: &'a Resolutions, types: &'a TypeInfo) -> Self {
+        Self {
+            compiler,
+            resolutions,
+            types,
+            constants: Vec::new(),
+            fields: BTreeMap::new(),
+            functions: HashMap::new(),
+            methods: BTreeMap::new(),
+            globals: HashMap::new(),
+            locals: HashMap::new(),
+            function: CFunction::default(),
+        }
+    }
+
+    /// Returns the C source of the program, or `None` if it has no `main` function.
+    pub fn transpile(mut self, ast: &Ast) -> Option<String> {
+        let Some(main) = ast.items.iter().find_map(|item| match item {
+            Item::Func(function) if function.name.as_ident() == "main" => Some(function),
+            _ => None,
+        }) else {
+            self.compiler.reporter.borrow_mut().add(
+                diag!(
+                    "No `main` function found.",
+                    "Add a `func main() { ... }` to run this program.",
+                    Span::default()
+                )
+                .with_code(ErrorCode::NoMain),
+            );
+            return None;
+        };
+        // `g_main` is called without arguments, so a `main` with parameters would not compile.
+        if !main.params.is_empty() {
+            self.compiler.reporter.borrow_mut().add(
+                diag!(
+                    "The `main` function cannot take parameters.",
+                    "`main` is called without arguments when the program starts.",
+                    main.name.span
+                )
+                .with_code(ErrorCode::MainParameters),
+            );
+            return None;
+        }
+
+        let mut prototypes = String::new();
+        let mut globals = String::new();
+        for item in &ast.items {
+            match item {
+                Item::Func(function) => {
+                    let name = format!("g_{}", mangle(function.name.as_ident()));
+                    writeln!(prototypes, "static Value {};", signature(&name, function)).unwrap();
+                    self.functions
+                        .insert(function.name.as_ident().to_string(), name);
+                }
+                Item::Class(class) => {
+                    for method in &class.methods {
+                        let name = method_name(class.name.as_ident(), method.name.as_ident());
+                        writeln!(prototypes, "static Value {};", signature(&name, method)).unwrap();
+                        self.functions
+                            .insert(format!("{}.{}", class.name.ty, method.name.ty), name);
+                        self.methods
+                            .entry((method.name.as_ident().to_string(), method.params.len()))
+                            .or_default()
+                            .push(class.name.as_ident().to_string());
+                    }
+                }
+                Item::Const(constant) => {
+                    let name = format!("G_{}", mangle(constant.name.as_ident()));
+                    writeln!(globals, "static Value {};", name).unwrap();
+                    self.globals.insert(constant.name.span, name);
+                }
+                _ => {}
+            }
+        }
+
+        let mut functions = String::new();
+        self.begin_function();
+        for item in &ast.items {
+            if let Item::Const(constant) = item {
+                let value = self.expr(&constant.expr);
+                let name = self.globals[&constant.name.span].clone();
+                self.line(format!("{} = {};", name, value));
+            }
+        }
+        functions.push_str(&self.end_function("static Value gl_globals(void)"));
+
+        for item in &ast.items {
+            match item {
+                Item::Func(function) => {
+                    let name = self.functions[function.name.as_ident()].clone();
+                    functions.push_str(&self.function(function, &name));
+                }
+                Item::Class(class) => {
+                    for method in &class.methods {
+                        let name = method_name(class.name.as_ident(), method.name.as_ident());
+                        functions.push_str(&self.function(method, &name));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let dispatchers = self.dispatchers(&mut prototypes);
+        let mut output = String::new();
+        writeln!(output, "/* Generated by glacier. */\n").unwrap();
+        output.push_str(RUNTIME);
+        output.push('\n');
+        output.push_str(&self.declarations(ast));
+        output.push_str(&self.constant_pool());
+        output.push_str(&globals);
+        output.push('\n');
+        output.push_str(&prototypes);
+        output.push('\n');
+        output.push_str(&dispatchers);
+        output.push_str(&functions);
+        writeln!(
+            output,
+            "int main(void) {{\n    gl_init_constants();\n    gl_release(gl_globals());\n    gl_release({}());\n    return 0;\n}}",
+            self.functions[main.name.as_ident()]
+        )
+        .unwrap();
+        Some(output)
+    }
+
+    /// Declares the field names, struct and class types and enum variants.
+    fn declarations(&mut self, ast: &Ast) -> String {
+        let mut types = String::new();
+        for item in &ast.items {
+            let (name, fields): (&str, Vec<&str>) = match item {
+                Item::Struct(decl) => (
+                    decl.name.as_ident(),
+                    decl.fields
+                        .iter()
+                        .map(|field| field.name.as_ident())
+                        .collect(),
+                ),
+                Item::Class(class) => (
+                    class.name.as_ident(),
+                    class
+                        .fields
+                        .iter()
+                        .map(|field| field.name.as_ident())
+                        .collect(),
+                ),
+                Item::Enum(decl) => {
+                    for variant in &decl.variants {
+                        writeln!(
+                            types,
+                            "static const GlVariant {} = {{{}, {}}};",
+                            variant_name(decl.name.as_ident(), variant.name.as_ident()),
+                            c_string(decl.name.as_ident()),
+                            c_string(variant.name.as_ident())
+                        )
+                        .unwrap();
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let field_names: Vec<String> = fields.iter().map(|field| self.field(field)).collect();
+            let list = if fields.is_empty() {
+                String::from("NULL")
+            } else {
+                writeln!(
+                    types,
+                    "static const char *const TF_{}[] = {{{}}};",
+                    mangle(name),
+                    field_names.join(", ")
+                )
+                .unwrap();
+                format!("TF_{}", mangle(name))
+            };
+            writeln!(
+                types,
+                "static const GlType T_{} = {{{}, {}, {}}};",
+                mangle(name),
+                c_string(name),
+                fields.len(),
+                list
+            )
+            .unwrap();
+        }
+
+        let mut output = String::new();
+        for (name, c_name) in &self.fields {
+            writeln!(
+                output,
+                "static const char {}[] = {};",
+                c_name,
+                c_string(name)
+            )
+            .unwrap();
+        }
+        output.push_str(&types);
+        output.push('\n');
+        output
+    }
+
+    /// Defines the constant pool and the function that fills it at startup.
+    fn constant_pool(&self) -> String {
+        let mut init = String::new();
+        for (i, value) in self.constants.iter().enumerate() {
+            writeln!(init, "    K[{}] = {};", i, build_value(value)).unwrap();
+        }
+
+        format!(
+            "static Value K[{}];\n\nstatic void gl_init_constants(void) {{\n{}}}\n\n",
+            self.constants.len().max(1),
+            init
+        )
+    }
+
+    /// Defines, for every method name and arity, a function that calls the
+    /// method of the class of its receiver.
+    fn dispatchers(&mut self, prototypes: &mut String) -> String {
+        let mut output = String::new();
+        for ((method, arity), classes) in &self.methods {
+            let params: String = (0..*arity).map(|i| format!(", Value a{}", i)).collect();
+            let args: String = (0..*arity).map(|i| format!(", a{}", i)).collect();
+            let name = dispatcher_name(method, *arity);
+            let header = format!(
+                "static Value {}(const char *location, Value self{})",
+                name, params
+            );
+            writeln!(prototypes, "{};", header).unwrap();
+
+            writeln!(output, "{} {{", header).unwrap();
+            writeln!(output, "    const GlType *type = gl_class_of(&self);").unwrap();
+            for class in classes {
+                writeln!(
+                    output,
+                    "    if (type == &T_{}) return {}(self{});",
+                    mangle(class),
+                    method_name(class, method),
+                    args
+                )
+                .unwrap();
+            }
+            writeln!(
+                output,
+                "    gl_no_method({}, &self, location);\n    return gl_unit();\n}}\n",
+                c_string(method)
+            )
+            .unwrap();
+        }
+        output
+    }
+
+    fn function(&mut self, function: &Function, name: &str) -> String {
+        self.begin_function();
+        if let Some(self_param) = &function.self_param {
+            self.declare(self_param);
+        }
+        for param in &function.params {
+            self.declare(&param.name);
+        }
+        self.function.counted = true;
+        self.block(&function.body);
+        self.end_function(&format!("static Value {}", signature(name, function)))
+    }
+
+    fn begin_function(&mut self) {
+        self.function = CFunction {
+            indent: 1,
+            ..Default::default()
+        };
+        self.locals.clear();
+    }
+
+    /// Wraps the generated body in a function with the given header. The locals
+    /// are declared up front and released on the way out.
+    fn end_function(&mut self, header: &str) -> String {
+        let function = std::mem::take(&mut self.function);
+        let params = header.matches("Value l").count();
+        let mut output = format!("{} {{\n    Value gl_ret = gl_unit();\n", header);
+        for slot in params..function.locals {
+            writeln!(output, "    Value l{} = gl_unit();", slot).unwrap();
+        }
+        if function.counted {
+            let overflow = stack_overflow(Span::default());
+            writeln!(
+                output,
+                "    if (++gl_depth > {}) gl_fail(\"{}\", {}, {}, gl_call_site);",
+                MAX_CALL_DEPTH,
+                ErrorCode::StackOverflow.as_str(),
+                c_string(&overflow.primary_msg),
+                c_string(overflow.secondary_msg.as_deref().unwrap_or_default())
+            )
+            .unwrap();
+        }
+        output.push_str(&function.body);
+        output.push_str("gl_out:\n");
+        if function.counted {
+            output.push_str("    gl_depth--;\n");
+        }
+        for slot in 0..function.locals {
+            writeln!(output, "    gl_release(l{});", slot).unwrap();
+        }
+        output.push_str("    return gl_ret;\n}\n\n");
+        output
+    }
+
+    fn block(&mut self, block: &Block) {
+        for stmt in &block.0 {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::VarDecl { name, expr, .. } => {
+                let value = match expr {
+                    Expr::None => {
+                        let value = Value::default_for(self.types.binding(name), self.types);
+                        self.value(value)
+                    }
+                    expr => self.expr(expr),
+                };
+                let local = self.declare(name);
+                self.line(format!("gl_set(&{}, {});", local, value));
+            }
+            Statement::Const(constant) => {
+                let value = self.expr(&constant.expr);
+                let local = self.declare(&constant.name);
+                self.line(format!("gl_set(&{}, {});", local, value));
+            }
+            Statement::Return { expr, .. } => {
+                let value = match expr {
+                    Expr::None => String::from("gl_unit()"),
+                    expr => self.expr(expr),
+                };
+                self.line(format!("gl_ret = {};", value));
+                self.line("goto gl_out;");
+            }
+            Statement::Expression(expr) => {
+                let value = self.expr(expr);
+                if value != "gl_unit()" {
+                    self.line(format!("gl_release({});", value));
+                }
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let value = self.expr(condition);
+                let location = self.location(condition.span());
+                self.open(format!("if (gl_truth({}, {})) {{", value, location));
+                self.block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.close_open("} else {");
+                    self.block(else_branch);
+                }
+                self.close("}");
+            }
+            Statement::While { condition, body } => {
+                self.open("for (;;) {");
+                let value = self.expr(condition);
+                let location = self.location(condition.span());
+                self.line(format!("if (!gl_truth({}, {})) break;", value, location));
+                self.block(body);
+                self.close("}");
+            }
+            Statement::For { var, range, body } => {
+                let Expr::Range {
+                    start,
+                    end,
+                    inclusive,
+                } = range
+                else {
+                    unreachable!()
+                };
+                self.open("{");
+                let start_value = self.expr(start);
+                let low = self.temp_name();
+                let location = self.location(start.span());
+                self.line(format!(
+                    "int64_t {} = gl_as_int({}, {});",
+                    low, start_value, location
+                ));
+                let end_value = self.expr(end);
+                let high = self.temp_name();
+                let location = self.location(end.span());
+                self.line(format!(
+                    "int64_t {} = gl_as_int({}, {});",
+                    high, end_value, location
+                ));
+                // Count the iterations up front so that ranges ending at the
+                // largest `int` do not overflow.
+                let count = self.temp_name();
+                let step = self.temp_name();
+                let (compare, extra) = if *inclusive {
+                    ("<=", " + 1")
+                } else {
+                    ("<", "")
+                };
+                self.line(format!(
+                    "uint64_t {count} = {low} {compare} {high} ? (uint64_t){high} - (uint64_t){low}{extra} : 0, {step};"
+                ));
+                self.open(format!("for ({step} = 0; {step} < {count}; {step}++) {{"));
+                let local = self.declare(var);
+                self.line(format!(
+                    "gl_set(&{}, gl_int((int64_t)((uint64_t){} + {})));",
+                    local, low, step
+                ));
+                self.block(body);
+                self.close("}");
+                self.close("}");
+            }
+            Statement::Break(_) => self.line("break;"),
+            Statement::Continue(_) => self.line("continue;"),
+            Statement::Unknown => {}
+        }
+    }
+
+    /// Generates the code that evaluates `expr` and returns a C expression for the
+    /// resulting value, owned by the caller. Anything with side effects is stored
+    /// in a temporary first, so that the evaluation order matches the interpreter.
+    fn expr(&mut self, expr: &Expr) -> String {
+        let span = expr.span();
+        match expr {
+            Expr::Binary {
+                lhs,
+                op: op @ (BinOp::And | BinOp::Or),
+                rhs,
+            } => {
+                let result = self.temp_name();
+                self.line(format!("Value {};", result));
+                let lhs_value = self.expr(lhs);
+                let location = self.location(lhs.span());
+                let negate = if *op == BinOp::And { "" } else { "!" };
+                self.open(format!(
+                    "if ({}gl_truth({}, {})) {{",
+                    negate, lhs_value, location
+                ));
+                let rhs_value = self.expr(rhs);
+                let location = self.location(rhs.span());
+                self.line(format!(
+                    "{} = gl_bool(gl_truth({}, {}));",
+                    result, rhs_value, location
+                ));
+                self.close_open("} else {");
+                self.line(format!(
+                    "{} = gl_bool({});",
+                    result,
+                    i32::from(*op == BinOp::Or)
+                ));
+                self.close("}");
+                result
+            }
+            Expr::Binary { lhs, op, rhs } => {
+                let lhs = self.expr(lhs);
+                let rhs = self.expr(rhs);
+                let location = self.location(span);
+                self.temp(format!(
+                    "gl_binary({}, {}, {}, {})",
+                    binary_op(op),
+                    lhs,
+                    rhs,
+                    location
+                ))
+            }
+            Expr::Unary { op, rhs } => {
+                let value = self.expr(rhs);
+                let location = self.location(span);
+                match op {
+                    UnaryOp::Negative => self.temp(format!("gl_negative({}, {})", value, location)),
+                    UnaryOp::Negate => self.temp(format!("gl_not({}, {})", value, location)),
+                    UnaryOp::Unknown => value,
+                }
+            }
+            Expr::Literal(literal) => {
+                let value = Value::from_literal(literal);
+                self.value(value)
+            }
+            Expr::Var(name) => match self.variable(name) {
+                Some(variable) => self.temp(format!("gl_retain({})", variable)),
+                None => String::from("gl_unit()"),
+            },
+            Expr::Grouping(expr) => self.expr(expr),
+            Expr::Call { callee, args } => self.call(callee, args, span),
+            Expr::Path(path) => self.value(variant(path)),
+            Expr::Match {
+                keyword,
+                scrutinee,
+                arms,
+            } => self.match_expr(keyword, scrutinee, arms),
+            Expr::StructLiteral { name, fields } => {
+                let mut values = HashMap::new();
+                for field in fields {
+                    let value = self.expr(&field.value);
+                    values.insert(field.name.as_ident(), value);
+                }
+                // Fields are evaluated in the order they are written and stored in declaration order.
+                let ordered: Vec<String> = self
+                    .types
+                    .struct_fields(name.as_ident())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(field, _)| {
+                        values
+                            .remove(field.as_str())
+                            .unwrap_or_else(|| String::from("gl_unit()"))
+                    })
+                    .collect();
+                self.temp(format!(
+                    "gl_struct(&T_{}, {})",
+                    mangle(name.as_ident()),
+                    value_list(&ordered)
+                ))
+            }
+            Expr::Field { object, field } => {
+                let object = self.expr(object);
+                let field_name = self.field(field.as_ident());
+                let location = self.location(field.span);
+                self.temp(format!(
+                    "gl_field({}, {}, {})",
+                    object, field_name, location
+                ))
+            }
+            Expr::Array { elements, .. } => {
+                let elements: Vec<String> =
+                    elements.iter().map(|element| self.expr(element)).collect();
+                self.temp(format!(
+                    "gl_array({}, {})",
+                    elements.len(),
+                    value_list(&elements)
+                ))
+            }
+            Expr::Index { object, index } => {
+                let object = self.expr(object);
+                let index = self.expr(index);
+                let location = self.location(span);
+                self.temp(format!("gl_index({}, {}, {})", object, index, location))
+            }
+            Expr::Assign { target, op, value } => {
+                self.assign(target, op.as_ref(), value, span);
+                String::from("gl_unit()")
+            }
+            Expr::Range { .. } | Expr::None | Expr::Unknown => String::from("gl_unit()"),
+        }
+    }
+
+    /// Returns the C name of the local or global that `name` refers to.
+    fn variable(&self, name: &Token) -> Option<String> {
+        let symbol = self.resolutions.get(name)?;
+        self.locals
+            .get(&symbol.span)
+            .or_else(|| self.globals.get(&symbol.span))
+            .cloned()
+    }
+
+    fn call(&mut self, callee: &Expr, args: &[Expr], span: Span) -> String {
+        match callee {
+            Expr::Var(name) => {
+                let kind = self.resolutions.get(name).map(|symbol| symbol.kind);
+                let args: Vec<String> = args.iter().map(|arg| self.expr(arg)).collect();
+                match kind {
+                    Some(SymbolKind::Function) => {
+                        let function = self.functions[name.as_ident()].clone();
+                        self.call_site(name.span);
+                        self.temp(format!("{}({})", function, args.join(", ")))
+                    }
+                    Some(SymbolKind::Class) => {
+                        let instance = Value::new_instance(name.as_ident(), self.types);
+                        let fields = self.value(instance);
+                        let object = self.temp(format!("gl_new({})", fields));
+                        if let Some(init) =
+                            self.functions.get(&format!("{}.init", name.ty)).cloned()
+                        {
+                            let mut init_args = vec![format!("gl_retain({})", object)];
+                            init_args.extend(args);
+                            self.call_site(name.span);
+                            self.line(format!("gl_release({}({}));", init, init_args.join(", ")));
+                        }
+                        object
+                    }
+                    Some(SymbolKind::Builtin) if name.as_ident() == "len" => {
+                        self.temp(format!("gl_len({})", args[0]))
+                    }
+                    Some(SymbolKind::Builtin) => {
+                        self.temp(format!("gl_println({}, {})", args.len(), value_list(&args)))
+                    }
+                    _ => String::from("gl_unit()"),
+                }
+            }
+            Expr::Field { object, field } => {
+                let receiver = self.expr(object);
+                let args: Vec<String> = args.iter().map(|arg| self.expr(arg)).collect();
+                let location = self.location(field.span);
+                let dispatcher = dispatcher_name(field.as_ident(), args.len());
+                let args: String = args.iter().map(|arg| format!(", {}", arg)).collect();
+                self.call_site(field.span);
+                self.temp(format!(
+                    "{}({}, {}{})",
+                    dispatcher, location, receiver, args
+                ))
+            }
+            Expr::Path(path) => {
+                let args: Vec<String> = args.iter().map(|arg| self.expr(arg)).collect();
+                self.temp(format!(
+                    "gl_enum(&{}, {}, {})",
+                    variant_name(path[0].as_ident(), path[path.len() - 1].as_ident()),
+                    args.len(),
+                    value_list(&args)
+                ))
+            }
+            _ => {
+                let location = self.location(span);
+                self.line(format!(
+                    "gl_fail(\"E0024\", \"Expression is not callable.\", NULL, {});",
+                    location
+                ));
+                String::from("gl_unit()")
+            }
+        }
+    }
+
+    /// Generates an assignment. The new value and every index along the target
+    /// are evaluated first, then the storage of the target is found through
+    /// pointers, copying shared arrays and structs on the way.
+    fn assign(&mut self, target: &Expr, op: Option<&BinOp>, value: &Expr, span: Span) {
+        let location = self.location(span);
+        if let Expr::Var(name) = target {
+            if let Some(variable) = self.variable(name) {
+                let value = self.expr(value);
+                match op {
+                    Some(op) => self.line(format!(
+                        "{0} = gl_binary({1}, {0}, {2}, {3});",
+                        variable,
+                        binary_op(op),
+                        value,
+                        location
+                    )),
+                    None => self.line(format!("gl_set(&{}, {});", variable, value)),
+                }
+                return;
+            }
+        }
+
+        let value = self.expr(value);
+        let mut steps = Vec::new();
+        let mut place = target;
+        loop {
+            match place {
+                Expr::Field { object, field } => {
+                    let field_name = self.field(field.as_ident());
+                    let location = self.location(field.span);
+                    steps.push(format!("gl_field_ptr({{}}, {}, {})", field_name, location));
+                    place = object;
+                }
+                Expr::Index { object, index } => {
+                    let index = self.expr(index);
+                    let location = self.location(place.span());
+                    steps.push(format!("gl_index_ptr({{}}, {}, {})", index, location));
+                    place = object;
+                }
+                _ => break,
+            }
+        }
+
+        let (root, temporary) = match place {
+            Expr::Var(name) => match self.variable(name) {
+                Some(variable) => (variable, false),
+                None => (self.expr(place), true),
+            },
+            place => {
+                let root = self.expr(place);
+                (self.temp(root), true)
+            }
+        };
+        let pointer = self.temp_name();
+        self.line(format!("Value *{} = &{};", pointer, root));
+        for step in steps.iter().rev() {
+            self.line(format!("{} = {};", pointer, step.replace("{}", &pointer)));
+        }
+        match op {
+            Some(op) => self.line(format!(
+                "*{0} = gl_binary({1}, *{0}, {2}, {3});",
+                pointer,
+                binary_op(op),
+                value,
+                location
+            )),
+            None => self.line(format!("gl_set({}, {});", pointer, value)),
+        }
+        if temporary {
+            self.line(format!("gl_release({});", root));
+        }
+    }
+
+    fn match_expr(&mut self, keyword: &Token, scrutinee: &Expr, arms: &[MatchArm]) -> String {
+        let value = self.expr(scrutinee);
+        let scrutinee = self.temp(value);
+        let result = self.temp_name();
+        self.line(format!("Value {} = gl_unit();", result));
+        let end = self.label();
+
+        for arm in arms {
+            let next_arm = self.label();
+            self.open("{");
+            self.pattern(&arm.pattern, &format!("&{}", scrutinee), &next_arm);
+            match &arm.body {
+                ArmBody::Expr(expr) => {
+                    let value = self.expr(expr);
+                    self.line(format!("{} = {};", result, value));
+                }
+                ArmBody::Block(block) => self.block(block),
+            }
+            self.line(format!("goto {};", end));
+            self.close("}");
+            self.line(format!("{}:;", next_arm));
+        }
+
+        let location = self.location(keyword.span);
+        self.line(format!("gl_no_match({}, {});", scrutinee, location));
+        self.line(format!("{}:;", end));
+        self.line(format!("gl_release({});", scrutinee));
+        result
+    }
+
+    /// Generates the test of whether the value that `value` points to matches
+    /// `pattern`, jumping to `next_arm` if it does not, and binds its names.
+    fn pattern(&mut self, pattern: &Pattern, value: &str, next_arm: &str) {
+        let location = self.location(pattern.span());
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding(name) => {
+                let local = self.declare(name);
+                self.line(format!("gl_set(&{}, gl_retain(*{}));", local, value));
+            }
+            Pattern::Literal(expr) => {
+                let literal = match expr {
+                    Expr::Unary {
+                        op: UnaryOp::Negative,
+                        rhs,
+                    } => match rhs.as_ref() {
+                        Expr::Literal(literal) => match Value::from_literal(literal) {
+                            Value::Int(int) => Value::Int(int.wrapping_neg()),
+                            Value::Float(float) => Value::Float(-float),
+                            value => value,
+                        },
+                        _ => Value::Unit,
+                    },
+                    Expr::Literal(literal) => Value::from_literal(literal),
+                    _ => Value::Unit,
+                };
+                let literal = self.value(literal);
+                self.line(format!(
+                    "if (!gl_truth(gl_binary(GL_EQ, gl_retain(*{}), {}, {}), {})) goto {};",
+                    value, literal, location, location, next_arm
+                ));
+            }
+            Pattern::Variant { path, fields } => {
+                let variant = variant_name(path[0].as_ident(), path[path.len() - 1].as_ident());
+                self.line(format!(
+                    "if (!gl_is_variant({}, &{})) goto {};",
+                    value, variant, next_arm
+                ));
+                for (i, field) in fields.iter().enumerate() {
+                    if let Pattern::Wildcard(_) = field {
+                        continue;
+                    }
+                    let field_value = self.temp_name();
+                    self.line(format!(
+                        "const Value *{} = gl_variant_field({}, {});",
+                        field_value, value, i
+                    ));
+                    self.pattern(field, &field_value, next_arm);
+                }
+            }
+        }
+    }
+
+    /// Returns a C expression for `value`. Values that live on the heap are built
+    /// once in the constant pool and shared.
+    fn value(&mut self, value: Value) -> String {
+        match value {
+            Value::Int(_) | Value::Float(_) | Value::Bool(_) | Value::Char(_) | Value::Unit => {
+                build_value(&value)
+            }
+            value => {
+                let index = match self
+                    .constants
+                    .iter()
+                    .position(|constant| *constant == value)
+                {
+                    Some(index) => index,
+                    None => {
+                        self.constants.push(value);
+                        self.constants.len() - 1
+                    }
+                };
+                format!("gl_retain(K[{}])", index)
+            }
+        }
+    }
+
+    /// Returns the C name of the static string that holds the field name `name`.
+    fn field(&mut self, name: &str) -> String {
+        self.fields
+            .entry(name.to_string())
+            .or_insert_with(|| format!("F_{}", mangle(name)))
+            .clone()
+    }
+
+    /// Allocates a local slot for the variable declared by `name`.
+    fn declare(&mut self, name: &Token) -> String {
+        let local = format!("l{}", self.function.locals);
+        self.function.locals += 1;
+        self.locals.insert(name.span, local.clone());
+        local
+    }
+
+    /// Stores `value` in a new temporary and returns its name.
+    fn temp(&mut self, value: String) -> String {
+        let name = self.temp_name();
+        self.line(format!("Value {} = {};", name, value));
+        name
+    }
+
+    fn temp_name(&mut self) -> String {
+        self.function.temps += 1;
+        format!("t{}", self.function.temps)
+    }
+
+    fn label(&mut self) -> String {
+        self.function.labels += 1;
+        format!("gl_label{}", self.function.labels)
+    }
+
+    /// Returns a C string literal that shows where `span` is, for runtime errors.
+    /// Records that the next call is the one at `span`, where the callee reports
+    /// a stack overflow.
+    fn call_site(&mut self, span: Span) {
+        let location = self.location(span);
+        self.line(format!("gl_call_site = {};", location));
+    }
+
+    fn location(&self, span: Span) -> String {
+        c_string(&plain_location(self.compiler, span))
+    }
+
+    fn line(&mut self, line: impl AsRef<str>) {
+        let indent = "    ".repeat(self.function.indent);
+        writeln!(self.function.body, "{}{}", indent, line.as_ref()).unwrap();
+    }
+
+    fn open(&mut self, line: impl AsRef<str>) {
+        self.line(line);
+        self.function.indent += 1;
+    }
+
+    fn close(&mut self, line: impl AsRef<str>) {
+        self.function.indent -= 1;
+        self.line(line);
+    }
+
+    fn close_open(&mut self, line: impl AsRef<str>) {
+        self.function.indent -= 1;
+        self.line(line);
+        self.function.indent += 1;
+    }
+}
+
+/// Compiles `source` into the executable `output` with the system C compiler.
+/// The source is compiled from a temporary directory and, with `emit_c`, also
+/// written next to `output` as `OUT.c`.
+pub fn build_executable(source: &str, output: &Path, emit_c: bool) -> Result<(), Diagnostic> {
+    if emit_c {
+        write_new(&output.with_extension("c"), source.as_bytes())?;
+    }
+    let dir = BuildDir::new()?;
+    let c_path = dir.path().join("main.c");
+    fs::write(&c_path, source).map_err(|err| write_error(&c_path, err))?;
+    cc(&[&c_path], output)
+}
+
+/// A directory for the intermediate files of a build, in the temporary
+/// directory of the system. It is removed with its files when dropped.
+pub struct BuildDir(PathBuf);
+
+impl BuildDir {
+    pub fn new() -> Result<Self, Diagnostic> {
+        let temp = std::env::temp_dir();
+        let mut attempt = 0;
+        loop {
+            let path = temp.join(format!("olive-{}-{}", process::id(), attempt));
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(Self(path)),
+                // Left over by an earlier process with the same id.
+                Err(err) if err.kind() == ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+                Err(err) => return Err(write_error(&path, err)),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for BuildDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes `contents` to a new file at `path`, which is kept after the build.
+/// A file that is already there is never replaced.
+pub fn write_new(path: &Path, contents: &[u8]) -> Result<(), Diagnostic> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|err| match err.kind() {
+            ErrorKind::AlreadyExists => {
+                Diagnostic::without_span(format!("`{}` already exists.", path.display()))
+                    .with_code(ErrorCode::CannotWriteFile)
+                    .with_note("The build does not replace existing files.")
+                    .with_help("Remove the file, or choose another output with `-o`.")
+            }
+            _ => write_error(path, err),
+        })?;
+    file.write_all(contents)
+        .map_err(|err| write_error(path, err))
+}
+
+/// Compiles and links `inputs`, C sources or object files, into the executable `output` with the system C compiler.
+///
+/// The inputs are generated by this compiler, so `cc` rejecting them is a bug
+/// in a backend and is reported as an internal compiler error with the output of `cc`.
+pub fn cc(inputs: &[&Path], output: &Path) -> Result<(), Diagnostic> {
+    let result = process::Command::new("cc")
+        .arg("-std=c99")
+        .arg("-O2")
+        .arg("-o")
+        .arg(output)
+        .args(inputs)
+        .arg("-lm")
+        .output();
+    match result {
+        Ok(result) if result.status.success() => Ok(()),
+        Ok(result) => {
+            let mut diagnostic = Diagnostic::without_span(format!(
+                "Internal compiler error: the C compiler `cc` failed with {}.",
+                result.status
+            ))
+            .with_code(ErrorCode::InternalCompilerError);
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            if !stderr.trim().is_empty() {
+                diagnostic = diagnostic.with_note(format!("`cc` printed:\n{}", stderr.trim_end()));
+            }
+            Err(diagnostic.with_help(
+                "This is a bug in the compiler. Please report it with the program that caused it.",
+            ))
+        }
+        Err(err) => Err(Diagnostic::without_span(format!(
+            "Couldn't run the C compiler `cc`: {}",
+            err
+        ))
+        .with_code(ErrorCode::CCompilerNotFound)
+        .with_note("An executable is built with the system C compiler.")),
+    }
+}
+
+/// Reports that the file at `path`, which is needed to build the executable, could not be written.
+pub fn write_error(path: &Path, err: std::io::Error) -> Diagnostic {
+    Diagnostic::without_span(format!("Couldn't write `{}`: {}", path.display(), err))
+        .with_code(ErrorCode::CannotWriteFile)
+        .with_note("The executable could not be built.")
+}
+
+/// Returns a C expression that builds `value` from scratch.
+fn build_value(value: &Value) -> String {
+    match value {
+        Value::Int(i64::MIN) => String::from("gl_int(INT64_MIN)"),
+        Value::Int(int) => format!("gl_int(INT64_C({}))", int),
+        Value::Float(float) => format!("gl_float_bits(UINT64_C(0x{:x}))", float.to_bits()),
+        Value::Bool(bool) => format!("gl_bool({})", i32::from(*bool)),
+        Value::Char(ch) => format!("gl_char({})", u32::from(*ch)),
+        Value::Unit => String::from("gl_unit()"),
+        Value::String(string) => {
+            format!("gl_string({}, {})", c_string(string), string.len())
+        }
+        Value::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(build_value).collect();
+            format!("gl_array({}, {})", elements.len(), value_list(&elements))
+        }
+        Value::Struct { name, fields } => {
+            let fields: Vec<String> = fields.iter().map(|(_, value)| build_value(value)).collect();
+            format!("gl_struct(&T_{}, {})", mangle(name), value_list(&fields))
+        }
+        Value::Enum {
+            name,
+            variant,
+            fields,
+        } => {
+            let fields: Vec<String> = fields.iter().map(build_value).collect();
+            format!(
+                "gl_enum(&{}, {}, {})",
+                variant_name(name, variant),
+                fields.len(),
+                value_list(&fields)
+            )
+        }
+        // Objects are never constants: fields of class type start out as `()`.
+        Value::Object(_) => String::from("gl_unit()"),
+    }
+}
+
+/// The value of a variant without a payload, such as `Shape::Empty`.
+fn variant(path: &[Token]) -> Value {
+    Value::Enum {
+        name: path[0].as_ident().to_string(),
+        variant: path[path.len() - 1].as_ident().to_string(),
+        fields: Vec::new(),
+    }
+}
+
+fn binary_op(op: &BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "GL_ADD",
+        BinOp::Subtract => "GL_SUB",
+        BinOp::Multiply => "GL_MUL",
+        BinOp::Divide => "GL_DIV",
+        BinOp::Eq => "GL_EQ",
+        BinOp::NotEq => "GL_NE",
+        BinOp::LT => "GL_LT",
+        BinOp::LTOrEq => "GL_LE",
+        BinOp::GT => "GL_GT",
+        BinOp::GTOrEq => "GL_GE",
+        BinOp::And | BinOp::Or => unreachable!(),
+    }
+}
+
+/// Returns a C array of `values` that the runtime takes ownership of, or `NULL` if it is empty.
+fn value_list(values: &[String]) -> String {
+    if values.is_empty() {
+        String::from("NULL")
+    } else {
+        format!("(Value[]){{{}}}", values.join(", "))
+    }
+}
+
+fn signature(name: &str, function: &Function) -> String {
+    let arity = function.params.len() + usize::from(function.self_param.is_some());
+    let params: Vec<String> = (0..arity).map(|i| format!("Value l{}", i)).collect();
+    if params.is_empty() {
+        format!("{}(void)", name)
+    } else {
+        format!("{}({})", name, params.join(", "))
+    }
+}
+
+fn method_name(class: &str, method: &str) -> String {
+    format!("m{}_{}_{}", class.len(), mangle(class), mangle(method))
+}
+
+fn variant_name(name: &str, variant: &str) -> String {
+    format!("V{}_{}_{}", name.len(), mangle(name), mangle(variant))
+}
+
+fn dispatcher_name(method: &str, arity: usize) -> String {
+    format!("d{}_{}", arity, mangle(method))
+}
+
+/// Turns a Glacier identifier into a valid C identifier.
+fn mangle(name: &str) -> String {
+    let mut mangled = String::new();
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() || ch == '_' {
+            mangled.push(ch);
+        } else {
+            write!(mangled, "_u{:x}_", u32::from(ch)).unwrap();
+        }
+    }
+    mangled
+}
+
+/// Returns a C string literal for `string`, escaping everything that is not printable ASCII.
+fn c_string(string: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in string.bytes() {
+        match byte {
+            b'"' | b'\\' | b'?' => write!(literal, "\\{}", byte as char).unwrap(),
+            b' '..=b'~' => literal.push(byte as char),
+            _ => write!(literal, "\\{:03o}", byte).unwrap(),
+        }
+    }
+    literal.push('"');
+    literal
+}
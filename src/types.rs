@@ -0,0 +1,149 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::utils::{Span, Token};
+
+/// The static type of a Glacier value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    Char,
+    String,
+    Unit,
+    /// An array whose elements all have the given type.
+    Array(Box<Type>),
+    /// A struct declared in the program, identified by its name.
+    Struct(String),
+    /// A class declared in the program. Its instances are shared by reference.
+    Class(String),
+    /// An enum declared in the program.
+    Enum(String),
+    /// The type of an expression that already failed to check. It is compatible
+    /// with every other type so a single mistake is only reported once.
+    Error,
+}
+
+impl Type {
+    /// Looks up one of the built-in types by the name used in annotations.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "int" => Some(Type::Int),
+            "float" => Some(Type::Float),
+            "bool" => Some(Type::Bool),
+            "char" => Some(Type::Char),
+            "string" => Some(Type::String),
+            _ => None,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Float | Type::Error)
+    }
+
+    /// Returns true if a value of type `other` can be used where `self` is expected.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Array(expected), Type::Array(found)) => expected.accepts(found),
+            _ => self == other || *self == Type::Error || *other == Type::Error,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::String => write!(f, "string"),
+            Type::Unit => write!(f, "()"),
+            Type::Array(element) => write!(f, "[{}]", element),
+            Type::Struct(name) | Type::Class(name) | Type::Enum(name) => write!(f, "{}", name),
+            Type::Error => write!(f, "{{unknown}}"),
+        }
+    }
+}
+
+/// The parameter and return types of a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Vec<Type>,
+    pub return_ty: Type,
+}
+
+/// The fields and method signatures of a class. Method signatures do not include `self`.
+#[derive(Debug, Clone, Default)]
+pub struct ClassInfo {
+    pub fields: Vec<(String, Type)>,
+    pub methods: HashMap<String, Signature>,
+}
+
+/// Types computed by the type checker for the declarations in a program.
+#[derive(Debug, Default)]
+pub struct TypeInfo {
+    bindings: HashMap<Span, Type>,
+    signatures: HashMap<String, Signature>,
+    /// The fields of every struct, in declaration order.
+    structs: HashMap<String, Vec<(String, Type)>>,
+    classes: HashMap<String, ClassInfo>,
+    /// The variants of every enum with their payload types, in declaration order.
+    enums: HashMap<String, Vec<(String, Vec<Type>)>>,
+}
+
+impl TypeInfo {
+    /// Returns the type of the variable or parameter declared by `name`.
+    pub fn binding(&self, name: &Token) -> &Type {
+        self.bindings.get(&name.span).unwrap_or(&Type::Error)
+    }
+
+    pub fn binding_by_span(&self, span: Span) -> &Type {
+        self.bindings.get(&span).unwrap_or(&Type::Error)
+    }
+
+    pub fn set_binding(&mut self, name: &Token, ty: Type) {
+        self.bindings.insert(name.span, ty);
+    }
+
+    pub fn signature(&self, name: &str) -> Option<&Signature> {
+        self.signatures.get(name)
+    }
+
+    pub fn set_signature(&mut self, name: &str, signature: Signature) {
+        self.signatures.insert(name.to_string(), signature);
+    }
+
+    pub fn struct_fields(&self, name: &str) -> Option<&[(String, Type)]> {
+        self.structs.get(name).map(Vec::as_slice)
+    }
+
+    pub fn set_struct_fields(&mut self, name: &str, fields: Vec<(String, Type)>) {
+        self.structs.insert(name.to_string(), fields);
+    }
+
+    pub fn class(&self, name: &str) -> Option<&ClassInfo> {
+        self.classes.get(name)
+    }
+
+    pub fn set_class(&mut self, name: &str, class: ClassInfo) {
+        self.classes.insert(name.to_string(), class);
+    }
+
+    pub fn enum_variants(&self, name: &str) -> Option<&[(String, Vec<Type>)]> {
+        self.enums.get(name).map(Vec::as_slice)
+    }
+
+    pub fn set_enum_variants(&mut self, name: &str, variants: Vec<(String, Vec<Type>)>) {
+        self.enums.insert(name.to_string(), variants);
+    }
+
+    /// Returns the fields of a struct or class type.
+    pub fn fields(&self, ty: &Type) -> Option<&[(String, Type)]> {
+        match ty {
+            Type::Struct(name) => self.struct_fields(name),
+            Type::Class(name) => self.class(name).map(|class| class.fields.as_slice()),
+            _ => None,
+        }
+    }
+}
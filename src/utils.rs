@@ -4,7 +4,7 @@ use crate::compiler::Compiler;
 
 pub type FileId = usize;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Default)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Default)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -20,6 +20,12 @@ impl<'a> Span {
         }
     }
 
+    /// Returns a span covering everything from the start of `self` to the end of `other`.
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start, other.end, self.file_id)
+    }
+
+    #[allow(dead_code)]
     pub fn get_filename(&self, compiler: &'a Compiler) -> &'a Path {
         compiler.get_module_filepath(self.file_id)
     }
@@ -41,6 +47,7 @@ pub enum TokenType {
     RBoxed,
     Dot,
     DoubleDot,
+    DoubleDotEq,
     LT,
     GT,
     Eq,
@@ -55,8 +62,16 @@ pub enum TokenType {
     Minus,
     Asterisk,
     Slash,
+    DoubleAmpersand,
+    DoublePipe,
+    PlusEq,
+    MinusEq,
+    AsteriskEq,
+    SlashEq,
     Colon,
+    DoubleColon,
     Comma,
+    Hash,
 
     KVariable,
     KMutable,
@@ -65,11 +80,24 @@ pub enum TokenType {
     KFunction,
     KStruct,
     KClass,
+    KIf,
+    KElse,
+    KWhile,
+    KFor,
+    KIn,
+    KBreak,
+    KContinue,
+    KEnum,
+    KMatch,
+    KImport,
+    KUse,
+    KPub,
 
     Integer(String),
     Float(String),
     Char(String),
     String(String),
+    Bool(bool),
 
     Identifier(String),
 
@@ -94,6 +122,7 @@ impl Display for TokenType {
                 TokenType::RBoxed => "]",
                 TokenType::Dot => ".",
                 TokenType::DoubleDot => "..",
+                TokenType::DoubleDotEq => "..=",
                 TokenType::LT => "<",
                 TokenType::GT => ">",
                 TokenType::Eq => "=",
@@ -107,21 +136,43 @@ impl Display for TokenType {
                 TokenType::Minus => "-",
                 TokenType::Asterisk => "*",
                 TokenType::Slash => "/",
+                TokenType::DoubleAmpersand => "&&",
+                TokenType::DoublePipe => "||",
+                TokenType::PlusEq => "+=",
+                TokenType::MinusEq => "-=",
+                TokenType::AsteriskEq => "*=",
+                TokenType::SlashEq => "/=",
                 TokenType::Colon => ":",
+                TokenType::DoubleColon => "::",
                 TokenType::Comma => ",",
+                TokenType::Hash => "#",
                 TokenType::KVariable => "var",
                 TokenType::KMutable => "mut",
                 TokenType::KConstant => "const",
                 TokenType::KFunction => "func",
                 TokenType::KStruct => "struct",
                 TokenType::KClass => "class",
+                TokenType::KIf => "if",
+                TokenType::KElse => "else",
+                TokenType::KWhile => "while",
+                TokenType::KFor => "for",
+                TokenType::KIn => "in",
+                TokenType::KBreak => "break",
+                TokenType::KContinue => "continue",
+                TokenType::KEnum => "enum",
+                TokenType::KMatch => "match",
+                TokenType::KImport => "import",
+                TokenType::KUse => "use",
+                TokenType::KPub => "pub",
                 TokenType::Integer(int) => int,
                 TokenType::Float(float) => float,
                 TokenType::Char(ch) => ch,
                 TokenType::String(string) => string,
+                TokenType::Bool(true) => "true",
+                TokenType::Bool(false) => "false",
                 TokenType::Semicolon => ";",
                 TokenType::Eof => "<EOF>",
-                TokenType::RightArrow => "=>",
+                TokenType::RightArrow => "->",
                 TokenType::KReturn => "return",
                 TokenType::Identifier(ident) => ident,
                 TokenType::Unknown => "<UNKNOWN>",
@@ -156,6 +207,14 @@ impl Token {
     pub fn is_eof(&self) -> bool {
         self.ty == TokenType::Eof
     }
+
+    /// Returns the name of an identifier token, or an empty string for any other token.
+    pub fn as_ident(&self) -> &str {
+        match &self.ty {
+            TokenType::Identifier(ident) => ident,
+            _ => "",
+        }
+    }
 }
 
 impl Display for Token {
@@ -169,30 +228,3 @@ impl PartialEq<TokenType> for Token {
         self.ty == *other
     }
 }
-
-/* #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Symbol {
-    Function {
-        name: String,
-        params: Vec<Parameter>,
-        return_ty: Type,
-        span: Span,
-    },
-}
-
-#[derive(Debug)]
-pub struct Project {
-    symbols: Vec<Symbol>,
-}
-
-impl Project {
-    pub fn new() -> Self {
-        Self {
-            symbols: Vec::new(),
-        }
-    }
-
-    pub fn find_func(&self, name: String) -> Option<&Symbol> {
-        self.symbols.iter().find()
-    }
-} */
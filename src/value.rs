@@ -0,0 +1,329 @@
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use crate::{
+    ast::BinOp,
+    diag,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    types::{Type, TypeInfo},
+    utils::{Span, Token, TokenType},
+};
+
+/// A runtime value produced while evaluating a Glacier program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+    Unit,
+    /// An array. Arrays are values like structs: the elements are shared between
+    /// copies and only cloned when a copy is written to.
+    Array(Rc<Vec<Value>>),
+    /// A struct value. Fields are kept in declaration order and copied along with the struct.
+    Struct {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    /// A variant of an enum, with the values of its payload.
+    Enum {
+        name: String,
+        variant: String,
+        fields: Vec<Value>,
+    },
+    /// An instance of a class. Copies of the value share the same instance,
+    /// which holds a `Value::Struct` with the class name and its fields.
+    Object(Rc<RefCell<Value>>),
+}
+
+impl Value {
+    /// The value a variable of type `ty` holds when it is declared without an initializer.
+    pub fn default_for(ty: &Type, types: &TypeInfo) -> Self {
+        match ty {
+            Type::Int => Value::Int(0),
+            Type::Float => Value::Float(0.0),
+            Type::Bool => Value::Bool(false),
+            Type::Char => Value::Char('\0'),
+            Type::String => Value::String(String::new()),
+            Type::Array(_) => Value::Array(Rc::default()),
+            Type::Struct(name) => Value::Struct {
+                name: name.clone(),
+                fields: types
+                    .struct_fields(name)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(field, ty)| (field.clone(), Value::default_for(ty, types)))
+                    .collect(),
+            },
+            // An enum starts out as its first variant.
+            Type::Enum(name) => match types.enum_variants(name).unwrap_or_default().first() {
+                Some((variant, fields)) => Value::Enum {
+                    name: name.clone(),
+                    variant: variant.clone(),
+                    fields: fields
+                        .iter()
+                        .map(|ty| Value::default_for(ty, types))
+                        .collect(),
+                },
+                None => Value::Unit,
+            },
+            // Class instances have no default; fields of class type hold `()` until assigned.
+            Type::Class(_) | Type::Unit | Type::Error => Value::Unit,
+        }
+    }
+
+    /// Creates a class instance whose fields hold their default values.
+    pub fn new_object(class: &str, types: &TypeInfo) -> Self {
+        Value::Object(Rc::new(RefCell::new(Value::new_instance(class, types))))
+    }
+
+    /// Creates the contents of a class instance: a `Value::Struct` with the
+    /// class name and the default values of its fields.
+    pub fn new_instance(class: &str, types: &TypeInfo) -> Self {
+        let fields = types
+            .class(class)
+            .map(|class| class.fields.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|(field, ty)| (field.clone(), Value::default_for(ty, types)))
+            .collect();
+        Value::Struct {
+            name: class.to_string(),
+            fields,
+        }
+    }
+
+    /// Returns the value of a literal token, or `()` for any other token.
+    pub fn from_literal(literal: &Token) -> Self {
+        match &literal.ty {
+            TokenType::Integer(int) => Value::Int(int.parse().unwrap_or_default()),
+            TokenType::Float(float) => Value::Float(float.parse().unwrap_or(f64::NAN)),
+            TokenType::Char(ch) => Value::Char(ch.chars().next().unwrap_or_default()),
+            TokenType::String(string) => Value::String(string.clone()),
+            TokenType::Bool(bool) => Value::Bool(*bool),
+            _ => Value::Unit,
+        }
+    }
+
+    /// Returns the literal token that produces this value, if it is a scalar.
+    /// Floats are written so that they parse back to the same value.
+    pub fn to_literal(&self, span: Span) -> Option<Token> {
+        let ty = match self {
+            Value::Int(int) => TokenType::Integer(int.to_string()),
+            Value::Float(float) => TokenType::Float(format!("{:?}", float)),
+            Value::Bool(bool) => TokenType::Bool(*bool),
+            Value::Char(ch) => TokenType::Char(ch.to_string()),
+            Value::String(string) => TokenType::String(string.clone()),
+            _ => return None,
+        };
+        Some(Token::new(ty, span))
+    }
+
+    pub fn type_name(&self) -> String {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Char(_) => "char",
+            Value::String(_) => "string",
+            Value::Unit => "()",
+            Value::Array(_) => "array",
+            Value::Struct { name, .. } | Value::Enum { name, .. } => name,
+            Value::Object(object) => return object.borrow().type_name(),
+        }
+        .to_string()
+    }
+
+    pub fn field(&self, name: &str) -> Option<Value> {
+        match self {
+            Value::Struct { fields, .. } => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone()),
+            Value::Object(object) => object.borrow().field(name),
+            _ => None,
+        }
+    }
+
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Value> {
+        match self {
+            Value::Struct { fields, .. } => fields
+                .iter_mut()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(int) => write!(f, "{}", int),
+            Value::Float(float) => write!(f, "{}", format_float(*float)),
+            Value::Bool(bool) => write!(f, "{}", bool),
+            Value::Char(ch) => write!(f, "{}", ch),
+            Value::String(string) => write!(f, "{}", string),
+            Value::Unit => write!(f, "()"),
+            Value::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Value::Struct { name, fields } => {
+                write!(f, "{} {{", name)?;
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}: {}", separator, field, value)?;
+                }
+                write!(f, "{}}}", if fields.is_empty() { "" } else { " " })
+            }
+            Value::Enum {
+                name,
+                variant,
+                fields,
+            } => {
+                write!(f, "{}::{}", name, variant)?;
+                if !fields.is_empty() {
+                    let fields: Vec<String> = fields.iter().map(|e| e.to_string()).collect();
+                    write!(f, "({})", fields.join(", "))?;
+                }
+                Ok(())
+            }
+            Value::Object(object) => write!(f, "{}", object.borrow()),
+        }
+    }
+}
+
+/// Formats a float the way every Glacier backend prints it: the shortest
+/// digits that round-trip, in plain notation for exponents in `-5..17` and
+/// in scientific notation (`1.5e20`) otherwise. Whole numbers keep a `.0`.
+pub fn format_float(float: f64) -> String {
+    if float.is_nan() {
+        return String::from("NaN");
+    } else if float.is_infinite() {
+        return String::from(if float > 0.0 { "inf" } else { "-inf" });
+    }
+
+    // `{:e}` yields the shortest round-tripping digits, e.g. `-1.25e-3`.
+    let sci = format!("{:e}", float);
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    let sign = if mantissa.starts_with('-') { "-" } else { "" };
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+
+    if (-5..17).contains(&exp) {
+        if exp < 0 {
+            format!("{}0.{}{}", sign, "0".repeat((-exp - 1) as usize), digits)
+        } else {
+            let int_len = exp as usize + 1;
+            if digits.len() > int_len {
+                format!("{}{}.{}", sign, &digits[..int_len], &digits[int_len..])
+            } else {
+                format!("{}{}{}.0", sign, digits, "0".repeat(int_len - digits.len()))
+            }
+        }
+    } else if digits.len() > 1 {
+        format!("{}{}.{}e{}", sign, &digits[..1], &digits[1..], exp)
+    } else {
+        format!("{}{}e{}", sign, digits, exp)
+    }
+}
+
+/// Applies the binary operator `op`, which every backend evaluates the same way:
+/// integers wrap on overflow and dividing an integer by zero is an error.
+pub fn binary_op(op: &BinOp, lhs: Value, rhs: Value, span: Span) -> Result<Value, Diagnostic> {
+    let value = match (&lhs, &rhs) {
+        (Value::Int(a), Value::Int(b)) => match op {
+            BinOp::Add => Value::Int(a.wrapping_add(*b)),
+            BinOp::Subtract => Value::Int(a.wrapping_sub(*b)),
+            BinOp::Multiply => Value::Int(a.wrapping_mul(*b)),
+            BinOp::Divide if *b == 0 => {
+                return Err(diag!(
+                    "Attempt to divide by zero.",
+                    "The divisor of this expression is zero.",
+                    span
+                )
+                .with_code(ErrorCode::DivisionByZero))
+            }
+            BinOp::Divide => Value::Int(a.wrapping_div(*b)),
+            _ => Value::Bool(compare(op, a, b)),
+        },
+        (Value::Float(a), Value::Float(b)) => match op {
+            BinOp::Add => Value::Float(a + b),
+            BinOp::Subtract => Value::Float(a - b),
+            BinOp::Multiply => Value::Float(a * b),
+            BinOp::Divide => Value::Float(a / b),
+            _ => Value::Bool(compare(op, a, b)),
+        },
+        (Value::String(a), Value::String(b)) if *op == BinOp::Add => {
+            Value::String(format!("{}{}", a, b))
+        }
+        (Value::String(a), Value::String(b)) if is_comparison(op) => Value::Bool(compare(op, a, b)),
+        (Value::Char(a), Value::Char(b)) if is_comparison(op) => Value::Bool(compare(op, a, b)),
+        (Value::Bool(a), Value::Bool(b)) if matches!(op, BinOp::Eq | BinOp::NotEq) => {
+            Value::Bool(compare(op, a, b))
+        }
+        _ => {
+            return Err(diag!(
+                format!(
+                    "Cannot apply binary operator `{}` to types `{}` and `{}`.",
+                    op,
+                    lhs.type_name(),
+                    rhs.type_name()
+                ),
+                span
+            )
+            .with_code(ErrorCode::InvalidOperands))
+        }
+    };
+
+    Ok(value)
+}
+
+/// Converts `index` into a position in `elements`, if it is in bounds.
+pub fn element_index(elements: &[Value], index: i64) -> Option<usize> {
+    usize::try_from(index).ok().filter(|&i| i < elements.len())
+}
+
+pub fn out_of_bounds(elements: &[Value], index: i64, span: Span) -> Diagnostic {
+    diag!("Index out of bounds.", span)
+        .with_code(ErrorCode::IndexOutOfBounds)
+        .with_secondary_msg(format!(
+            "The length is {} but the index is {}.",
+            elements.len(),
+            index
+        ))
+}
+
+/// How deeply calls can nest before the program is stopped, so that unbounded
+/// recursion is reported instead of exhausting the stack.
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
+pub fn stack_overflow(span: Span) -> Diagnostic {
+    diag!("Stack overflow.", span)
+        .with_code(ErrorCode::StackOverflow)
+        .with_secondary_msg(format!(
+            "This call is nested more than {} calls deep.",
+            MAX_CALL_DEPTH
+        ))
+}
+
+fn is_comparison(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Eq | BinOp::NotEq | BinOp::GTOrEq | BinOp::LTOrEq | BinOp::GT | BinOp::LT
+    )
+}
+
+fn compare<T: PartialOrd>(op: &BinOp, a: T, b: T) -> bool {
+    match op {
+        BinOp::Eq => a == b,
+        BinOp::NotEq => a != b,
+        BinOp::GTOrEq => a >= b,
+        BinOp::LTOrEq => a <= b,
+        BinOp::GT => a > b,
+        BinOp::LT => a < b,
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,510 @@
+use crate::{
+    diag,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    ir::{BlockId, Function, InstKind, Module, Terminator, UnaryOp, ValueId},
+    types::Type,
+    utils::Span,
+};
+
+/// Where a value is defined: its block and its position in the block. Block
+/// parameters are at position 0 and the instruction `i` at position `i + 1`.
+type Def = (BlockId, usize);
+
+/// Checks that a module is well formed: every block ends with a terminator,
+/// jumps pass the right arguments to existing blocks, every value is defined
+/// once before it is used, and the operands of instructions have matching types.
+pub struct Verifier<'a> {
+    module: &'a Module,
+    errors: Vec<Diagnostic>,
+}
+
+impl<'a> Verifier<'a> {
+    pub fn new(module: &'a Module) -> Self {
+        Self {
+            module,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn verify(mut self) -> Vec<Diagnostic> {
+        for function in &self.module.functions {
+            FunctionVerifier::new(self.module, function, &mut self.errors).verify();
+        }
+        self.errors
+    }
+}
+
+struct FunctionVerifier<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    errors: &'a mut Vec<Diagnostic>,
+    defs: Vec<Option<Def>>,
+    /// The immediate dominator of every block reachable from the entry block.
+    idoms: Vec<Option<BlockId>>,
+}
+
+impl<'a> FunctionVerifier<'a> {
+    fn new(module: &'a Module, function: &'a Function, errors: &'a mut Vec<Diagnostic>) -> Self {
+        Self {
+            module,
+            function,
+            errors,
+            defs: vec![None; function.values.len()],
+            idoms: Vec::new(),
+        }
+    }
+
+    fn verify(mut self) {
+        let function = self.function;
+        for (i, block) in function.blocks.iter().enumerate() {
+            if block.terminator.is_none() {
+                self.error(
+                    format!("{} has no terminator.", BlockId(i as u32)),
+                    function.span,
+                );
+                return;
+            }
+        }
+        for block in &function.blocks {
+            if let Some((terminator, span)) = &block.terminator {
+                for target in terminator.targets() {
+                    if target.block.0 as usize >= function.blocks.len() {
+                        self.error(format!("{} does not exist.", target.block), *span);
+                        return;
+                    }
+                }
+            }
+        }
+
+        if !self.collect_defs() {
+            return;
+        }
+        self.compute_dominators();
+
+        for (i, block) in function.blocks.iter().enumerate() {
+            let id = BlockId(i as u32);
+            for (position, inst) in block.insts.iter().enumerate() {
+                for operand in inst.kind.operands() {
+                    self.check_use(operand, (id, position + 1), inst.span);
+                }
+                self.check_inst(&inst.kind, inst.result, inst.span);
+            }
+            let (terminator, span) = block.terminator.as_ref().unwrap();
+            for operand in terminator.operands() {
+                self.check_use(operand, (id, block.insts.len() + 1), *span);
+            }
+            self.check_terminator(terminator, *span);
+        }
+    }
+
+    /// Records where every value is defined, reporting values defined more than once.
+    fn collect_defs(&mut self) -> bool {
+        let function = self.function;
+        let mut ok = true;
+        for (i, block) in function.blocks.iter().enumerate() {
+            let id = BlockId(i as u32);
+            let params = block.params.iter().map(|param| (*param, 0, function.span));
+            let results = block
+                .insts
+                .iter()
+                .enumerate()
+                .filter_map(|(position, inst)| {
+                    inst.result.map(|result| (result, position + 1, inst.span))
+                });
+            for (value, position, span) in params.chain(results) {
+                match self.defs.get(value.0 as usize) {
+                    None => {
+                        self.error(format!("{} has no type.", value), span);
+                        ok = false;
+                    }
+                    Some(Some(_)) => {
+                        self.error(format!("{} is defined more than once.", value), span);
+                        ok = false;
+                    }
+                    Some(None) => self.defs[value.0 as usize] = Some((id, position)),
+                }
+            }
+        }
+        ok
+    }
+
+    /// Computes the immediate dominators with the iterative algorithm of
+    /// Cooper, Harvey and Kennedy, "A Simple, Fast Dominance Algorithm".
+    fn compute_dominators(&mut self) {
+        let order = self.function.reverse_postorder();
+        let mut rank = vec![usize::MAX; self.function.blocks.len()];
+        for (i, block) in order.iter().enumerate() {
+            rank[block.0 as usize] = i;
+        }
+        let predecessors = self.function.predecessors();
+        let mut idoms = vec![None; self.function.blocks.len()];
+        idoms[0] = Some(BlockId(0));
+
+        let intersect = |idoms: &[Option<BlockId>], mut a: BlockId, mut b: BlockId| {
+            while a != b {
+                while rank[a.0 as usize] > rank[b.0 as usize] {
+                    a = idoms[a.0 as usize].unwrap();
+                }
+                while rank[b.0 as usize] > rank[a.0 as usize] {
+                    b = idoms[b.0 as usize].unwrap();
+                }
+            }
+            a
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in order.iter().skip(1) {
+                let mut new_idom = None;
+                for &predecessor in &predecessors[block.0 as usize] {
+                    if idoms[predecessor.0 as usize].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => predecessor,
+                        Some(idom) => intersect(&idoms, predecessor, idom),
+                    });
+                }
+                if new_idom != idoms[block.0 as usize] {
+                    idoms[block.0 as usize] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        self.idoms = idoms;
+    }
+
+    /// Returns true if `a` dominates `b`. Unreachable blocks are dominated by every block.
+    fn dominates(&self, a: BlockId, mut b: BlockId) -> bool {
+        if self.idoms[b.0 as usize].is_none() {
+            return true;
+        }
+        loop {
+            if a == b {
+                return true;
+            }
+            let idom = self.idoms[b.0 as usize].unwrap();
+            if idom == b {
+                return false;
+            }
+            b = idom;
+        }
+    }
+
+    /// Checks that `value` is defined before it is used at `position`.
+    fn check_use(&mut self, value: ValueId, (block, position): Def, span: Span) {
+        let Some(Some((def_block, def_position))) = self.defs.get(value.0 as usize).copied() else {
+            self.error(format!("{} is used but never defined.", value), span);
+            return;
+        };
+        let defined_before = match def_block == block {
+            true => def_position < position,
+            false => self.dominates(def_block, block),
+        };
+        if !defined_before {
+            self.error(
+                format!("{} is used before it is defined in {}.", value, def_block),
+                span,
+            );
+        }
+    }
+
+    fn check_inst(&mut self, kind: &InstKind, result: Option<ValueId>, span: Span) {
+        let ty = |value: ValueId| self.ty(value);
+        let result_ty = result.map(ty);
+        match kind {
+            InstKind::Binary(op, lhs, rhs) => {
+                let (lhs_ty, rhs_ty) = (ty(*lhs), ty(*rhs));
+                if !lhs_ty.accepts(&rhs_ty) {
+                    self.error(
+                        format!(
+                            "the operands of `{}` have different types, {} and {}.",
+                            op, lhs_ty, rhs_ty
+                        ),
+                        span,
+                    );
+                }
+                let expected = match op.is_comparison() {
+                    true => Type::Bool,
+                    false => lhs_ty,
+                };
+                self.expect_result(op, result_ty, &expected, span);
+            }
+            InstKind::Unary(op, value) => {
+                let expected = ty(*value);
+                let valid = match op {
+                    UnaryOp::Neg => expected.is_numeric(),
+                    UnaryOp::Not => Type::Bool.accepts(&expected),
+                };
+                if !valid {
+                    self.error(format!("`{}` cannot be applied to {}.", op, expected), span);
+                }
+                self.expect_result(op, result_ty, &expected, span);
+            }
+            InstKind::Call(name, args) => match self.module.function(name) {
+                Some(callee) if callee.params().len() != args.len() => self.error(
+                    format!(
+                        "`{}` takes {} arguments but is called with {}.",
+                        name,
+                        callee.params().len(),
+                        args.len()
+                    ),
+                    span,
+                ),
+                Some(_) => {}
+                None => self.error(format!("`{}` is called but never defined.", name), span),
+            },
+            InstKind::IsVariant(..) => {
+                self.expect_result(&"is_variant", result_ty, &Type::Bool, span)
+            }
+            InstKind::Global(name) | InstKind::SetGlobal(name, _)
+                if !self.module.globals.iter().any(|(global, _)| global == name) =>
+            {
+                self.error(format!("@{} is not a global.", name), span)
+            }
+            _ => {}
+        }
+    }
+
+    fn expect_result(
+        &mut self,
+        op: &dyn std::fmt::Display,
+        result: Option<Type>,
+        expected: &Type,
+        span: Span,
+    ) {
+        match result {
+            Some(result) if !result.accepts(expected) => self.error(
+                format!(
+                    "`{}` produces {} but its result has type {}.",
+                    op, expected, result
+                ),
+                span,
+            ),
+            Some(_) => {}
+            None => self.error(format!("`{}` has no result.", op), span),
+        }
+    }
+
+    fn check_terminator(&mut self, terminator: &Terminator, span: Span) {
+        let function = self.function;
+        match terminator {
+            Terminator::Branch { condition, .. } => {
+                let ty = self.ty(*condition);
+                if !Type::Bool.accepts(&ty) {
+                    self.error(
+                        format!("the branch condition {} has type {}.", condition, ty),
+                        span,
+                    );
+                }
+            }
+            Terminator::Return(value) => {
+                let ty = match value {
+                    Some(value) => self.ty(*value),
+                    None => Type::Unit,
+                };
+                if !function.return_ty.accepts(&ty) {
+                    self.error(
+                        format!(
+                            "returns {} from a function returning {}.",
+                            ty, function.return_ty
+                        ),
+                        span,
+                    );
+                }
+            }
+            _ => {}
+        }
+
+        for target in terminator.targets() {
+            let params = &function.block(target.block).params;
+            if params.len() != target.args.len() {
+                self.error(
+                    format!(
+                        "{} takes {} arguments but the jump passes {}.",
+                        target.block,
+                        params.len(),
+                        target.args.len()
+                    ),
+                    span,
+                );
+                continue;
+            }
+            for (param, arg) in params.iter().zip(&target.args) {
+                let (param_ty, arg_ty) = (self.ty(*param), self.ty(*arg));
+                if !param_ty.accepts(&arg_ty) {
+                    self.error(
+                        format!(
+                            "{} of {} has type {} but is passed {}.",
+                            param, target.block, param_ty, arg_ty
+                        ),
+                        span,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns the type of `value`, or the error type if it is out of range,
+    /// which is reported where it is used.
+    fn ty(&self, value: ValueId) -> Type {
+        self.function
+            .values
+            .get(value.0 as usize)
+            .cloned()
+            .unwrap_or(Type::Error)
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.errors.push(
+            diag!(
+                format!("Invalid IR in `{}`: {}", self.function.name, message),
+                span
+            )
+            .with_code(ErrorCode::InternalCompilerError),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::{BinaryOp, Inst, Target},
+        value::Value,
+    };
+
+    /// Verifies a module made of `function` and returns the error messages.
+    fn verify(function: Function) -> Vec<String> {
+        let module = Module {
+            globals: Vec::new(),
+            functions: vec![function],
+        };
+        Verifier::new(&module)
+            .verify()
+            .into_iter()
+            .map(|error| error.primary_msg)
+            .collect()
+    }
+
+    fn inst(result: Option<ValueId>, kind: InstKind) -> Inst {
+        Inst {
+            result,
+            kind,
+            span: Span::default(),
+        }
+    }
+
+    fn terminate(function: &mut Function, block: BlockId, terminator: Terminator) {
+        function.block_mut(block).terminator = Some((terminator, Span::default()));
+    }
+
+    /// `f(x: int) -> int` that returns `x + 1`.
+    fn add_one() -> Function {
+        let mut function = Function::new("f", Type::Int, Span::default());
+        let entry = function.entry();
+        let x = function.add_param(entry, Type::Int);
+        let one = function.add_value(Type::Int);
+        let sum = function.add_value(Type::Int);
+        let insts = &mut function.block_mut(entry).insts;
+        insts.push(inst(Some(one), InstKind::Const(Value::Int(1))));
+        insts.push(inst(Some(sum), InstKind::Binary(BinaryOp::Add, x, one)));
+        terminate(&mut function, entry, Terminator::Return(Some(sum)));
+        function
+    }
+
+    #[test]
+    fn accepts_well_formed_function() {
+        assert_eq!(verify(add_one()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rejects_missing_terminator() {
+        let mut function = add_one();
+        function.block_mut(BlockId(0)).terminator = None;
+        assert_eq!(
+            verify(function),
+            ["Invalid IR in `f`: b0 has no terminator."]
+        );
+    }
+
+    #[test]
+    fn rejects_undefined_value() {
+        let mut function = add_one();
+        terminate(
+            &mut function,
+            BlockId(0),
+            Terminator::Return(Some(ValueId(7))),
+        );
+        assert_eq!(
+            verify(function),
+            ["Invalid IR in `f`: v7 is used but never defined."]
+        );
+    }
+
+    #[test]
+    fn rejects_use_before_definition() {
+        let mut function = add_one();
+        function.block_mut(BlockId(0)).insts.reverse();
+        assert_eq!(
+            verify(function),
+            ["Invalid IR in `f`: v1 is used before it is defined in b0."]
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_number_of_jump_arguments() {
+        let mut function = add_one();
+        let exit = function.add_block();
+        let result = function.add_param(exit, Type::Int);
+        terminate(&mut function, exit, Terminator::Return(Some(result)));
+        terminate(
+            &mut function,
+            BlockId(0),
+            Terminator::Jump(Target {
+                block: exit,
+                args: Vec::new(),
+            }),
+        );
+        assert_eq!(
+            verify(function),
+            ["Invalid IR in `f`: b1 takes 1 arguments but the jump passes 0."]
+        );
+    }
+
+    #[test]
+    fn rejects_operands_of_different_types() {
+        let mut function = add_one();
+        function.values[1] = Type::Float;
+        assert_eq!(
+            verify(function),
+            ["Invalid IR in `f`: the operands of `add` have different types, int and float."]
+        );
+    }
+
+    #[test]
+    fn rejects_non_bool_branch_condition() {
+        let mut function = add_one();
+        let exit = function.add_block();
+        terminate(&mut function, exit, Terminator::Return(Some(ValueId(2))));
+        let target = Target {
+            block: exit,
+            args: Vec::new(),
+        };
+        terminate(
+            &mut function,
+            BlockId(0),
+            Terminator::Branch {
+                condition: ValueId(0),
+                then: target.clone(),
+                otherwise: target,
+            },
+        );
+        assert_eq!(
+            verify(function),
+            ["Invalid IR in `f`: the branch condition v0 has type int."]
+        );
+    }
+}
@@ -0,0 +1,412 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    ast::BinOp,
+    bytecode::{Instruction, Program},
+    compiler::Compiler,
+    diag,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    error_codes::ErrorCode,
+    utils::Span,
+    value::{binary_op, element_index, out_of_bounds, stack_overflow, Value, MAX_CALL_DEPTH},
+};
+
+type VmResult<T> = Result<T, Diagnostic>;
+
+/// A call in progress: the function being run, the next instruction and the
+/// stack index of its first local slot.
+struct Frame {
+    function: usize,
+    ip: usize,
+    base: usize,
+}
+
+/// A stack-based virtual machine that runs a program compiled to bytecode.
+pub struct Vm<'a> {
+    compiler: &'a Compiler,
+    program: &'a Program,
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    globals: Vec<Value>,
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(compiler: &'a Compiler, program: &'a Program) -> Self {
+        Self {
+            compiler,
+            program,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: vec![Value::Unit; program.globals.len()],
+        }
+    }
+
+    pub fn run(mut self) {
+        let Some(main) = self.program.main else {
+            self.compiler.reporter.borrow_mut().add(
+                diag!(
+                    "No `main` function found.",
+                    "Add a `func main() { ... }` to run this program.",
+                    Span::default()
+                )
+                .with_code(ErrorCode::NoMain),
+            );
+            return;
+        };
+        // `main` is called without arguments, so parameters would read slots that were never set.
+        let arity = self.program.functions[main as usize].arity;
+        if arity != 0 {
+            self.compiler.reporter.borrow_mut().add(
+                diag!(
+                    format!(
+                        "Function `main` takes {} argument(s) but 0 were supplied.",
+                        arity
+                    ),
+                    Span::default()
+                )
+                .with_code(ErrorCode::ArgumentCount),
+            );
+            return;
+        }
+
+        let result = self
+            .execute(self.program.init)
+            .and_then(|_| self.execute(main));
+        if let Err(diagnostic) = result {
+            self.compiler.reporter.borrow_mut().add(diagnostic);
+        }
+    }
+
+    /// Calls `function` with no arguments and runs until it returns.
+    fn execute(&mut self, function: u32) -> VmResult<Value> {
+        let depth = self.frames.len();
+        self.push_frame(function as usize, 0);
+        let program = self.program;
+
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let function = &program.functions[frame.function];
+            let instruction = function.code[frame.ip];
+            let span = function.spans[frame.ip];
+            let base = frame.base;
+            frame.ip += 1;
+
+            match instruction {
+                Instruction::Constant(index) => {
+                    self.stack.push(program.constants[index as usize].clone());
+                }
+                Instruction::Unit => self.stack.push(Value::Unit),
+                Instruction::True => self.stack.push(Value::Bool(true)),
+                Instruction::False => self.stack.push(Value::Bool(false)),
+                Instruction::Pop => {
+                    self.pop();
+                }
+                Instruction::Dup => {
+                    let value = self.stack.last().unwrap().clone();
+                    self.stack.push(value);
+                }
+
+                Instruction::GetLocal(slot) => {
+                    let value = self.stack[base + slot as usize].clone();
+                    self.stack.push(value);
+                }
+                Instruction::SetLocal(slot) => {
+                    self.stack[base + slot as usize] = self.pop();
+                }
+                Instruction::TakeLocal(slot) => {
+                    let value =
+                        std::mem::replace(&mut self.stack[base + slot as usize], Value::Unit);
+                    self.stack.push(value);
+                }
+                Instruction::GetGlobal(slot) => {
+                    self.stack.push(self.globals[slot as usize].clone());
+                }
+                Instruction::SetGlobal(slot) => {
+                    self.globals[slot as usize] = self.pop();
+                }
+
+                Instruction::Add => self.binary(&BinOp::Add, span)?,
+                Instruction::Subtract => self.binary(&BinOp::Subtract, span)?,
+                Instruction::Multiply => self.binary(&BinOp::Multiply, span)?,
+                Instruction::Divide => self.binary(&BinOp::Divide, span)?,
+                Instruction::Equal => self.binary(&BinOp::Eq, span)?,
+                Instruction::NotEqual => self.binary(&BinOp::NotEq, span)?,
+                Instruction::Less => self.binary(&BinOp::LT, span)?,
+                Instruction::LessEqual => self.binary(&BinOp::LTOrEq, span)?,
+                Instruction::Greater => self.binary(&BinOp::GT, span)?,
+                Instruction::GreaterEqual => self.binary(&BinOp::GTOrEq, span)?,
+                Instruction::Negative => {
+                    let value = match self.pop() {
+                        Value::Int(int) => Value::Int(int.wrapping_neg()),
+                        Value::Float(float) => Value::Float(-float),
+                        value => return Err(unary_error("-", &value, span)),
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::Not => {
+                    let value = match self.pop() {
+                        Value::Bool(bool) => Value::Bool(!bool),
+                        value => return Err(unary_error("!", &value, span)),
+                    };
+                    self.stack.push(value);
+                }
+
+                Instruction::Jump(target) => self.frames.last_mut().unwrap().ip = target as usize,
+                Instruction::JumpIfFalse(target) => match self.pop() {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => self.frames.last_mut().unwrap().ip = target as usize,
+                    value => {
+                        return Err(diag!(
+                            format!(
+                                "Expected a `bool` condition, found `{}`.",
+                                value.type_name()
+                            ),
+                            span
+                        )
+                        .with_code(ErrorCode::MismatchedTypes))
+                    }
+                },
+
+                Instruction::Call { function, args } => {
+                    self.check_depth(span)?;
+                    self.push_frame(function as usize, args as usize);
+                }
+                Instruction::Invoke { method, args } => {
+                    let receiver = &self.stack[self.stack.len() - args as usize - 1];
+                    let name = string_constant(program, method);
+                    let function = match receiver {
+                        Value::Object(instance) => program
+                            .methods
+                            .get(&instance.borrow().type_name())
+                            .and_then(|methods| methods.get(name)),
+                        _ => None,
+                    };
+                    let Some(&function) = function else {
+                        return Err(diag!(
+                            format!("No method `{}` on type `{}`.", name, receiver.type_name()),
+                            span
+                        )
+                        .with_code(ErrorCode::UnknownMethod));
+                    };
+                    self.check_depth(span)?;
+                    self.push_frame(function as usize, args as usize + 1);
+                }
+                Instruction::CallBuiltin { builtin, args } => {
+                    let args = self.stack.split_off(self.stack.len() - args as usize);
+                    self.stack.push(builtin.call(&args));
+                }
+                Instruction::Return => {
+                    let value = self.pop();
+                    self.stack.truncate(base);
+                    self.frames.pop();
+                    if self.frames.len() == depth {
+                        return Ok(value);
+                    }
+                    self.stack.push(value);
+                }
+
+                Instruction::Array(len) => {
+                    let elements = self.stack.split_off(self.stack.len() - len as usize);
+                    self.stack.push(Value::Array(Rc::new(elements)));
+                }
+                Instruction::Index => {
+                    let index = self.pop_index(span)?;
+                    let array = self.pop();
+                    let Value::Array(elements) = &array else {
+                        return Err(not_indexable(&array, span));
+                    };
+                    match element_index(elements, index) {
+                        Some(i) => self.stack.push(elements[i].clone()),
+                        None => return Err(out_of_bounds(elements, index, span)),
+                    }
+                }
+                Instruction::TakeIndex => {
+                    let index = self.pop_index(span)?;
+                    let element = self.element_mut(index, span)?;
+                    let value = std::mem::replace(element, Value::Unit);
+                    self.stack.push(value);
+                }
+                Instruction::SetIndex => {
+                    let index = self.pop_index(span)?;
+                    let value = self.pop();
+                    *self.element_mut(index, span)? = value;
+                }
+
+                Instruction::Struct(index) => {
+                    let mut value = program.constants[index as usize].clone();
+                    if let Value::Struct { fields, .. } = &mut value {
+                        let values = self.stack.split_off(self.stack.len() - fields.len());
+                        for ((_, field), value) in fields.iter_mut().zip(values) {
+                            *field = value;
+                        }
+                    }
+                    self.stack.push(value);
+                }
+                Instruction::GetField(name) => {
+                    let name = string_constant(program, name);
+                    let object = self.pop();
+                    match object.field(name) {
+                        Some(value) => self.stack.push(value),
+                        None => return Err(no_field(name, &object, span)),
+                    }
+                }
+                Instruction::TakeField(name) => {
+                    let name = string_constant(program, name);
+                    let value = self
+                        .update_field(name, span, |field| std::mem::replace(field, Value::Unit))?;
+                    self.stack.push(value);
+                }
+                Instruction::SetField(name) => {
+                    let name = string_constant(program, name);
+                    let value = self.pop();
+                    self.update_field(name, span, |field| *field = value)?;
+                }
+                Instruction::New(index) => {
+                    let instance = program.constants[index as usize].clone();
+                    self.stack
+                        .push(Value::Object(Rc::new(RefCell::new(instance))));
+                }
+
+                Instruction::Variant(index) => {
+                    let mut value = program.constants[index as usize].clone();
+                    if let Value::Enum { fields, .. } = &mut value {
+                        *fields = self.stack.split_off(self.stack.len() - fields.len());
+                    }
+                    self.stack.push(value);
+                }
+                Instruction::IsVariant(name) => {
+                    let name = string_constant(program, name);
+                    let is_variant =
+                        matches!(self.pop(), Value::Enum { variant, .. } if variant == name);
+                    self.stack.push(Value::Bool(is_variant));
+                }
+                Instruction::VariantField(field) => {
+                    let Value::Enum { mut fields, .. } = self.pop() else {
+                        unreachable!()
+                    };
+                    self.stack.push(fields.swap_remove(field as usize));
+                }
+                Instruction::NoMatch => {
+                    let value = self.pop();
+                    return Err(diag!(
+                        format!("No arm of this `match` matched the value `{}`.", value),
+                        span
+                    )
+                    .with_code(ErrorCode::NonExhaustiveMatch));
+                }
+            }
+        }
+    }
+
+    /// Stops the program when a call at `span` would nest too deeply.
+    fn check_depth(&self, span: Span) -> VmResult<()> {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(stack_overflow(span));
+        }
+        Ok(())
+    }
+
+    /// Starts a call to `function`, whose `args` arguments are on top of the stack.
+    fn push_frame(&mut self, function: usize, args: usize) {
+        let base = self.stack.len() - args;
+        let locals = self.program.functions[function].locals as usize;
+        self.stack.resize(base + locals.max(args), Value::Unit);
+        self.frames.push(Frame {
+            function,
+            ip: 0,
+            base,
+        });
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap()
+    }
+
+    fn pop_index(&mut self, span: Span) -> VmResult<i64> {
+        match self.pop() {
+            Value::Int(int) => Ok(int),
+            value => Err(diag!(
+                format!("Expected an `int`, found `{}`.", value.type_name()),
+                span
+            )
+            .with_code(ErrorCode::MismatchedTypes)),
+        }
+    }
+
+    fn binary(&mut self, op: &BinOp, span: Span) -> VmResult<()> {
+        let rhs = self.pop();
+        let lhs = self.pop();
+        self.stack.push(binary_op(op, lhs, rhs, span)?);
+        Ok(())
+    }
+
+    /// Returns the element at `index` of the array on top of the stack, copying
+    /// the elements first if they are shared with another array.
+    fn element_mut(&mut self, index: i64, span: Span) -> VmResult<&mut Value> {
+        let array = self.stack.last_mut().unwrap();
+        let Value::Array(elements) = array else {
+            return Err(not_indexable(array, span));
+        };
+        match element_index(elements, index) {
+            Some(i) => Ok(&mut Rc::make_mut(elements)[i]),
+            None => Err(out_of_bounds(elements, index, span)),
+        }
+    }
+
+    /// Runs `update` on the field `name` of the struct or object on top of the stack.
+    fn update_field<T>(
+        &mut self,
+        name: &str,
+        span: Span,
+        update: impl FnOnce(&mut Value) -> T,
+    ) -> VmResult<T> {
+        let object = self.stack.last_mut().unwrap();
+        // Objects are shared, so their fields are written through the reference.
+        if let Value::Object(instance) = object {
+            let mut instance = instance.borrow_mut();
+            return match instance.field_mut(name) {
+                Some(field) => Ok(update(field)),
+                None => Err(no_field(name, &instance, span)),
+            };
+        }
+        match object.field_mut(name) {
+            Some(field) => Ok(update(field)),
+            None => Err(no_field(name, object, span)),
+        }
+    }
+}
+
+fn string_constant(program: &Program, index: u32) -> &str {
+    match &program.constants[index as usize] {
+        Value::String(string) => string,
+        _ => unreachable!(),
+    }
+}
+
+fn unary_error(op: &str, value: &Value, span: Span) -> Diagnostic {
+    diag!(
+        format!(
+            "Cannot apply unary operator `{}` to type `{}`.",
+            op,
+            value.type_name()
+        ),
+        span
+    )
+    .with_code(ErrorCode::InvalidOperands)
+}
+
+fn not_indexable(value: &Value, span: Span) -> Diagnostic {
+    diag!(
+        format!("Cannot index into a value of type `{}`.", value.type_name()),
+        span
+    )
+    .with_code(ErrorCode::NotIndexable)
+}
+
+fn no_field(name: &str, object: &Value, span: Span) -> Diagnostic {
+    diag!(
+        format!("No field `{}` on type `{}`.", name, object.type_name()),
+        span
+    )
+    .with_code(ErrorCode::UnknownField)
+}